@@ -0,0 +1,139 @@
+//! Compares the indexed database with the linear scans it replaced,
+//! on a synthetic library of 50k tracks.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ytpapi2::YoutubeMusicVideoRef;
+
+#[allow(dead_code)]
+#[path = "../src/database/fixtures.rs"]
+mod fixtures;
+// Benchmarks are built with `cfg(test)` but without the tests
+#[allow(dead_code, unused_imports)]
+#[path = "../src/database/index.rs"]
+mod index;
+#[allow(dead_code)]
+#[path = "../src/database/rating.rs"]
+mod rating;
+#[allow(dead_code)]
+#[path = "../src/database/stats.rs"]
+mod stats;
+
+use index::Database;
+
+const LIBRARY_SIZE: usize = 50_000;
+const PLAYLIST_SIZE: usize = 500;
+
+fn video(i: usize) -> YoutubeMusicVideoRef {
+    fixtures::library_video(i, 2_000, 10_000)
+}
+
+fn library() -> Vec<YoutubeMusicVideoRef> {
+    (0..LIBRARY_SIZE).map(video).collect()
+}
+
+/// Half of the playlist is downloaded, the other half isn't
+fn playlist() -> Vec<YoutubeMusicVideoRef> {
+    (0..PLAYLIST_SIZE)
+        .map(|i| video(i * 97 % LIBRARY_SIZE + (i % 2) * LIBRARY_SIZE))
+        .collect()
+}
+
+/// What `format_playlist` does for every playlist of the chooser
+fn downloaded_in_playlist(c: &mut Criterion) {
+    let videos = library();
+    let database = Database::from_videos(videos.clone());
+    let playlist = playlist();
+    let mut group = c.benchmark_group("downloaded_in_playlist");
+    group.sample_size(10);
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            black_box(&playlist)
+                .iter()
+                .filter(|x| videos.iter().any(|y| x.video_id == y.video_id))
+                .count()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            black_box(&playlist)
+                .iter()
+                .filter(|x| database.contains(&x.video_id))
+                .count()
+        })
+    });
+    group.finish();
+}
+
+fn tracks_of_artist(c: &mut Criterion) {
+    let videos = library();
+    let database = Database::from_videos(videos.clone());
+    let mut group = c.benchmark_group("tracks_of_artist");
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            videos
+                .iter()
+                .filter(|x| x.author.eq_ignore_ascii_case(black_box("artist 42")))
+                .count()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| database.by_artist(black_box("artist 42")).count())
+    });
+    group.finish();
+}
+
+fn tracks_of_album(c: &mut Criterion) {
+    let videos = library();
+    let database = Database::from_videos(videos.clone());
+    let mut group = c.benchmark_group("tracks_of_album");
+    group.bench_function("linear", |b| {
+        b.iter(|| {
+            videos
+                .iter()
+                .filter(|x| x.album.eq_ignore_ascii_case(black_box("album 421")))
+                .count()
+        })
+    });
+    group.bench_function("indexed", |b| {
+        b.iter(|| database.by_album(black_box("album 421")).count())
+    });
+    group.finish();
+}
+
+fn build_and_update(c: &mut Criterion) {
+    let videos = library();
+    let mut group = c.benchmark_group("database");
+    group.sample_size(10);
+    group.bench_function("from_videos", |b| {
+        b.iter(|| Database::from_videos(black_box(videos.clone())))
+    });
+    let mut database = Database::from_videos(videos);
+    group.bench_function("remove_insert", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i = (i + 7_919) % LIBRARY_SIZE;
+            let removed = database.remove(&video(i).video_id).unwrap();
+            database.insert(removed);
+        })
+    });
+    // What the eviction of a full cache does
+    group.bench_function("remove_batch", |b| {
+        b.iter(|| {
+            let mut database = database.clone();
+            for i in (0..LIBRARY_SIZE).step_by(50) {
+                database.remove(&video(i).video_id);
+            }
+            database
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    downloaded_in_playlist,
+    tracks_of_artist,
+    tracks_of_album,
+    build_and_update
+);
+criterion_main!(benches);
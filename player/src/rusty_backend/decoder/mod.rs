@@ -9,6 +9,7 @@ use super::Source;
 
 use self::{read_seek_source::ReadSeekSource, symphonia::SymphoniaDecoder};
 use ::symphonia::core::io::{MediaSource, MediaSourceStream};
+mod opus;
 mod read_seek_source;
 mod symphonia;
 
@@ -19,16 +20,18 @@ pub struct Decoder {
 impl Decoder {
     /// Builds a new decoder.
     ///
-    /// Attempts to automatically detect the format of the source of data.
+    /// Attempts to automatically detect the format of the source of data,
+    /// trying first the format matching `extension` (for instance `mp4` or `webm`).
     pub fn new_decoder<R: Read + Seek + Send + Sync + 'static>(
         data: R,
+        extension: Option<&str>,
     ) -> Result<SymphoniaDecoder, DecoderError> {
         let mss = MediaSourceStream::new(
             Box::new(ReadSeekSource::new(data)) as Box<dyn MediaSource>,
             ::symphonia::core::io::MediaSourceStreamOptions::default(),
         );
 
-        match symphonia::SymphoniaDecoder::new(mss, Some("mp4")) {
+        match symphonia::SymphoniaDecoder::new(mss, extension) {
             Err(e) => Err(e),
             Ok(decoder) => Ok(decoder),
         }
@@ -0,0 +1,130 @@
+use std::sync::{Mutex, OnceLock};
+
+use symphonia::core::{
+    audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Channels, Signal, SignalSpec},
+    codecs::{
+        CodecDescriptor, CodecParameters, CodecRegistry, Decoder, DecoderOptions, FinalizeResult,
+        CODEC_TYPE_OPUS,
+    },
+    errors::{Error, Result},
+    formats::Packet,
+    support_codec,
+};
+
+/// Opus always decodes at 48 kHz
+const SAMPLE_RATE: u32 = 48_000;
+/// Number of frames of the longest Opus packet (120 ms at 48 kHz)
+const MAX_FRAMES: usize = 5760;
+
+/// Symphonia has no Opus decoder yet, so WebM/Opus streams are decoded with libopus.
+pub struct OpusDecoder {
+    params: CodecParameters,
+    // `opus::Decoder` isn't `Sync`, the mutex is only ever accessed through `get_mut`
+    decoder: Mutex<opus::Decoder>,
+    channels: usize,
+    /// Frames still to drop at the start of the stream, the pre-skip of the `OpusHead`
+    skip: usize,
+    samples: Vec<f32>,
+    buffer: AudioBuffer<f32>,
+}
+
+/// Reads the channel count from the `OpusHead` stored in the codec private data
+fn head_channels(params: &CodecParameters) -> Option<usize> {
+    let head = params.extra_data.as_deref()?;
+    if head.len() < 10 || &head[..8] != b"OpusHead" {
+        return None;
+    }
+    Some(head[9] as usize)
+}
+
+/// Reads the number of frames to drop at the start of the stream from the `OpusHead`
+fn head_pre_skip(params: &CodecParameters) -> Option<usize> {
+    let head = params.extra_data.as_deref()?;
+    if head.len() < 12 || &head[..8] != b"OpusHead" {
+        return None;
+    }
+    Some(u16::from_le_bytes([head[10], head[11]]) as usize)
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let channels = head_channels(params)
+            .or_else(|| params.channels.map(|x| x.count()))
+            .unwrap_or(2);
+        let (opus_channels, layout) = match channels {
+            1 => (opus::Channels::Mono, Channels::FRONT_LEFT),
+            2 => (
+                opus::Channels::Stereo,
+                Channels::FRONT_LEFT | Channels::FRONT_RIGHT,
+            ),
+            _ => {
+                return Err(Error::Unsupported(
+                    "opus: only mono and stereo streams are supported",
+                ))
+            }
+        };
+        let decoder = opus::Decoder::new(SAMPLE_RATE, opus_channels)
+            .map_err(|_| Error::DecodeError("opus: can't create decoder"))?;
+        Ok(Self {
+            params: params.clone(),
+            decoder: Mutex::new(decoder),
+            channels,
+            skip: head_pre_skip(params).unwrap_or(0),
+            samples: vec![0.0; MAX_FRAMES * channels],
+            buffer: AudioBuffer::new(MAX_FRAMES as u64, SignalSpec::new(SAMPLE_RATE, layout)),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[support_codec!(CODEC_TYPE_OPUS, "opus", "Opus")]
+    }
+
+    fn reset(&mut self) {
+        if let Ok(decoder) = self.decoder.get_mut() {
+            let _ = decoder.reset_state();
+        }
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef> {
+        let decoder = self
+            .decoder
+            .get_mut()
+            .map_err(|_| Error::DecodeError("opus: poisoned decoder"))?;
+        let frames = decoder
+            .decode_float(&packet.data, &mut self.samples, false)
+            .map_err(|_| Error::DecodeError("opus: invalid packet"))?;
+        let skipped = frames.min(self.skip);
+        self.skip -= skipped;
+        self.buffer.clear();
+        self.buffer.render_reserved(Some(frames - skipped));
+        for channel in 0..self.channels {
+            for (frame, sample) in self.buffer.chan_mut(channel).iter_mut().enumerate() {
+                *sample = self.samples[(skipped + frame) * self.channels + channel];
+            }
+        }
+        Ok(self.buffer.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef {
+        self.buffer.as_audio_buffer_ref()
+    }
+}
+
+/// The codecs enabled in Symphonia and the Opus decoder
+pub fn get_codecs() -> &'static CodecRegistry {
+    static CODECS: OnceLock<CodecRegistry> = OnceLock::new();
+    CODECS.get_or_init(|| {
+        let mut registry = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut registry);
+        registry.register_all::<OpusDecoder>();
+        registry
+    })
+}
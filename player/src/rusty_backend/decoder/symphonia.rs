@@ -70,7 +70,7 @@ impl SymphoniaDecoder {
             None => return Ok(None),
         };
 
-        let mut decoder = symphonia::default::get_codecs()
+        let mut decoder = super::opus::get_codecs()
             .make(&stream.codec_params, &DecoderOptions { verify: true })?;
 
         //Calculate total duraiton
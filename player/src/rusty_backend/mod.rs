@@ -167,8 +167,9 @@ impl Player {
         self.stop(guard);
         let file = File::open(path).map_err(PlayError::Io)?;
         //println!("{:?}", path);
-        let decoder =
-            Decoder::new_decoder(BufReader::new(file)).map_err(PlayError::DecoderError)?;
+        let extension = path.extension().and_then(|x| x.to_str());
+        let decoder = Decoder::new_decoder(BufReader::new(file), extension)
+            .map_err(PlayError::DecoderError)?;
         self.data.total_duration = decoder.total_duration();
         self.sink.append(decoder);
         Ok(())
@@ -87,7 +87,7 @@ impl OutputStreamHandle {
     where
         R: Read + Seek + Send + Sync + 'static,
     {
-        let input = decoder::Decoder::new_decoder(input)?;
+        let input = decoder::Decoder::new_decoder(input, None)?;
         let mut sink = Sink::try_new(self)?;
         sink.append(input);
         Ok(sink)
@@ -0,0 +1,132 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    consts::CONFIG,
+    database, read,
+    tasks::{self, export::export_library, local_playlists, offline, playlist_file},
+};
+
+const HELP: &str = r#"Usage: ytermusic [COMMAND]
+
+Without a command, starts the player.
+
+Commands:
+  export [DIRECTORY]  Export the downloaded musics to DIRECTORY
+                      (defaults to the `export.directory` of the config)
+  repair              Reconcile the local database with the downloaded files
+  import <FILE>...    Import playlist files (JSON, CSV, M3U, XSPF or a list of
+                      YouTube video ids or URLs) as offline local playlists
+  export-library <FILE>
+                      Export the local database to FILE, in the format given
+                      by its extension (json, csv, m3u8 or xspf)
+  export-playlist <NAME> <FILE>
+                      Export the local or offline playlist NAME to FILE
+  help                Print this message"#;
+
+/// Runs the subcommand given on the command line if any.
+/// Returns whether a subcommand was run, in which case the player must not start.
+pub fn handle_args() -> bool {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        None => false,
+        Some("export") => {
+            export(args.get(1).map(PathBuf::from));
+            true
+        }
+        Some("import") if args.len() > 1 => {
+            import(&args[1..]);
+            true
+        }
+        Some("export-library") if args.len() == 2 => {
+            export_database(Path::new(&args[1]));
+            true
+        }
+        Some("export-playlist") if args.len() == 3 => {
+            export_playlist(&args[1], Path::new(&args[2]));
+            true
+        }
+        Some("repair" | "--repair") => {
+            repair();
+            true
+        }
+        Some("help" | "-h" | "--help") => {
+            println!("{HELP}");
+            true
+        }
+        Some(command) => {
+            println!("Unknown command `{command}`\n");
+            println!("{HELP}");
+            true
+        }
+    }
+}
+
+fn export(directory: Option<PathBuf>) {
+    let directory = directory.unwrap_or_else(|| CONFIG.export.directory.clone());
+    let Some(database) = read() else {
+        println!("Can't read the local database");
+        return;
+    };
+    let videos = database.videos();
+    // The ratings are exported from the global database
+    database::load(database);
+    println!(
+        "Exporting {} musics to `{}`...",
+        videos.len(),
+        directory.display()
+    );
+    match export_library(&videos, &directory) {
+        Ok(report) => println!("Done: {report}"),
+        Err(e) => println!("Can't export the musics: {e}"),
+    }
+}
+
+fn repair() {
+    println!("Repairing the local database...");
+    let (database, report) = tasks::repair::repair();
+    if report.is_empty() {
+        println!("Nothing to repair, {} musics", database.len());
+    } else {
+        println!("Done: {report}, {} musics", database.len());
+    }
+}
+
+fn import(files: &[String]) {
+    for file in files {
+        match playlist_file::import(Path::new(file)) {
+            Ok((name, videos)) => {
+                println!("Imported {} musics from `{file}` as {name}", videos.len())
+            }
+            Err(e) => println!("{e}"),
+        }
+    }
+    println!("The missing musics will be downloaded the next time the player starts");
+}
+
+fn export_database(file: &Path) {
+    let Some(database) = read() else {
+        println!("Can't read the local database");
+        return;
+    };
+    match playlist_file::export(file, "Library", &database.videos()) {
+        Ok(()) => println!("Exported {} musics to `{}`", database.len(), file.display()),
+        Err(e) => println!("Can't export the library: {e}"),
+    }
+}
+
+fn export_playlist(name: &str, file: &Path) {
+    let local = local_playlists::LOCAL_PLAYLISTS.read().unwrap().clone();
+    let pinned = offline::PINNED_PLAYLISTS.read().unwrap().clone();
+    let Some((_, videos)) = local
+        .into_iter()
+        .chain(pinned.into_iter().map(|x| (x.name, x.videos)))
+        .find(|(playlist, _)| playlist == name)
+    else {
+        println!("No local or offline playlist named {name}");
+        return;
+    };
+    match playlist_file::export(file, name, &videos) {
+        Ok(()) => println!("Exported {} musics to `{}`", videos.len(), file.display()),
+        Err(e) => println!("Can't export {name}: {e}"),
+    }
+}
@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+
+use directories::UserDirs;
 use log::info;
 use ratatui::style::{Color, Modifier, Style};
 use serde::{Deserialize, Serialize};
@@ -22,6 +25,9 @@ pub struct MusicPlayerConfig {
     /// Whether to shuffle playlists before playing
     #[serde(default)]
     pub shuffle: bool,
+    /// Shuffle tracks that are often skipped or were played in the last day later
+    #[serde(default)]
+    pub smart_shuffle: bool,
     #[serde(default = "default_paused_style", with = "StyleDef")]
     pub gauge_paused_style: Style,
     #[serde(default = "default_playing_style", with = "StyleDef")]
@@ -38,6 +44,29 @@ pub struct MusicPlayerConfig {
     pub text_previous_style: Style,
     #[serde(default = "default_downloading_style", with = "StyleDef")]
     pub text_downloading_style: Style,
+    /// How the cover of the current music is shown
+    #[serde(default)]
+    pub cover: CoverMode,
+    /// Whether the lyrics of the current music are shown next to the queue on start,
+    /// they can be toggled with `L`
+    #[serde(default)]
+    pub lyrics: bool,
+    /// Whether tracks related to the last played ones are added when the queue runs out,
+    /// it can be toggled with `r`
+    #[serde(default)]
+    pub autoplay: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverMode {
+    /// The cover isn't shown
+    Off,
+    /// Drawn with unicode half blocks, works in every terminal
+    Halfblocks,
+    /// Uses kitty or sixel graphics when the terminal supports them, half blocks otherwise
+    #[default]
+    Auto,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -61,6 +90,7 @@ impl Default for MusicPlayerConfig {
             dbus: default_true(),
             initial_volume: default_volume(),
             shuffle: Default::default(),
+            smart_shuffle: Default::default(),
             gauge_paused_style: default_paused_style(),
             gauge_playing_style: default_playing_style(),
             gauge_nomusic_style: default_nomusic_style(),
@@ -70,6 +100,9 @@ impl Default for MusicPlayerConfig {
             text_previous_style: default_nomusic_style(),
             text_downloading_style: default_downloading_style(),
             volume_slider: enable_volume_slider(),
+            cover: CoverMode::default(),
+            lyrics: Default::default(),
+            autoplay: Default::default(),
         }
     }
 }
@@ -110,6 +143,182 @@ pub struct PlaylistConfig {}
 #[non_exhaustive]
 pub struct SearchConfig {}
 
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ExportConfig {
+    /// Directory the library is exported to from the TUI.
+    /// Defaults to a `ytermusic` folder in the music directory of the user.
+    #[serde(default = "default_export_directory")]
+    pub directory: PathBuf,
+    /// Path of each exported track relative to the export directory.
+    /// Available fields are `{author}`, `{album}`, `{title}` and `{video_id}`.
+    #[serde(default = "default_export_template")]
+    pub template: String,
+    /// Whether to hardlink the tracks instead of copying them when possible
+    #[serde(default)]
+    pub hardlink: bool,
+    /// Whether to generate an M3U file for each offline and local playlist
+    #[serde(default = "default_true")]
+    pub m3u: bool,
+    /// Format of the playlists exported from the TUI: `m3u8`, `xspf`, `json` or `csv`
+    #[serde(default = "default_playlist_format")]
+    pub playlist_format: String,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_export_directory(),
+            template: default_export_template(),
+            hardlink: Default::default(),
+            m3u: default_true(),
+            playlist_format: default_playlist_format(),
+        }
+    }
+}
+
+fn default_export_directory() -> PathBuf {
+    UserDirs::new()
+        .and_then(|dirs| dirs.audio_dir().map(|dir| dir.join("ytermusic")))
+        .unwrap_or_else(|| PathBuf::from("./ytermusic-export"))
+}
+
+fn default_export_template() -> String {
+    "{author}/{album}/{title}.m4a".to_owned()
+}
+
+fn default_playlist_format() -> String {
+    "m3u8".to_owned()
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct DownloadConfig {
+    /// Backends used to download the musics, tried in order until one succeeds
+    #[serde(default = "default_download_backends")]
+    pub backends: Vec<DownloadBackendConfig>,
+    /// Preferred audio codec, the other one is used when it isn't available
+    #[serde(default)]
+    pub codec: AudioCodec,
+    /// Maximum audio bitrate in kbps, for metered connections (0 means no limit).
+    /// Higher bitrates are only used when no format fits.
+    #[serde(default)]
+    pub max_bitrate: u64,
+    /// Downloads the lowest bitrate available instead of the highest
+    #[serde(default)]
+    pub data_saver: bool,
+    /// Maximum size of the cache in MiB (0 means no limit). The least listened tracks
+    /// are evicted on start, tracks of offline playlists never are.
+    #[serde(default)]
+    pub cache_limit: u64,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            backends: default_download_backends(),
+            codec: AudioCodec::default(),
+            max_bitrate: 0,
+            data_saver: false,
+            cache_limit: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    /// AAC in an MP4 container
+    #[default]
+    Aac,
+    /// Opus in a WebM container
+    Opus,
+}
+
+impl AudioCodec {
+    /// Container used by YouTube for this codec
+    pub fn container(self) -> &'static str {
+        match self {
+            Self::Aac => "mp4",
+            Self::Opus => "webm",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DownloadBackendConfig {
+    /// Downloads directly from YouTube
+    RustyYtdl,
+    /// Runs an external command like `yt-dlp` that must write the audio to `{output}`.
+    /// `{video_id}`, `{output}` and `{format}` (a `yt-dlp` format selector following
+    /// the codec and bitrate preferences) are replaced in the arguments.
+    External {
+        command: String,
+        #[serde(default = "default_external_args")]
+        args: Vec<String>,
+    },
+    /// Fetches `<url>/<video_id>.mp4` or `<url>/<video_id>.webm` from an HTTP directory
+    Mirror { url: String },
+}
+
+fn default_download_backends() -> Vec<DownloadBackendConfig> {
+    vec![DownloadBackendConfig::RustyYtdl]
+}
+
+fn default_external_args() -> Vec<String> {
+    [
+        "--quiet",
+        "--no-playlist",
+        "-f",
+        "{format}",
+        "-o",
+        "{output}",
+        "https://music.youtube.com/watch?v={video_id}",
+    ]
+    .into_iter()
+    .map(str::to_owned)
+    .collect()
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
+pub struct ApiConfig {
+    /// URL of YouTube Music, can point to a mirror or to the mock server of `ytpapi2`
+    #[serde(default = "default_api_base_url")]
+    pub base_url: String,
+    /// Seconds after which a request to YouTube Music is given up (0 means never)
+    #[serde(default = "default_api_timeout")]
+    pub timeout: u64,
+    /// Proxy the requests to YouTube Music go through, like `http://127.0.0.1:8080` or
+    /// `socks5://127.0.0.1:9050`
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Whether the musics played to the end are reported to YouTube Music, which adds
+    /// them to the history of the account and to what its recommendations are based on
+    #[serde(default)]
+    pub report_plays: bool,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            base_url: default_api_base_url(),
+            timeout: default_api_timeout(),
+            proxy: None,
+            report_plays: false,
+        }
+    }
+}
+
+fn default_api_base_url() -> String {
+    "https://music.youtube.com".to_owned()
+}
+
+fn default_api_timeout() -> u64 {
+    30
+}
+
 #[allow(unused)]
 #[derive(Debug, Default, Deserialize, Serialize)]
 #[non_exhaustive]
@@ -122,6 +331,12 @@ pub struct Config {
     pub playlist: PlaylistConfig,
     #[serde(default)]
     pub search: SearchConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub download: DownloadConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
 }
 
 impl Config {
@@ -0,0 +1,31 @@
+//! The videos of the tests and of the benchmarks
+
+use ytpapi2::YoutubeMusicVideoRef;
+
+/// The `i`-th video of a library of 3 artists and 5 albums
+pub fn video(i: usize) -> YoutubeMusicVideoRef {
+    library_video(i, 3, 5)
+}
+
+/// The `i`-th video of a library of `artists` artists and `albums` albums.
+/// Like the videos of the search, every tenth one has no artist, every other one no album
+/// and every seventh one no duration. Titles hold characters the playlist formats escape.
+pub fn library_video(i: usize, artists: usize, albums: usize) -> YoutubeMusicVideoRef {
+    YoutubeMusicVideoRef {
+        title: format!("Title {i}, \"quoted\" & <more> ✓"),
+        author: if i.is_multiple_of(10) {
+            String::new()
+        } else {
+            format!("Artist {}", i % artists)
+        },
+        album: if i.is_multiple_of(2) {
+            String::new()
+        } else {
+            format!("Album {}", i % albums)
+        },
+        video_id: format!("id{i:09}"),
+        duration: (!i.is_multiple_of(7)).then_some(180 + i as u32),
+        thumbnail: String::new(),
+        artist_id: String::new(),
+    }
+}
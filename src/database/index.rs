@@ -0,0 +1,385 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ytpapi2::YoutubeMusicVideoRef;
+
+use super::{rating::Rating, stats::PlayStats};
+
+/// The downloaded videos, indexed by video id, artist and album.
+/// Artists and albums are matched case-insensitively, videos without an artist or an album
+/// aren't indexed by it.
+/// Play statistics and ratings are kept when a video is removed from the cache.
+#[derive(Debug, Default, Clone)]
+pub struct Database {
+    /// The videos in insertion order, `None` where a video was removed.
+    /// Removed slots are dropped once they outnumber the videos.
+    slots: Vec<Option<YoutubeMusicVideoRef>>,
+    /// Number of videos in `slots`
+    len: usize,
+    by_id: HashMap<String, usize>,
+    by_artist: HashMap<String, BTreeSet<usize>>,
+    by_album: HashMap<String, BTreeSet<usize>>,
+    stats: HashMap<String, PlayStats>,
+    /// The rated videos with their metadata, so they can still be listed once evicted
+    ratings: HashMap<String, (YoutubeMusicVideoRef, Rating)>,
+}
+
+impl Database {
+    /// Builds the indexes of the videos, keeping the first of duplicated videos
+    pub fn from_videos(videos: Vec<YoutubeMusicVideoRef>) -> Self {
+        let mut database = Self {
+            slots: Vec::with_capacity(videos.len()),
+            len: 0,
+            by_id: HashMap::with_capacity(videos.len()),
+            by_artist: HashMap::new(),
+            by_album: HashMap::new(),
+            stats: HashMap::new(),
+            ratings: HashMap::new(),
+        };
+        for video in videos {
+            if !database.contains(&video.video_id) {
+                database.insert(video);
+            }
+        }
+        database
+    }
+
+    pub fn with_stats(mut self, stats: HashMap<String, PlayStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// The statistics of every video ever played, downloaded or not
+    pub fn all_stats(&self) -> &HashMap<String, PlayStats> {
+        &self.stats
+    }
+
+    pub fn stats(&self, video_id: &str) -> PlayStats {
+        self.stats.get(video_id).copied().unwrap_or_default()
+    }
+
+    pub fn stats_mut(&mut self, video_id: &str) -> &mut PlayStats {
+        self.stats.entry(video_id.to_owned()).or_default()
+    }
+
+    pub fn with_ratings(mut self, ratings: Vec<(YoutubeMusicVideoRef, Rating)>) -> Self {
+        for (video, rating) in ratings {
+            self.set_rating(&video, rating);
+        }
+        self
+    }
+
+    pub fn rating(&self, video_id: &str) -> Rating {
+        self.ratings
+            .get(video_id)
+            .map(|(_, rating)| *rating)
+            .unwrap_or_default()
+    }
+
+    /// Rates a video, forgetting it when it isn't rated anymore
+    pub fn set_rating(&mut self, video: &YoutubeMusicVideoRef, rating: Rating) {
+        if rating.is_unrated() {
+            self.ratings.remove(&video.video_id);
+        } else {
+            self.ratings
+                .insert(video.video_id.clone(), (video.clone(), rating));
+        }
+    }
+
+    /// Every rated video, downloaded or not
+    pub fn rated(&self) -> impl Iterator<Item = (&YoutubeMusicVideoRef, Rating)> {
+        self.ratings
+            .values()
+            .map(|(video, rating)| (video, *rating))
+    }
+
+    /// A copy of the videos in insertion order
+    pub fn videos(&self) -> Vec<YoutubeMusicVideoRef> {
+        self.iter().cloned().collect()
+    }
+
+    /// The videos in insertion order
+    pub fn iter(&self) -> std::iter::Flatten<std::slice::Iter<'_, Option<YoutubeMusicVideoRef>>> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn get(&self, video_id: &str) -> Option<&YoutubeMusicVideoRef> {
+        self.by_id
+            .get(video_id)
+            .and_then(|&i| self.slots[i].as_ref())
+    }
+
+    /// The duration of a video, or the one stored for its cached file when it's unknown
+    pub fn duration(&self, video: &YoutubeMusicVideoRef) -> Option<u32> {
+        video
+            .duration
+            .or_else(|| self.get(&video.video_id)?.duration)
+    }
+
+    /// Sets the duration of a downloaded video. Returns whether the video is downloaded.
+    pub fn set_duration(&mut self, video_id: &str, seconds: u32) -> bool {
+        if let Some((video, _)) = self.ratings.get_mut(video_id) {
+            video.duration = Some(seconds);
+        }
+        let Some(video) = self
+            .by_id
+            .get(video_id)
+            .and_then(|&i| self.slots[i].as_mut())
+        else {
+            return false;
+        };
+        video.duration = Some(seconds);
+        true
+    }
+
+    pub fn contains(&self, video_id: &str) -> bool {
+        self.by_id.contains_key(video_id)
+    }
+
+    /// The videos of an artist in insertion order
+    pub fn by_artist<'a>(
+        &'a self,
+        artist: &str,
+    ) -> impl Iterator<Item = &'a YoutubeMusicVideoRef> + 'a {
+        self.lookup(&self.by_artist, artist)
+    }
+
+    /// The videos of an album in insertion order
+    pub fn by_album<'a>(
+        &'a self,
+        album: &str,
+    ) -> impl Iterator<Item = &'a YoutubeMusicVideoRef> + 'a {
+        self.lookup(&self.by_album, album)
+    }
+
+    fn lookup<'a>(
+        &'a self,
+        index: &'a HashMap<String, BTreeSet<usize>>,
+        key: &str,
+    ) -> impl Iterator<Item = &'a YoutubeMusicVideoRef> + 'a {
+        index
+            .get(&key.to_lowercase())
+            .into_iter()
+            .flatten()
+            .filter_map(|&i| self.slots[i].as_ref())
+    }
+
+    /// Adds a video, replacing the one with the same id
+    pub fn insert(&mut self, video: YoutubeMusicVideoRef) {
+        self.remove(&video.video_id);
+        let position = self.slots.len();
+        self.by_id.insert(video.video_id.clone(), position);
+        index(&mut self.by_artist, &video.author, position);
+        index(&mut self.by_album, &video.album, position);
+        self.slots.push(Some(video));
+        self.len += 1;
+    }
+
+    /// Removes a video, keeping the order of the others
+    pub fn remove(&mut self, video_id: &str) -> Option<YoutubeMusicVideoRef> {
+        let position = self.by_id.remove(video_id)?;
+        let video = self.slots[position].take()?;
+        self.len -= 1;
+        unindex(&mut self.by_artist, &video.author, position);
+        unindex(&mut self.by_album, &video.album, position);
+        if self.slots.len() > 2 * self.len {
+            self.compact();
+        }
+        Some(video)
+    }
+
+    /// Drops the removed slots, in a time amortized over the removals
+    fn compact(&mut self) {
+        let videos = std::mem::take(&mut self.slots)
+            .into_iter()
+            .flatten()
+            .collect();
+        *self = Self {
+            stats: std::mem::take(&mut self.stats),
+            ratings: std::mem::take(&mut self.ratings),
+            ..Self::from_videos(videos)
+        };
+    }
+}
+
+/// Adds a position to the bucket of a key, unless the key is empty
+fn index(index: &mut HashMap<String, BTreeSet<usize>>, key: &str, position: usize) {
+    if !key.is_empty() {
+        index
+            .entry(key.to_lowercase())
+            .or_default()
+            .insert(position);
+    }
+}
+
+/// Removes a position from the bucket of a key, dropping the bucket once empty
+fn unindex(index: &mut HashMap<String, BTreeSet<usize>>, key: &str, position: usize) {
+    let key = key.to_lowercase();
+    if let Some(positions) = index.get_mut(&key) {
+        positions.remove(&position);
+        if positions.is_empty() {
+            index.remove(&key);
+        }
+    }
+}
+
+impl PartialEq for Database {
+    /// Databases are equal whatever slots were removed
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter()) && self.stats == other.stats && self.ratings == other.ratings
+    }
+}
+
+impl Eq for Database {}
+
+impl<'a> IntoIterator for &'a Database {
+    type Item = &'a YoutubeMusicVideoRef;
+    type IntoIter = std::iter::Flatten<std::slice::Iter<'a, Option<YoutubeMusicVideoRef>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Relative, the benchmarks include this file at the root of their crate
+    use super::super::fixtures::video;
+    use super::*;
+
+    /// Checks that every index points to the right videos
+    fn assert_consistent(database: &Database) {
+        assert_eq!(database.by_id.len(), database.len());
+        assert_eq!(database.iter().count(), database.len());
+        for (i, video) in database.slots.iter().enumerate() {
+            let Some(video) = video else {
+                continue;
+            };
+            assert_eq!(database.by_id[&video.video_id], i);
+            assert_eq!(
+                database.by_artist(&video.author).any(|x| x == video),
+                !video.author.is_empty()
+            );
+            assert_eq!(
+                database.by_album(&video.album).any(|x| x == video),
+                !video.album.is_empty()
+            );
+        }
+        let indexed = |index: &HashMap<String, BTreeSet<usize>>| {
+            index.values().map(BTreeSet::len).sum::<usize>()
+        };
+        assert_eq!(
+            indexed(&database.by_artist),
+            database.iter().filter(|x| !x.author.is_empty()).count()
+        );
+        assert_eq!(
+            indexed(&database.by_album),
+            database.iter().filter(|x| !x.album.is_empty()).count()
+        );
+        assert!(database.slots.len() <= 2 * database.len().max(1));
+    }
+
+    #[test]
+    fn from_videos_skips_duplicates() {
+        let database = Database::from_videos(vec![video(1), video(2), video(1)]);
+        assert_eq!(database.videos(), [video(1), video(2)]);
+        assert_consistent(&database);
+    }
+
+    #[test]
+    fn remove_keeps_indexes_consistent() {
+        let mut database = Database::from_videos((0..20).map(video).collect());
+        for i in [0, 19, 7, 7, 3, 12] {
+            database.remove(&video(i).video_id);
+            assert!(!database.contains(&video(i).video_id));
+            assert_consistent(&database);
+        }
+        assert_eq!(database.len(), 15);
+        assert!(database.remove("unknown").is_none());
+    }
+
+    #[test]
+    fn remove_keeps_order() {
+        let mut database = Database::from_videos((0..10).map(video).collect());
+        for i in [2, 5, 0, 9, 6, 7, 1] {
+            database.remove(&video(i).video_id);
+            assert_consistent(&database);
+        }
+        assert_eq!(database.videos(), [video(3), video(4), video(8)]);
+        database.insert(video(0));
+        assert_eq!(database.videos(), [video(3), video(4), video(8), video(0)]);
+        assert_eq!(database, Database::from_videos(database.videos()));
+    }
+
+    #[test]
+    fn videos_without_artist_or_album_are_not_indexed_by_it() {
+        let mut database = Database::from_videos(
+            (0..6)
+                .map(|i| YoutubeMusicVideoRef {
+                    author: String::new(),
+                    album: String::new(),
+                    ..video(i)
+                })
+                .collect(),
+        );
+        assert!(database.by_artist.is_empty());
+        assert!(database.by_album.is_empty());
+        assert_eq!(database.by_artist("").count(), 0);
+        assert_eq!(database.by_album("").count(), 0);
+        database.remove(&video(3).video_id);
+        assert_consistent(&database);
+    }
+
+    #[test]
+    fn insert_replaces_video() {
+        let mut database = Database::from_videos((0..5).map(video).collect());
+        let mut renamed = video(1);
+        renamed.author = "Someone Else".to_owned();
+        renamed.album = "Another Album".to_owned();
+        database.insert(renamed.clone());
+        assert_consistent(&database);
+        assert_eq!(database.len(), 5);
+        assert_eq!(
+            database.by_artist("someone else").collect::<Vec<_>>(),
+            [&renamed]
+        );
+        assert_eq!(
+            database.by_album("another album").collect::<Vec<_>>(),
+            [&renamed]
+        );
+        assert_eq!(database.by_artist("artist 1").count(), 1);
+        assert_eq!(database.by_album("album 1").count(), 0);
+    }
+
+    #[test]
+    fn lookups_ignore_case() {
+        let database = Database::from_videos((0..20).map(video).collect());
+        assert_eq!(database.by_artist("ARTIST 1").count(), 6);
+        assert_eq!(database.by_artist("Artist 9").count(), 0);
+        assert_eq!(
+            database.by_album("aLbUm 3").collect::<Vec<_>>(),
+            [&video(3), &video(13)]
+        );
+        assert_eq!(database.by_album("Album 2").count(), 2);
+        assert_eq!(database.by_album("Album 9").count(), 0);
+    }
+
+    #[test]
+    fn set_duration_fills_unknown_durations() {
+        let mut database = Database::from_videos((0..3).map(video).collect());
+        assert_eq!(database.duration(&video(0)), None);
+        assert!(database.set_duration(&video(0).video_id, 215));
+        assert!(!database.set_duration("unknown", 215));
+        // Videos from the API are given the duration of their cached file
+        assert_eq!(database.duration(&video(0)), Some(215));
+        assert_eq!(database.duration(&video(1)), Some(181));
+        let unknown = YoutubeMusicVideoRef {
+            duration: None,
+            ..video(1)
+        };
+        assert_eq!(database.duration(&unknown), Some(181));
+    }
+}
@@ -1,37 +1,131 @@
-use std::{fs::OpenOptions, sync::RwLock};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
 
 use log::info;
 use once_cell::sync::Lazy;
 
+#[cfg(test)]
+pub(crate) mod fixtures;
+mod index;
+mod rating;
 mod reader;
+mod stats;
 mod writer;
 
-pub use reader::read;
-pub use writer::{write, write_video};
+pub use index::Database;
+pub use rating::Rating;
+pub use reader::{decode, read, salvage, Salvaged};
+pub use stats::{now, PlayStats};
+pub use writer::{write, write_to};
 use ytpapi2::YoutubeMusicVideoRef;
 
-use crate::consts::CACHE_DIR;
+/// Bytes starting every database since the format is versioned
+const MAGIC: &[u8; 4] = b"YTMD";
+/// Version of the database format written by this build
+pub const VERSION: u32 = 3;
 
 /// A global variable to store the current musical Database
-pub static DATABASE: Lazy<RwLock<Vec<YoutubeMusicVideoRef>>> =
-    Lazy::new(|| RwLock::new(Vec::new()));
+pub static DATABASE: Lazy<RwLock<Database>> = Lazy::new(|| RwLock::new(Database::default()));
+
+/// Time between two writes of the changes of the database
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Whether the database changed since it was last written
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+/// Whether the database was read from the disk, it isn't written before
+static LOADED: AtomicBool = AtomicBool::new(false);
+
+/// Replaces the database with the one read from the disk
+pub fn load(database: Database) {
+    *DATABASE.write().unwrap() = database;
+    LOADED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the database was read from the disk
+pub fn is_loaded() -> bool {
+    LOADED.load(Ordering::SeqCst)
+}
+
+/// Marks the database as changed, the flush task writes it shortly after
+fn write_later() {
+    DIRTY.store(true, Ordering::SeqCst);
+}
+
+/// Writes the database if it changed since it was last written
+pub fn flush() {
+    if DIRTY.swap(false, Ordering::SeqCst) {
+        write();
+    }
+}
+
+/// Writes the changes of the database every few seconds, so the tracks of a batch of
+/// downloads or of plays are written at once rather than each rewriting the database
+pub fn spawn_flush_task() {
+    crate::run_service(async {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            flush();
+        }
+    });
+}
 
 /// Remove a video from the database
 pub fn remove_video(video: &YoutubeMusicVideoRef) {
-    let mut database = DATABASE.write().unwrap();
-    database.retain(|v| v.video_id != video.video_id);
+    DATABASE.write().unwrap().remove(&video.video_id);
     write();
 }
 
 /// Append a video to the database
 pub fn append(video: YoutubeMusicVideoRef) {
-    let mut file = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .create(true)
-        .open(CACHE_DIR.join("db.bin"))
-        .unwrap();
-    write_video(&mut file, &video);
     info!("Appended {} to database", video.title);
-    DATABASE.write().unwrap().push(video);
+    DATABASE.write().unwrap().insert(video);
+    write_later();
+}
+
+/// Records that a video started playing
+pub fn record_start(video_id: &str) {
+    DATABASE.write().unwrap().stats_mut(video_id).last_played = now();
+}
+
+/// Records the end of a listening session of `listened` seconds
+pub fn record_listening(video_id: &str, listened: u64, completed: bool) {
+    {
+        let mut database = DATABASE.write().unwrap();
+        let stats = database.stats_mut(video_id);
+        stats.listening_time += listened;
+        if completed {
+            stats.play_count += 1;
+        } else {
+            stats.skip_count += 1;
+        }
+    }
+    write_later();
+}
+
+/// Stores the duration decoded from the cached file of a video the API gave no duration for
+pub fn record_duration(video_id: &str, seconds: u32) {
+    if DATABASE.write().unwrap().set_duration(video_id, seconds) {
+        info!("Recorded the duration of {video_id}: {seconds}s");
+        write_later();
+    }
+}
+
+/// Changes the rating of a video and returns the new rating
+pub fn rate(video: &YoutubeMusicVideoRef, change: impl FnOnce(&mut Rating)) -> Rating {
+    let rating = {
+        let mut database = DATABASE.write().unwrap();
+        let mut rating = database.rating(&video.video_id);
+        change(&mut rating);
+        database.set_rating(video, rating);
+        rating
+    };
+    info!("Rated {} {rating:?}", video.video_id);
+    write_later();
+    rating
 }
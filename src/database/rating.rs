@@ -0,0 +1,31 @@
+/// A rating given locally to a track, independent of the YouTube account
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    pub favorite: bool,
+    /// From 1 to 5, 0 when the track isn't rated
+    pub stars: u8,
+}
+
+impl Rating {
+    pub fn is_unrated(&self) -> bool {
+        !self.favorite && self.stars == 0
+    }
+
+    /// The glyph shown before rated tracks, empty when the track isn't rated
+    pub fn glyph(&self) -> String {
+        let mut glyph = String::new();
+        if self.favorite {
+            glyph.push('♥');
+        }
+        if self.stars > 0 {
+            if self.favorite {
+                glyph.push(' ');
+            }
+            glyph.extend(std::iter::repeat_n('★', self.stars as usize));
+        }
+        if !glyph.is_empty() {
+            glyph.push(' ');
+        }
+        glyph
+    }
+}
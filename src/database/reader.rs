@@ -1,39 +1,470 @@
-use std::io::{Cursor, Read};
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read},
+};
 
+use log::{info, warn};
 use varuint::ReadVarint;
-use ytpapi2::YoutubeMusicVideoRef;
+use ytpapi2::{parse_duration, YoutubeMusicVideoRef};
 
 use crate::consts::CACHE_DIR;
 
-/// Reads the database
-pub fn read() -> Option<Vec<YoutubeMusicVideoRef>> {
-    let mut buffer = Cursor::new(std::fs::read(CACHE_DIR.join("db.bin")).ok()?);
+use super::{rating::Rating, stats::PlayStats, writer::write_to, Database, MAGIC, VERSION};
+
+/// Reads the database, migrating it to the current format if needed
+pub fn read() -> Option<Database> {
+    let path = CACHE_DIR.join("db.bin");
+    let bytes = std::fs::read(&path).ok()?;
+    match decode(&bytes) {
+        Ok((version, database)) => {
+            if version < VERSION {
+                info!("Migrating database from version {version} to {VERSION}");
+                if let Err(e) = write_to(&path, &database) {
+                    warn!("Can't migrate database: {e}");
+                }
+            }
+            Some(database)
+        }
+        Err(e) => {
+            warn!("Can't read database: {e}");
+            None
+        }
+    }
+}
+
+/// Decodes a database of any version, returning the version it was written with
+pub fn decode(bytes: &[u8]) -> Result<(u32, Database), String> {
+    let Some(content) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        // Databases written before the header existed are version 0
+        return read_legacy(bytes).map(|videos| (0, Database::from_videos(videos)));
+    };
+    let mut buffer = Cursor::new(content);
+    let version = read_u32(&mut buffer).ok_or("truncated header")?;
+    if version == 0 || version > VERSION {
+        return Err(format!("unsupported version {version}"));
+    }
+    let mut payload = read_payload(&mut buffer)?;
+    let videos = read_videos(&mut payload)?;
+    // Version 1 had no play statistics
+    let stats = if version >= 2 {
+        read_all_stats(&mut payload)?
+    } else {
+        HashMap::new()
+    };
+    // Version 2 had no ratings
+    let ratings = if version >= 3 {
+        read_ratings(&mut payload)?
+    } else {
+        Vec::new()
+    };
+    Ok((
+        version,
+        Database::from_videos(videos)
+            .with_stats(stats)
+            .with_ratings(ratings),
+    ))
+}
+
+/// What could be read from a damaged database
+#[derive(Debug, Default, PartialEq)]
+pub struct Salvaged {
+    /// The videos in the order they were stored, duplicates included
+    pub videos: Vec<YoutubeMusicVideoRef>,
+    pub stats: HashMap<String, PlayStats>,
+    pub ratings: Vec<(YoutubeMusicVideoRef, Rating)>,
+    /// The number of records that couldn't be read
+    pub dropped: usize,
+}
+
+/// Reads every valid record of a database, ignoring the checksum and skipping
+/// the invalid records instead of failing on the first one
+pub fn salvage(bytes: &[u8]) -> Salvaged {
+    let mut salvaged = Salvaged::default();
+    let Some(content) = bytes.strip_prefix(MAGIC.as_slice()) else {
+        let mut buffer = Cursor::new(bytes);
+        while !is_exhausted(&buffer) {
+            let Some(video) = read_video(&mut buffer) else {
+                salvaged.dropped += 1;
+                break;
+            };
+            salvaged.videos.push(video);
+        }
+        return salvaged;
+    };
+    let mut buffer = Cursor::new(content);
+    let Some(version) = read_u32(&mut buffer) else {
+        return salvaged;
+    };
+    buffer.set_position(buffer.position() + 4);
+    let dropped = &mut salvaged.dropped;
+    salvaged.videos = salvage_records(&mut buffer, read_record_video, dropped);
+    if version >= 2 {
+        salvaged.stats = salvage_records(&mut buffer, read_record_stats, dropped)
+            .into_iter()
+            .collect();
+    }
+    if version >= 3 {
+        salvaged.ratings = salvage_records(&mut buffer, read_record_rating, dropped);
+    }
+    salvaged
+}
+
+/// Reads a count followed by as many length-prefixed records, skipping the invalid ones
+/// and stopping at the first truncated one
+fn salvage_records<T>(
+    buffer: &mut Cursor<&[u8]>,
+    read: impl Fn(&mut Cursor<&[u8]>) -> Result<T, String>,
+    dropped: &mut usize,
+) -> Vec<T> {
+    let mut records = Vec::new();
+    let Some(count) = read_u32(buffer) else {
+        return records;
+    };
+    for _ in 0..count {
+        let record = read_u32(buffer)
+            .filter(|length| *length as usize <= remaining(buffer))
+            .and_then(|length| {
+                let mut record = vec![0u8; length as usize];
+                buffer.read_exact(&mut record).ok()?;
+                Some(record)
+            });
+        let Some(record) = record else {
+            // Nothing after a truncated record can be trusted
+            *dropped += 1;
+            buffer.set_position(buffer.get_ref().len() as u64);
+            break;
+        };
+        match read(&mut Cursor::new(record.as_slice())) {
+            Ok(record) => records.push(record),
+            Err(e) => {
+                warn!("Dropped database record: {e}");
+                *dropped += 1;
+            }
+        }
+    }
+    records
+}
+
+/// Reads a headerless stream of videos, each being five varint-prefixed strings
+fn read_legacy(bytes: &[u8]) -> Result<Vec<YoutubeMusicVideoRef>, String> {
+    let mut buffer = Cursor::new(bytes);
     let mut videos = Vec::new();
-    while buffer.get_mut().len() > buffer.position() as usize {
-        videos.push(read_video(&mut buffer)?);
+    while bytes.len() > buffer.position() as usize {
+        videos.push(read_video(&mut buffer).ok_or("truncated video")?);
     }
-    Some(videos)
+    Ok(videos)
+}
+
+/// Reads the checksum following the header and checks the payload against it
+fn read_payload<'a>(buffer: &mut Cursor<&'a [u8]>) -> Result<Cursor<&'a [u8]>, String> {
+    let mut checksum = [0; 4];
+    buffer
+        .read_exact(&mut checksum)
+        .map_err(|_| "truncated header")?;
+    let payload = &buffer.get_ref()[buffer.position() as usize..];
+    if crc32fast::hash(payload) != u32::from_le_bytes(checksum) {
+        return Err("checksum mismatch".to_owned());
+    }
+    Ok(Cursor::new(payload))
+}
+
+/// Reads a count followed by as many length-prefixed records
+fn read_records<T>(
+    buffer: &mut Cursor<&[u8]>,
+    read: impl Fn(&mut Cursor<&[u8]>) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    let count = read_u32(buffer).ok_or("truncated payload")?;
+    let mut records = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut record = vec![0u8; read_u32(buffer).ok_or("truncated payload")? as usize];
+        buffer
+            .read_exact(&mut record)
+            .map_err(|_| "truncated record")?;
+        records.push(read(&mut Cursor::new(record.as_slice()))?);
+    }
+    Ok(records)
+}
+
+fn read_videos(buffer: &mut Cursor<&[u8]>) -> Result<Vec<YoutubeMusicVideoRef>, String> {
+    read_records(buffer, read_record_video)
+}
+
+/// Reads a video stored in a length-prefixed record
+fn read_record_video(record: &mut Cursor<&[u8]>) -> Result<YoutubeMusicVideoRef, String> {
+    let mut video = read_video(record).ok_or("invalid video")?;
+    // Fields are only ever appended to a record, so older records miss the last ones
+    if !is_exhausted(record) {
+        video.thumbnail = read_str(record).ok_or("invalid video")?;
+    }
+    if !is_exhausted(record) {
+        let seconds = read_u32(record).ok_or("invalid video")?;
+        video.duration = (seconds > 0).then_some(seconds);
+    }
+    if !is_exhausted(record) {
+        video.artist_id = read_str(record).ok_or("invalid video")?;
+    }
+    Ok(video)
+}
+
+fn read_ratings(buffer: &mut Cursor<&[u8]>) -> Result<Vec<(YoutubeMusicVideoRef, Rating)>, String> {
+    read_records(buffer, read_record_rating)
+}
+
+fn read_record_rating(
+    record: &mut Cursor<&[u8]>,
+) -> Result<(YoutubeMusicVideoRef, Rating), String> {
+    let invalid = || "invalid rating".to_owned();
+    let rating = Rating {
+        favorite: read_u32(record).ok_or_else(invalid)? != 0,
+        stars: read_u32(record).ok_or_else(invalid)?.min(5) as u8,
+    };
+    Ok((read_record_video(record)?, rating))
+}
+
+fn read_all_stats(buffer: &mut Cursor<&[u8]>) -> Result<HashMap<String, PlayStats>, String> {
+    Ok(read_records(buffer, read_record_stats)?
+        .into_iter()
+        .collect())
+}
+
+fn read_record_stats(record: &mut Cursor<&[u8]>) -> Result<(String, PlayStats), String> {
+    let invalid = || "invalid play statistics".to_owned();
+    let video_id = read_str(record).ok_or_else(invalid)?;
+    Ok((
+        video_id,
+        PlayStats {
+            play_count: read_u32(record).ok_or_else(invalid)?,
+            skip_count: read_u32(record).ok_or_else(invalid)?,
+            last_played: read_u64(record).ok_or_else(invalid)?,
+            listening_time: read_u64(record).ok_or_else(invalid)?,
+        },
+    ))
+}
+
+fn is_exhausted(cursor: &Cursor<&[u8]>) -> bool {
+    remaining(cursor) == 0
+}
+
+fn remaining(cursor: &Cursor<&[u8]>) -> usize {
+    cursor
+        .get_ref()
+        .len()
+        .saturating_sub(cursor.position() as usize)
 }
 
 /// Reads a video from the cursor
-fn read_video(buffer: &mut Cursor<Vec<u8>>) -> Option<YoutubeMusicVideoRef> {
+fn read_video(buffer: &mut Cursor<&[u8]>) -> Option<YoutubeMusicVideoRef> {
     Some(YoutubeMusicVideoRef {
         title: read_str(buffer)?,
         author: read_str(buffer)?,
         album: read_str(buffer)?,
         video_id: read_str(buffer)?,
-        duration: read_str(buffer)?,
+        duration: parse_duration(&read_str(buffer)?),
+        thumbnail: String::new(),
+        artist_id: String::new(),
     })
 }
 
 /// Reads a string from the cursor
-fn read_str(cursor: &mut Cursor<Vec<u8>>) -> Option<String> {
-    let mut buf = vec![0u8; read_u32(cursor)? as usize];
+fn read_str(cursor: &mut Cursor<&[u8]>) -> Option<String> {
+    let length = read_u32(cursor)? as usize;
+    if length > remaining(cursor) {
+        return None;
+    }
+    let mut buf = vec![0u8; length];
     cursor.read_exact(&mut buf).ok()?;
     String::from_utf8(buf).ok()
 }
 
 /// Reads a u32 from the cursor
-fn read_u32(cursor: &mut Cursor<Vec<u8>>) -> Option<u32> {
+fn read_u32(cursor: &mut Cursor<&[u8]>) -> Option<u32> {
     ReadVarint::<u32>::read_varint(cursor).ok()
 }
+
+/// Reads a u64 from the cursor
+fn read_u64(cursor: &mut Cursor<&[u8]>) -> Option<u64> {
+    ReadVarint::<u64>::read_varint(cursor).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use varuint::WriteVarint;
+    use ytpapi2::format_duration;
+
+    use super::*;
+    use crate::database::{fixtures::video, writer::encode};
+
+    fn videos() -> Vec<YoutubeMusicVideoRef> {
+        (0..10).map(video).collect()
+    }
+
+    fn database() -> Database {
+        let mut database = Database::from_videos(videos());
+        database.stats_mut("id000000003").play_count = 3;
+        database.set_rating(
+            &videos()[5],
+            Rating {
+                favorite: true,
+                stars: 4,
+            },
+        );
+        database
+    }
+
+    /// Encodes a video like records of version 1, with the duration as text
+    fn encode_legacy_video(buffer: &mut Vec<u8>, video: &YoutubeMusicVideoRef) {
+        for field in [
+            &video.title,
+            &video.author,
+            &video.album,
+            &video.video_id,
+            &video.duration.map(format_duration).unwrap_or_default(),
+        ] {
+            buffer.write_varint(field.len() as u32).unwrap();
+            buffer.write_all(field.as_bytes()).unwrap();
+        }
+    }
+
+    /// Encodes the videos like databases written before the header existed
+    fn encode_legacy(videos: &[YoutubeMusicVideoRef]) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for video in videos {
+            encode_legacy_video(&mut buffer, video);
+        }
+        buffer
+    }
+
+    #[test]
+    fn reads_legacy_database() {
+        let videos = videos();
+        assert_eq!(
+            decode(&encode_legacy(&videos)),
+            Ok((0, Database::from_videos(videos)))
+        );
+    }
+
+    #[test]
+    fn migrates_legacy_database() {
+        let videos = videos();
+        let (version, migrated) = decode(&encode_legacy(&videos)).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(
+            decode(&encode(&migrated)),
+            Ok((VERSION, Database::from_videos(videos)))
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_legacy_database() {
+        let encoded = encode_legacy(&videos());
+        assert!(decode(&encoded[..encoded.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_database() {
+        let encoded = encode(&database());
+        for length in MAGIC.len()..encoded.len() {
+            assert!(decode(&encoded[..length]).is_err(), "length {length}");
+        }
+    }
+
+    #[test]
+    fn rejects_garbage_at_the_tail() {
+        let mut encoded = encode(&database());
+        encoded.extend_from_slice(b"garbage");
+        assert!(decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn rejects_flipped_byte() {
+        let encoded = encode(&database());
+        for i in MAGIC.len() + 1..encoded.len() {
+            let mut corrupted = encoded.clone();
+            corrupted[i] ^= 0x20;
+            assert!(decode(&corrupted).is_err(), "byte {i}");
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let mut encoded = encode(&database());
+        encoded[MAGIC.len()] = 42;
+        assert_eq!(decode(&encoded), Err("unsupported version 42".to_owned()));
+    }
+
+    #[test]
+    fn reads_version_1_database() {
+        let video = &videos()[0];
+        let mut record = Vec::new();
+        encode_legacy_video(&mut record, video);
+        let mut payload = Vec::new();
+        payload.write_varint(1u32).unwrap();
+        payload.write_varint(record.len() as u32).unwrap();
+        payload.extend_from_slice(&record);
+        let mut encoded = MAGIC.to_vec();
+        encoded.write_varint(1u32).unwrap();
+        encoded.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        encoded.extend_from_slice(&payload);
+        assert_eq!(
+            decode(&encoded),
+            Ok((1, Database::from_videos(vec![video.clone()])))
+        );
+    }
+
+    #[test]
+    fn reads_version_2_database() {
+        let mut database = Database::from_videos(videos());
+        database.stats_mut("id000000003").play_count = 3;
+        // Version 2 is version 3 without the ratings, which are an empty list here
+        let encoded = encode(&database);
+        let payload = &encoded[MAGIC.len() + 5..encoded.len() - 1];
+        let mut encoded = MAGIC.to_vec();
+        encoded.write_varint(2u32).unwrap();
+        encoded.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+        encoded.extend_from_slice(payload);
+        assert_eq!(decode(&encoded), Ok((2, database)));
+    }
+
+    #[test]
+    fn salvages_valid_database() {
+        let database = database();
+        let salvaged = salvage(&encode(&database));
+        assert_eq!(salvaged.videos, videos());
+        assert_eq!(&salvaged.stats, database.all_stats());
+        assert_eq!(salvaged.ratings.len(), 1);
+        assert_eq!(salvaged.dropped, 0);
+    }
+
+    #[test]
+    fn salvage_skips_invalid_records() {
+        let mut encoded = encode(&database());
+        // The first byte of the title of the first video: magic, version, checksum,
+        // count, record length then title length
+        encoded[MAGIC.len() + 8] = 0xFF;
+        assert!(decode(&encoded).is_err());
+        let salvaged = salvage(&encoded);
+        assert_eq!(salvaged.videos, videos()[1..]);
+        assert_eq!(salvaged.stats.len(), 1);
+        assert_eq!(salvaged.ratings.len(), 1);
+        assert_eq!(salvaged.dropped, 1);
+    }
+
+    #[test]
+    fn salvage_stops_at_truncation() {
+        let encoded = encode(&database());
+        let salvaged = salvage(&encoded[..encoded.len() / 2]);
+        assert!(!salvaged.videos.is_empty());
+        assert_eq!(salvaged.videos, videos()[..salvaged.videos.len()]);
+        assert_eq!(salvaged.dropped, 1);
+    }
+
+    #[test]
+    fn salvages_truncated_legacy_database() {
+        let encoded = encode_legacy(&videos());
+        let salvaged = salvage(&encoded[..encoded.len() - 3]);
+        assert_eq!(salvaged.videos, videos()[..9]);
+        assert_eq!(salvaged.dropped, 1);
+    }
+}
@@ -0,0 +1,28 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What we know about how a track is listened to
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PlayStats {
+    /// Number of times the track was listened to the end
+    pub play_count: u32,
+    /// Number of times the track was skipped before its end
+    pub skip_count: u32,
+    /// When the track was last started, in seconds since the Unix epoch (0 if never)
+    pub last_played: u64,
+    /// Total time spent listening to the track, in seconds
+    pub listening_time: u64,
+}
+
+impl PlayStats {
+    pub fn never_played(&self) -> bool {
+        self.last_played == 0
+    }
+}
+
+/// The current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default()
+}
@@ -1,31 +1,124 @@
-use std::{fs::OpenOptions, io::Write};
+use std::{
+    fs::File,
+    io::Write,
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
 
+use log::{error, warn};
 use varuint::WriteVarint;
 use ytpapi2::YoutubeMusicVideoRef;
 
 use crate::consts::CACHE_DIR;
 
-/// Writes the database to the disk
+use super::{rating::Rating, stats::PlayStats, Database, MAGIC, VERSION};
+
+/// Held for the whole of a write, so two writers never share the temporary file
+static WRITING: Mutex<()> = Mutex::new(());
+
+/// Writes the database to the disk, once it was read from it
 pub fn write() {
-    let db = super::DATABASE.read().unwrap();
-    let mut file = OpenOptions::new()
-        .write(true)
-        .append(false)
-        .create(true)
-        .open(CACHE_DIR.join("db.bin"))
-        .unwrap();
-    for video in db.iter() {
-        write_video(&mut file, video)
+    if !super::is_loaded() {
+        // The empty database would replace the one on the disk
+        warn!("Database not written: it isn't loaded yet");
+        return;
+    }
+    let _writing = WRITING.lock().unwrap_or_else(PoisonError::into_inner);
+    // Read once the previous write is over, so the last write has the last state
+    let encoded = encode(
+        &super::DATABASE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner),
+    );
+    if let Err(e) = replace(&CACHE_DIR.join("db.bin"), &encoded) {
+        error!("Can't write database: {e}");
     }
 }
 
+/// Writes the database to a temporary file which then replaces the database file,
+/// so a crash can't leave a partially written database behind
+pub fn write_to(path: &Path, database: &Database) -> std::io::Result<()> {
+    let encoded = encode(database);
+    let _writing = WRITING.lock().unwrap_or_else(PoisonError::into_inner);
+    replace(path, &encoded)
+}
+
+fn replace(path: &Path, encoded: &[u8]) -> std::io::Result<()> {
+    let temporary = path.with_extension("bin.tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(encoded)?;
+    file.sync_all()?;
+    std::fs::rename(temporary, path)
+}
+
+/// Encodes the database in the current format:
+/// the magic, the version, the CRC32 of the payload then the payload.
+/// The payload is the number of videos followed by the length-prefixed videos,
+/// then the number of play statistics followed by the length-prefixed statistics,
+/// then the number of ratings followed by the length-prefixed ratings.
+pub fn encode(database: &Database) -> Vec<u8> {
+    let mut payload = Vec::new();
+    let mut record = Vec::new();
+    write_u32(&mut payload, database.len() as u32);
+    for video in database {
+        record.clear();
+        write_video(&mut record, video);
+        write_record(&mut payload, &record);
+    }
+    write_u32(&mut payload, database.all_stats().len() as u32);
+    for (video_id, stats) in database.all_stats() {
+        record.clear();
+        write_stats(&mut record, video_id, stats);
+        write_record(&mut payload, &record);
+    }
+    let ratings = database.rated().collect::<Vec<_>>();
+    write_u32(&mut payload, ratings.len() as u32);
+    for (video, rating) in ratings {
+        record.clear();
+        write_rating(&mut record, video, rating);
+        write_record(&mut payload, &record);
+    }
+    let mut buffer = Vec::with_capacity(payload.len() + 16);
+    buffer.extend_from_slice(MAGIC);
+    write_u32(&mut buffer, VERSION);
+    buffer.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    buffer.extend_from_slice(&payload);
+    buffer
+}
+
+/// Writes a length-prefixed record
+fn write_record(buffer: &mut Vec<u8>, record: &[u8]) {
+    write_u32(buffer, record.len() as u32);
+    buffer.extend_from_slice(record);
+}
+
 /// Writes a video to a file
-pub fn write_video(buffer: &mut impl Write, video: &YoutubeMusicVideoRef) {
+fn write_video(buffer: &mut impl Write, video: &YoutubeMusicVideoRef) {
     write_str(buffer, &video.title);
     write_str(buffer, &video.author);
     write_str(buffer, &video.album);
     write_str(buffer, &video.video_id);
-    write_str(buffer, &video.duration);
+    // Durations used to be stored as text, they now follow the thumbnail
+    write_str(buffer, "");
+    write_str(buffer, &video.thumbnail);
+    write_u32(buffer, video.duration.unwrap_or_default());
+    write_str(buffer, &video.artist_id);
+}
+
+/// Writes the play statistics of a video
+fn write_stats(buffer: &mut impl Write, video_id: &str, stats: &PlayStats) {
+    write_str(buffer, video_id);
+    write_u32(buffer, stats.play_count);
+    write_u32(buffer, stats.skip_count);
+    write_u64(buffer, stats.last_played);
+    write_u64(buffer, stats.listening_time);
+}
+
+/// Writes a rating followed by the rated video, so the video can still get new fields
+fn write_rating(buffer: &mut impl Write, video: &YoutubeMusicVideoRef, rating: Rating) {
+    write_u32(buffer, rating.favorite as u32);
+    write_u32(buffer, rating.stars as u32);
+    write_video(buffer, video);
 }
 
 /// Writes a string from the cursor
@@ -38,3 +131,102 @@ fn write_str(cursor: &mut impl Write, value: &str) {
 fn write_u32(cursor: &mut impl Write, value: u32) {
     cursor.write_varint(value).unwrap();
 }
+
+/// Writes a u64 from the cursor
+fn write_u64(cursor: &mut impl Write, value: u64) {
+    cursor.write_varint(value).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{fixtures, reader::decode};
+
+    /// The database keeps the thumbnails and the artist ids, unlike the other formats
+    fn video(i: usize) -> YoutubeMusicVideoRef {
+        YoutubeMusicVideoRef {
+            thumbnail: format!("https://example.com/{i}.jpg"),
+            artist_id: format!("UC{i}"),
+            ..fixtures::video(i)
+        }
+    }
+
+    fn database(size: usize) -> Database {
+        let mut database = Database::from_videos((0..size).map(video).collect());
+        // Statistics of videos that aren't in the cache anymore are kept too
+        for i in (0..size + 10).step_by(3) {
+            *database.stats_mut(&video(i).video_id) = PlayStats {
+                play_count: i as u32,
+                skip_count: 1,
+                last_played: 1_700_000_000 + i as u64,
+                listening_time: 300 * i as u64,
+            };
+        }
+        // So are ratings
+        for i in (0..size + 10).step_by(4) {
+            database.set_rating(
+                &video(i),
+                Rating {
+                    favorite: i.is_multiple_of(8),
+                    stars: (i % 6) as u8,
+                },
+            );
+        }
+        database
+    }
+
+    #[test]
+    fn round_trip() {
+        let database = database(100);
+        assert_eq!(decode(&encode(&database)), Ok((VERSION, database)));
+    }
+
+    #[test]
+    fn round_trip_empty() {
+        let database = Database::default();
+        assert_eq!(decode(&encode(&database)), Ok((VERSION, database)));
+    }
+
+    #[test]
+    fn starts_with_header() {
+        let encoded = encode(&database(1));
+        assert!(encoded.starts_with(MAGIC));
+        assert_eq!(encoded[MAGIC.len()], VERSION as u8);
+    }
+
+    #[test]
+    fn write_to_replaces_longer_database() {
+        let dir = std::env::temp_dir().join(format!("ytermusic-db-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("db.bin");
+        write_to(&path, &database(50)).unwrap();
+        write_to(&path, &database(3)).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(decode(&bytes), Ok((VERSION, database(3))));
+        assert!(!path.with_extension("bin.tmp").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_writes_stay_valid() {
+        let dir = std::env::temp_dir().join(format!(
+            "ytermusic-db-concurrent-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("db.bin");
+        std::thread::scope(|scope| {
+            for size in 1..9 {
+                let path = &path;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        write_to(path, &database(size * 20)).unwrap();
+                    }
+                });
+            }
+        });
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(decode(&bytes).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
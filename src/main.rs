@@ -17,6 +17,7 @@ use systems::{logger::init, player::player_system};
 
 use crate::{consts::HEADER_TUTORIAL, systems::logger::get_log_file_path, utils::get_project_dirs};
 
+mod cli;
 mod config;
 mod consts;
 mod database;
@@ -51,6 +52,7 @@ where
 }
 
 fn shutdown() {
+    database::flush();
     for _ in 0..1000 {
         SIGNALING_STOP.0.send(()).unwrap();
     }
@@ -104,6 +106,10 @@ async fn app_start() {
 
     std::fs::create_dir_all(CACHE_DIR.join("downloads")).unwrap();
 
+    if cli::handle_args() {
+        return;
+    }
+
     if let Err((error, filepath)) = get_header_file() {
         println!("Can't read or find `{}`", filepath.display());
         println!("Error: {error}");
@@ -113,11 +119,11 @@ async fn app_start() {
 
     STARTUP_TIME.log("Startup");
 
-    // Spawn the clean task
+    // Clean before the downloads start, the files are verified once the database is loaded
     let (updater_s, updater_r) = flume::unbounded::<ManagerMessage>();
-    tasks::clean::spawn_clean_task();
+    tasks::clean::clean();
 
-    STARTUP_TIME.log("Spawned clean task");
+    STARTUP_TIME.log("Cleaned downloads");
     // Spawn the player task
     let (sa, player) = player_system(updater_s.clone());
     // Spawn the downloader system
@@ -125,13 +131,19 @@ async fn app_start() {
     STARTUP_TIME.log("Spawned system task");
     tasks::last_playlist::spawn_last_playlist_task(updater_s.clone());
     STARTUP_TIME.log("Spawned last playlist task");
+    tasks::offline::spawn_offline_playlists_task(updater_s.clone());
+    STARTUP_TIME.log("Spawned offline playlists task");
+    tasks::local_playlists::spawn_local_playlists_task(updater_s.clone());
+    STARTUP_TIME.log("Spawned local playlists task");
     // Spawn the API task
     tasks::api::spawn_api_task(updater_s.clone());
     STARTUP_TIME.log("Spawned api task");
     // Spawn the database getter task
     tasks::local_musics::spawn_local_musics_task(updater_s);
+    database::spawn_flush_task();
 
     STARTUP_TIME.log("Running manager");
     let mut manager = Manager::new(sa, player).await;
     manager.run(&updater_r).unwrap();
+    database::flush();
 }
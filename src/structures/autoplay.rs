@@ -0,0 +1,129 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use log::info;
+use rand::seq::SliceRandom;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{consts::CONFIG, database::now, DATABASE};
+
+/// Related tracks are added when fewer tracks than this are left in the queue
+const REMAINING: usize = 3;
+/// Number of the last played tracks the radio can be started from
+const SEEDS: usize = 5;
+/// Tracks played less than this many seconds ago aren't added again
+const RECENTLY_PLAYED: u64 = 3 * 60 * 60;
+/// Time to wait before looking again when no new related track was found
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Continues with tracks related to the last played ones when the queue runs out
+pub struct Autoplay {
+    pub enabled: bool,
+    /// Whether related tracks are being fetched
+    fetching: bool,
+    /// Changes when the queue is replaced, so the tracks fetched for the previous queue
+    /// are dropped
+    generation: u64,
+    /// No tracks are fetched before this time
+    next_attempt: Option<Instant>,
+    /// The videos of the queue added by autoplay
+    added: HashSet<String>,
+}
+
+impl Default for Autoplay {
+    fn default() -> Self {
+        Self {
+            enabled: CONFIG.player.autoplay,
+            fetching: false,
+            generation: 0,
+            next_attempt: None,
+            added: HashSet::new(),
+        }
+    }
+}
+
+impl Autoplay {
+    /// The video to start a radio from when the queue is running out with the generation
+    /// to fetch its tracks for, `None` when no tracks have to be fetched
+    pub fn seed(
+        &mut self,
+        queue: &VecDeque<YoutubeMusicVideoRef>,
+        current: Option<&YoutubeMusicVideoRef>,
+        previous: &[YoutubeMusicVideoRef],
+    ) -> Option<(YoutubeMusicVideoRef, u64)> {
+        if !self.enabled || self.fetching || queue.len() >= REMAINING {
+            return None;
+        }
+        if self.next_attempt.is_some_and(|x| Instant::now() < x) {
+            return None;
+        }
+        let seeds = current
+            .into_iter()
+            .chain(previous.iter().rev())
+            .take(SEEDS)
+            .collect::<Vec<_>>();
+        let seed = (*seeds.choose(&mut rand::thread_rng())?).clone();
+        self.fetching = true;
+        Some((seed, self.generation))
+    }
+
+    /// Keeps the related tracks that weren't played recently and aren't queued yet,
+    /// and remembers them as added by autoplay.
+    /// Every track is dropped when they were fetched before the queue was replaced.
+    pub fn filter(
+        &mut self,
+        generation: u64,
+        videos: Vec<YoutubeMusicVideoRef>,
+        queue: &VecDeque<YoutubeMusicVideoRef>,
+        current: Option<&YoutubeMusicVideoRef>,
+        previous: &[YoutubeMusicVideoRef],
+    ) -> Vec<YoutubeMusicVideoRef> {
+        if generation != self.generation {
+            return Vec::new();
+        }
+        self.fetching = false;
+        let mut seen = queue
+            .iter()
+            .chain(current)
+            .chain(previous)
+            .map(|x| x.video_id.clone())
+            .collect::<HashSet<_>>();
+        let db = DATABASE.read().unwrap();
+        let now = now();
+        let videos = videos
+            .into_iter()
+            .filter(|x| {
+                now.saturating_sub(db.stats(&x.video_id).last_played) >= RECENTLY_PLAYED
+                    && seen.insert(x.video_id.clone())
+            })
+            .collect::<Vec<_>>();
+        if videos.is_empty() {
+            info!("Autoplay found no new track");
+            self.next_attempt = Some(Instant::now() + RETRY_DELAY);
+        } else {
+            self.next_attempt = None;
+            self.added.extend(videos.iter().map(|x| x.video_id.clone()));
+        }
+        videos
+    }
+
+    /// Whether the video was added to the queue by autoplay
+    pub fn is_added(&self, video_id: &str) -> bool {
+        self.added.contains(video_id)
+    }
+
+    /// Forgets the added videos and the tracks being fetched when the queue is replaced
+    pub fn clear(&mut self) {
+        self.added.clear();
+        self.next_attempt = None;
+        self.fetching = false;
+        self.generation += 1;
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.next_attempt = None;
+    }
+}
@@ -0,0 +1,84 @@
+use flume::Sender;
+use log::info;
+use player::Player;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{database, tasks::history, term::ManagerMessage};
+
+/// Share of a track that has to be reached for it to count as played rather than skipped
+const COMPLETION_RATIO: f64 = 0.9;
+
+struct Session {
+    video: YoutubeMusicVideoRef,
+    /// Last position seen, in seconds
+    position: u32,
+    /// Seconds actually listened to, seeking doesn't count
+    listened: u64,
+    duration: Option<f64>,
+    ended: bool,
+}
+
+impl Session {
+    fn end(&mut self, updater: &Sender<ManagerMessage>) {
+        if self.ended {
+            return;
+        }
+        self.ended = true;
+        let completed = self
+            .duration
+            .map(|duration| self.position as f64 >= duration * COMPLETION_RATIO)
+            .unwrap_or(false);
+        if completed {
+            history::report_play(&self.video, updater);
+        } else {
+            info!("Skipped {} at {}s", self.video.video_id, self.position);
+        }
+        database::record_listening(&self.video.video_id, self.listened, completed);
+    }
+}
+
+/// Follows what the player plays to record the play statistics of the tracks
+#[derive(Default)]
+pub struct Listening {
+    session: Option<Session>,
+}
+
+impl Listening {
+    pub fn update(
+        &mut self,
+        current: Option<&YoutubeMusicVideoRef>,
+        sink: &Player,
+        updater: &Sender<ManagerMessage>,
+    ) {
+        let current_id = current.map(|x| x.video_id.as_str());
+        if self.session.as_ref().map(|x| x.video.video_id.as_str()) != current_id {
+            if let Some(mut session) = self.session.take() {
+                session.end(updater);
+            }
+            if let Some(video) = current {
+                database::record_start(&video.video_id);
+                self.session = Some(Session {
+                    video: video.clone(),
+                    position: 0,
+                    listened: 0,
+                    duration: None,
+                    ended: false,
+                });
+            }
+        }
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        if sink.is_finished() {
+            session.end(updater);
+            return;
+        }
+        let position = sink.elapsed();
+        // Playing moves forward a second at a time, bigger steps are seeks
+        if position > session.position && position - session.position <= 2 {
+            session.listened += (position - session.position) as u64;
+        }
+        session.position = position;
+        session.duration = sink.duration().or(session.duration);
+    }
+}
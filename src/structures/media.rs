@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use flume::Sender;
 use log::{error, info};
@@ -9,15 +9,20 @@ use souvlaki::{
 };
 use ytpapi2::YoutubeMusicVideoRef;
 
-use crate::{consts::CONFIG, shutdown, term::ManagerMessage};
+use crate::{consts::CONFIG, shutdown, tasks::artwork, term::ManagerMessage};
 
 use super::sound_action::SoundAction;
 
+/// Time between two looks for the cover of the current track while it isn't downloaded
+const COVER_RETRY: Duration = Duration::from_secs(2);
+
 pub struct Media {
     controls: Option<MediaControls>,
 
-    current_meta: Option<(String, String, String)>,
+    current_meta: Option<(String, String, String, Option<String>)>,
     current_playback: Option<MediaPlayback>,
+    /// The cover URL of the track it was looked for, and when
+    cover: Option<(String, Option<String>, Instant)>,
 }
 
 impl Media {
@@ -28,6 +33,7 @@ impl Media {
                 controls: None,
                 current_meta: None,
                 current_playback: None,
+                cover: None,
             };
         }
         let mut handle = get_handle(&updater);
@@ -42,6 +48,23 @@ impl Media {
             controls: handle,
             current_meta: None,
             current_playback: None,
+            cover: None,
+        }
+    }
+
+    /// The cover URL of the track, looked for again only while the cover isn't downloaded
+    fn cover_url(&mut self, video_id: &str) -> Option<String> {
+        match &self.cover {
+            Some((id, url, checked))
+                if id == video_id && (url.is_some() || checked.elapsed() < COVER_RETRY) =>
+            {
+                url.clone()
+            }
+            _ => {
+                let url = artwork::cover_url(video_id);
+                self.cover = Some((video_id.to_owned(), url.clone(), Instant::now()));
+                url
+            }
         }
     }
 
@@ -50,25 +73,36 @@ impl Media {
         current: &Option<YoutubeMusicVideoRef>,
         sink: &Player,
     ) -> Result<(), souvlaki::Error> {
+        if self.controls.is_none() {
+            return Ok(());
+        }
+        let cover_url = current
+            .as_ref()
+            .and_then(|video| self.cover_url(&video.video_id));
         if let Some(e) = &mut self.controls {
             let media_meta = MediaMetadata {
                 title: current.as_ref().map(|video| video.title.as_str()),
                 album: current.as_ref().map(|video| video.album.as_str()),
                 artist: current.as_ref().map(|video| video.author.as_str()),
-                cover_url: None,
-                duration: None,
+                cover_url: cover_url.as_deref(),
+                duration: current
+                    .as_ref()
+                    .and_then(|video| video.duration)
+                    .map(|seconds| Duration::from_secs(seconds.into())),
             };
             if self.current_meta
                 != Some((
                     media_meta.title.unwrap_or("").to_string(),
                     media_meta.album.unwrap_or("").to_string(),
                     media_meta.artist.unwrap_or("").to_string(),
+                    cover_url.clone(),
                 ))
             {
                 self.current_meta = Some((
                     media_meta.title.unwrap_or("").to_string(),
                     media_meta.album.unwrap_or("").to_string(),
                     media_meta.artist.unwrap_or("").to_string(),
+                    cover_url.clone(),
                 ));
                 e.set_metadata(media_meta)?;
             }
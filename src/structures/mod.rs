@@ -1,5 +1,8 @@
 pub mod app_status;
+pub mod autoplay;
+pub mod listening;
 pub mod media;
 pub mod music_status;
 pub mod performance;
+pub mod playlist;
 pub mod sound_action;
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use ytpapi2::YoutubeMusicVideoRef;
+
+/// A playlist of the chooser. Its id doesn't change with its name, which may contain
+/// a track count, and tells apart playlists with the same name from different sources:
+/// the browse id for YouTube Music and a prefixed name for the local ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    pub videos: Vec<YoutubeMusicVideoRef>,
+}
+
+impl Playlist {
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        videos: Vec<YoutubeMusicVideoRef>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            videos,
+        }
+    }
+}
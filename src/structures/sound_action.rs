@@ -23,6 +23,9 @@ pub enum SoundAction {
     AddVideosToQueue(Vec<YoutubeMusicVideoRef>),
     AddVideoUnary(YoutubeMusicVideoRef),
     ReplaceQueue(Vec<YoutubeMusicVideoRef>),
+    /// Related tracks fetched for autoplay with the generation they were fetched for,
+    /// the ones already heard are left out
+    Autoplay(u64, Vec<YoutubeMusicVideoRef>),
     VideoStatusUpdate(String, MusicDownloadStatus),
 }
 
@@ -53,6 +56,7 @@ impl SoundAction {
                 player.previous.clear();
                 player.current = None;
                 player.music_status.clear();
+                player.autoplay.clear();
                 handle_error(
                     &player.updater,
                     "sink stop",
@@ -84,7 +88,7 @@ impl SoundAction {
                     Self::insert(
                         player,
                         v.video_id.clone(),
-                        if db.iter().any(|e| e.video_id == v.video_id) {
+                        if db.contains(&v.video_id) {
                             MusicDownloadStatus::Downloaded
                         } else {
                             MusicDownloadStatus::NotDownloaded
@@ -120,12 +124,7 @@ impl SoundAction {
                 Self::insert(
                     player,
                     video.video_id.clone(),
-                    if DATABASE
-                        .read()
-                        .unwrap()
-                        .iter()
-                        .any(|e| e.video_id == video.video_id)
-                    {
+                    if DATABASE.read().unwrap().contains(&video.video_id) {
                         MusicDownloadStatus::Downloaded
                     } else {
                         MusicDownloadStatus::NotDownloaded
@@ -135,10 +134,21 @@ impl SoundAction {
             }
             Self::ReplaceQueue(videos) => {
                 player.queue.clear();
+                player.autoplay.clear();
                 download::clean(&player.soundaction_sender);
                 Self::AddVideosToQueue(videos).apply_sound_action(player);
                 Self::Next(1).apply_sound_action(player);
             }
+            Self::Autoplay(generation, videos) => {
+                let videos = player.autoplay.filter(
+                    generation,
+                    videos,
+                    &player.queue,
+                    player.current.as_ref(),
+                    &player.previous,
+                );
+                Self::AddVideosToQueue(videos).apply_sound_action(player);
+            }
         }
     }
 }
@@ -8,15 +8,22 @@ use ytpapi2::YoutubeMusicVideoRef;
 use crate::{
     run_service,
     structures::sound_action::SoundAction,
-    tasks::download::{start_download, IN_DOWNLOAD},
+    tasks::{
+        download::{start_download, IN_DOWNLOAD},
+        offline,
+    },
 };
 
 pub static HANDLES: Lazy<Mutex<Vec<JoinHandle<()>>>> = Lazy::new(|| Mutex::new(Vec::new()));
 pub static DOWNLOAD_LIST: Lazy<Mutex<VecDeque<YoutubeMusicVideoRef>>> =
     Lazy::new(|| Mutex::new(VecDeque::new()));
+/// Low priority downloads (playlists available offline and quarantined files)
+pub static LOW_PRIORITY_LIST: Lazy<Mutex<VecDeque<YoutubeMusicVideoRef>>> =
+    Lazy::new(|| Mutex::new(VecDeque::new()));
 
 fn take() -> Option<YoutubeMusicVideoRef> {
-    DOWNLOAD_LIST.lock().unwrap().pop_front()
+    let next = DOWNLOAD_LIST.lock().unwrap().pop_front();
+    next.or_else(|| LOW_PRIORITY_LIST.lock().unwrap().pop_front())
 }
 
 /// A worker of this system that downloads pending songs
@@ -45,6 +52,8 @@ pub fn clean(sender: &Sender<SoundAction>) {
         handle.clear();
     }
     spawn_system(sender);
+    // Aborted offline downloads have to be queued again
+    offline::queue_downloads();
 }
 
 const DOWNLOADER_COUNT: usize = 4;
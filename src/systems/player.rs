@@ -10,19 +10,27 @@ use ratatui::style::Style;
 use ytpapi2::YoutubeMusicVideoRef;
 
 use crate::{
-    consts::{CACHE_DIR, CONFIG},
+    consts::CONFIG,
     database,
     errors::{handle_error, handle_error_option},
-    structures::{app_status::MusicDownloadStatus, media::Media, sound_action::SoundAction},
+    structures::{
+        app_status::MusicDownloadStatus, autoplay::Autoplay, listening::Listening, media::Media,
+        sound_action::SoundAction,
+    },
+    tasks::{integrity, radio},
     term::{
+        cover::CoverArt,
+        duration_column,
         list_selector::{ListSelector, ListSelectorAction},
+        lyrics::LyricsPanel,
         playlist::PLAYER_RUNNING,
         ManagerMessage, Screens,
     },
-    utils::invert,
+    utils::{audio_path, invert},
+    DATABASE,
 };
 
-use super::download::DOWNLOAD_LIST;
+use super::download::{DOWNLOAD_LIST, LOW_PRIORITY_LIST};
 
 pub enum PlayerAction {
     Current(MusicDownloadStatus, bool), // Is paused
@@ -59,6 +67,10 @@ pub struct PlayerState {
     pub previous: Vec<YoutubeMusicVideoRef>,
     pub music_status: HashMap<String, MusicDownloadStatus>,
     pub list_selector: ListSelector<PlayerAction>,
+    pub cover: CoverArt,
+    pub lyrics: LyricsPanel,
+    pub listening: Listening,
+    pub autoplay: Autoplay,
     pub controls: Media,
     pub sink: Player,
     pub guard: Guard,
@@ -90,6 +102,10 @@ impl PlayerState {
             controls: Media::new(updater.clone(), soundaction_sender.clone()),
             soundaction_receiver,
             list_selector: ListSelector::default(),
+            cover: CoverArt::default(),
+            lyrics: LyricsPanel::default(),
+            listening: Listening::default(),
+            autoplay: Autoplay::default(),
             music_status: HashMap::new(),
             updater,
             stream_error_receiver,
@@ -110,6 +126,12 @@ impl PlayerState {
         while let Ok(e) = self.soundaction_receiver.try_recv() {
             e.apply_sound_action(self);
         }
+        if let Some((seed, generation)) =
+            self.autoplay
+                .seed(&self.queue, self.current.as_ref(), &self.previous)
+        {
+            radio::spawn_radio_task(seed, generation, self.soundaction_sender.clone());
+        }
         if self
             .current
             .as_ref()
@@ -147,29 +169,18 @@ impl PlayerState {
                 .unwrap_or(true)
             {
                 if let Some(video) = self.queue.pop_front() {
-                    let k = CACHE_DIR.join(format!("downloads/{}.mp4", &video.video_id));
+                    let k = audio_path(&video.video_id);
+                    if let Err(e) = integrity::verify_length(&video.video_id) {
+                        self.redownload(video, &e);
+                        return;
+                    }
                     if let Some(e) = self.current.replace(video.clone()) {
                         self.previous.push(e);
                     }
                     if let Err(e) = self.sink.play(k.as_path(), &self.guard) {
-                        if matches!(e, PlayError::DecoderError(_)) {
-                            // Cleaning the file
-
-                            database::remove_video(&video);
-                            handle_error(
-                                &self.updater,
-                                "invalid cleaning MP4",
-                                std::fs::remove_file(k),
-                            );
-                            handle_error(
-                                &self.updater,
-                                "invalid cleaning JSON",
-                                std::fs::remove_file(
-                                    CACHE_DIR.join(format!("downloads/{}.json", &video.video_id)),
-                                ),
-                            );
+                        if let PlayError::DecoderError(e) = e {
                             self.current = None;
-                            crate::write();
+                            self.redownload(video, &e.to_string());
                         } else {
                             self.updater
                                 .send(ManagerMessage::PassTo(
@@ -200,6 +211,61 @@ impl PlayerState {
             }
         }
         *DOWNLOAD_LIST.lock().unwrap() = to_download;
+        self.listening
+            .update(self.current.as_ref(), &self.sink, &self.updater);
+        self.record_decoded_duration();
+    }
+
+    /// Falls back to the duration decoded from the playing file when the API gave none
+    fn record_decoded_duration(&mut self) {
+        let Some(current) = &mut self.current else {
+            return;
+        };
+        if current.duration.is_some() || self.sink.is_finished() {
+            return;
+        }
+        let Some(duration) = self.sink.duration() else {
+            return;
+        };
+        let seconds = duration.round() as u32;
+        current.duration = Some(seconds);
+        database::record_duration(&current.video_id, seconds);
+    }
+
+    /// The known duration of the queue and the part of it left to play, in seconds
+    pub fn queue_time(&self) -> (u32, u32) {
+        let db = DATABASE.read().unwrap();
+        let duration = |video: &YoutubeMusicVideoRef| db.duration(video).unwrap_or_default();
+        let played = self.previous.iter().map(duration).sum::<u32>();
+        let current = self.current.as_ref().map(duration).unwrap_or_default();
+        let next = self.queue.iter().map(duration).sum::<u32>();
+        let elapsed = if self.current.is_some() {
+            self.sink.elapsed().min(current)
+        } else {
+            0
+        };
+        (played + current + next, current - elapsed + next)
+    }
+
+    /// The video of the row highlighted in the queue
+    pub fn highlighted(&self) -> Option<&YoutubeMusicVideoRef> {
+        match self.list_selector.scroll()? {
+            PlayerAction::Current(..) => self.current.as_ref(),
+            PlayerAction::Next(_, i) => self.queue.get(i - 1),
+            PlayerAction::Previous(_, i) => self.previous.get(self.previous.len() - i),
+        }
+    }
+
+    /// Quarantines a corrupted file and downloads it again before playing it
+    fn redownload(&mut self, video: YoutubeMusicVideoRef, reason: &str) {
+        integrity::quarantine(&video, reason);
+        LOW_PRIORITY_LIST
+            .lock()
+            .unwrap()
+            .retain(|x| x.video_id != video.video_id);
+        self.music_status
+            .insert(video.video_id.clone(), MusicDownloadStatus::NotDownloaded);
+        self.queue.push_front(video);
     }
 
     fn handle_stream_errors(&self) {
@@ -227,8 +293,12 @@ pub fn generate_music<'a>(
     previous: &'a [YoutubeMusicVideoRef],
     current: &'a Option<YoutubeMusicVideoRef>,
     sink: &'a Player,
+    autoplay: &'a Autoplay,
 ) -> Vec<(String, PlayerAction)> {
     let mut music = Vec::with_capacity(10 + queue.len() + previous.len());
+    let db = DATABASE.read().unwrap();
+    let glyph = |e: &YoutubeMusicVideoRef| db.rating(&e.video_id).glyph();
+    let duration = |e: &YoutubeMusicVideoRef| duration_column(db.duration(e));
 
     music.extend(previous.iter().rev().enumerate().rev().map(|(i, e)| {
         let status = music_status
@@ -236,7 +306,14 @@ pub fn generate_music<'a>(
             .copied()
             .unwrap_or(MusicDownloadStatus::Downloaded);
         (
-            format!(" {} {} | {}", status.character(None), e.author, e.title),
+            format!(
+                " {} {}{} | {}{}",
+                status.character(None),
+                glyph(e),
+                e.author,
+                e.title,
+                duration(e)
+            ),
             PlayerAction::Previous(status, i + 1),
         )
     }));
@@ -248,7 +325,13 @@ pub fn generate_music<'a>(
         let status = mstatus.character(Some(!sink.is_paused()));
 
         music.push((
-            format!(" {status} {} | {}", e.author, e.title),
+            format!(
+                " {status} {}{} | {}{}",
+                glyph(e),
+                e.author,
+                e.title,
+                duration(e)
+            ),
             PlayerAction::Current(mstatus, sink.is_paused()),
         ));
     }
@@ -258,7 +341,19 @@ pub fn generate_music<'a>(
             .copied()
             .unwrap_or(MusicDownloadStatus::Downloaded);
         (
-            format!(" {} {} | {}", status.character(None), e.author, e.title),
+            format!(
+                " {} {}{} | {}{}{}",
+                status.character(None),
+                glyph(e),
+                e.author,
+                e.title,
+                duration(e),
+                if autoplay.is_added(&e.video_id) {
+                    " [autoplay]"
+                } else {
+                    ""
+                }
+            ),
             PlayerAction::Next(status, i + 1),
         )
     }));
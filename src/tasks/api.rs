@@ -1,17 +1,37 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
 
 use flume::Sender;
-use log::{error, info};
+use log::{error, info, warn};
 use once_cell::sync::Lazy;
 use tokio::task::JoinSet;
-use ytpapi2::{Endpoint, YoutubeMusicInstance, YoutubeMusicPlaylistRef};
+use ytpapi2::{Endpoint, YoutubeMusicInstance, YoutubeMusicPlaylistRef, YoutubeMusicVideoRef};
 
 use crate::{
+    consts::CONFIG,
     get_header_file, run_service,
-    structures::performance,
+    structures::{performance, playlist::Playlist},
+    tasks::{history, likes, offline, remote_playlists, repair},
     term::{ManagerMessage, Screens},
 };
 
+/// Number of times a request failing with a retryable error is tried
+const ATTEMPTS: u32 = 3;
+
+/// Time waited before connecting again when YouTube Music can't be reached
+const RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// The connection of the API task, `None` until it is made or when it failed
+static API: Lazy<RwLock<Option<Arc<YoutubeMusicInstance>>>> = Lazy::new(|| RwLock::new(None));
+
+/// The connection to YouTube Music shared by the tasks, if it is made
+pub fn shared() -> Option<Arc<YoutubeMusicInstance>> {
+    API.read().unwrap().clone()
+}
+
 pub fn get_text_cookies_expired_or_invalid() -> String {
     let (Ok((_, path)) | Err((_, path))) = get_header_file();
     format!(
@@ -20,70 +40,134 @@ pub fn get_text_cookies_expired_or_invalid() -> String {
     )
 }
 
+/// Connects to YouTube Music with the header file and the `api` settings of the config
+pub async fn connect() -> ytpapi2::Result<YoutubeMusicInstance> {
+    let mut builder = YoutubeMusicInstance::builder().base_url(&CONFIG.api.base_url);
+    if CONFIG.api.timeout != 0 {
+        builder = builder.timeout(Duration::from_secs(CONFIG.api.timeout));
+    }
+    if let Some(proxy) = &CONFIG.api.proxy {
+        builder = builder.proxy(proxy);
+    }
+    builder
+        .from_header_file(&get_header_file().unwrap().1)
+        .await
+}
+
+/// Sends a request again after a growing delay while it fails with a retryable error
+pub async fn with_retries<T, F: Future<Output = ytpapi2::Result<T>>>(
+    what: &str,
+    request: impl Fn() -> F,
+) -> ytpapi2::Result<T> {
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Err(e) if e.is_retryable() && attempt < ATTEMPTS => {
+                warn!("{what} failed, retrying: {e}");
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Finds the metadata of a video known only by its id, the first track of its radio
+pub async fn resolve(
+    api: &YoutubeMusicInstance,
+    video_id: &str,
+) -> ytpapi2::Result<Option<YoutubeMusicVideoRef>> {
+    let videos = with_retries("Watch playlist", || api.get_watch_playlist(video_id)).await?;
+    Ok(videos.into_iter().find(|x| x.video_id == video_id))
+}
+
 pub fn spawn_api_task(updater_s: Sender<ManagerMessage>) {
     run_service(async move {
         info!("API task on");
         let guard = performance::guard("API task");
-        let client =
-            YoutubeMusicInstance::from_header_file(get_header_file().unwrap().1.as_path()).await;
+        // A session started offline connects once the network is back
+        let client = loop {
+            match with_retries("Connection to YouTube Music", connect).await {
+                Err(e) if e.is_retryable() => {
+                    warn!("YouTube Music can't be reached, connecting again later: {e}");
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+                client => break client,
+            }
+        };
         match client {
             Ok(api) => {
                 let api = Arc::new(api);
+                *API.write().unwrap() = Some(api.clone());
+                likes::spawn_sync_task();
+                repair::spawn_redownload_task();
+                // Imported tracks without metadata wait for the connection to be downloaded
+                offline::queue_downloads();
+                history::spawn_history_task(updater_s.clone());
                 let mut set = JoinSet::new();
                 let api_ = api.clone();
                 let updater_s_ = updater_s.clone();
                 set.spawn(async move {
-                    let search_results = api_.get_home(2).await;
+                    let search_results = with_retries("get_home", || api_.get_home(2)).await;
                     match search_results {
                         Ok(e) => {
                             for playlist in e.playlists {
                                 spawn_browse_playlist_task(
                                     playlist.clone(),
+                                    false,
                                     api_.clone(),
                                     updater_s_.clone(),
                                 )
                             }
                         }
                         Err(e) => {
-                            error!("get_home {e:?}")
-                        },
+                            error!("get_home -> {e}")
+                        }
                     }
                 });
                 let api_ = api.clone();
                 let updater_s_ = updater_s.clone();
                 set.spawn(async move {
-                    let search_results = api_.get_library(&Endpoint::MusicLikedPlaylists, 2).await;
+                    let search_results = with_retries("MusicLikedPlaylists", || {
+                        api_.get_library(&Endpoint::MusicLikedPlaylists, 2)
+                    })
+                    .await;
                     match search_results {
                         Ok(e) => {
                             for playlist in e {
                                 spawn_browse_playlist_task(
                                     playlist.clone(),
+                                    true,
                                     api_.clone(),
                                     updater_s_.clone(),
                                 )
                             }
                         }
                         Err(e) => {
-                            error!("MusicLikedPlaylists -> {e:?}");
+                            error!("MusicLikedPlaylists -> {e}");
                         }
                     }
                 });
                 let api_ = api.clone();
                 let updater_s_ = updater_s.clone();
                 set.spawn(async move {
-                    let search_results = api_.get_library(&Endpoint::MusicLibraryLanding, 2).await;
+                    let search_results = with_retries("MusicLibraryLanding", || {
+                        api_.get_library(&Endpoint::MusicLibraryLanding, 2)
+                    })
+                    .await;
                     match search_results {
                         Ok(e) => {
                             for playlist in e {
                                 spawn_browse_playlist_task(
                                     playlist.clone(),
+                                    true,
                                     api_.clone(),
                                     updater_s_.clone(),
                                 )
                             }
                         }
                         Err(e) => {
-                            error!("MusicLibraryLanding -> {e:?}");
+                            error!("MusicLibraryLanding -> {e}");
                         }
                     }
                 });
@@ -91,31 +175,22 @@ pub fn spawn_api_task(updater_s: Sender<ManagerMessage>) {
                     e.unwrap();
                 }
             }
-            Err(e) => match &e {
-                ytpapi2::YoutubeMusicError::NoCookieAttribute
-                | ytpapi2::YoutubeMusicError::NoSapsidInCookie
-                | ytpapi2::YoutubeMusicError::InvalidCookie
-                | ytpapi2::YoutubeMusicError::NeedToLogin
-                | ytpapi2::YoutubeMusicError::CantFindInnerTubeApiKey(_)
-                | ytpapi2::YoutubeMusicError::CantFindInnerTubeClientVersion(_)
-                | ytpapi2::YoutubeMusicError::CantFindVisitorData(_)
-                | ytpapi2::YoutubeMusicError::IoError(_) => {
-                    error!("{}", get_text_cookies_expired_or_invalid());
-                    error!("{e:?}");
-                    updater_s
-                        .send(
-                            ManagerMessage::Error(
-                                get_text_cookies_expired_or_invalid(),
-                                Box::new(Some(ManagerMessage::Quit)),
-                            )
-                            .pass_to(Screens::DeviceLost),
+            Err(e) if e.is_auth_error() => {
+                error!("{}", get_text_cookies_expired_or_invalid());
+                error!("{e}");
+                updater_s
+                    .send(
+                        ManagerMessage::Error(
+                            get_text_cookies_expired_or_invalid(),
+                            Box::new(Some(ManagerMessage::Quit)),
                         )
-                        .unwrap();
-                }
-                e => {
-                    error!("{e:?}");
-                }
-            },
+                        .pass_to(Screens::DeviceLost),
+                    )
+                    .unwrap();
+            }
+            Err(e) => {
+                error!("{e}");
+            }
         }
         drop(guard);
     });
@@ -123,8 +198,10 @@ pub fn spawn_api_task(updater_s: Sender<ManagerMessage>) {
 
 static BROWSED_PLAYLISTS: Lazy<Mutex<Vec<(String, String)>>> = Lazy::new(|| Mutex::new(vec![]));
 
+/// Sends the tracks of a playlist to the chooser. The playlists of the `library` can be edited.
 fn spawn_browse_playlist_task(
     playlist: YoutubeMusicPlaylistRef,
+    library: bool,
     api: Arc<YoutubeMusicInstance>,
     updater_s: Sender<ManagerMessage>,
 ) {
@@ -141,22 +218,41 @@ fn spawn_browse_playlist_task(
     run_service(async move {
         let guard = format!("Browse playlist {}", playlist.name);
         let guard = performance::guard(&guard);
-        match api.get_playlist(&playlist, 5).await {
+        let name = format!("{} ({})", playlist.name, playlist.subtitle);
+        let videos = if library {
+            // The ids of the entries tell which one to edit when a video is there several times
+            with_retries(&playlist.name, || api.get_playlist_entries(&playlist, 5))
+                .await
+                .map(|entries| {
+                    let videos = entries
+                        .iter()
+                        .map(|(video, _)| video.clone())
+                        .collect::<Vec<_>>();
+                    remote_playlists::register(
+                        name.clone(),
+                        playlist.browse_id.clone(),
+                        entries
+                            .into_iter()
+                            .map(|(video, set_video_id)| (video, Some(set_video_id)))
+                            .collect(),
+                    );
+                    videos
+                })
+        } else {
+            with_retries(&playlist.name, || api.get_playlist(&playlist, 5)).await
+        };
+        match videos {
             Ok(videos) => {
                 if videos.len() < 2 {
                     info!("Playlist {} is too small so skipped", playlist.name);
                     return;
                 }
-                let _ = updater_s.send(
-                    ManagerMessage::AddElementToChooser((
-                        format!("{} ({})", playlist.name, playlist.subtitle),
-                        videos,
-                    ))
-                    .pass_to(Screens::Playlist),
-                );
+                let playlist = Playlist::new(playlist.browse_id, name, videos);
+                let _ = updater_s
+                    .send(ManagerMessage::AddElementToChooser(playlist).pass_to(Screens::Playlist));
             }
             Err(e) => {
-                error!("{e:?}");
+                error!("{} -> {e}", playlist.name);
             }
         }
 
@@ -0,0 +1,87 @@
+use std::{collections::HashSet, path::PathBuf, sync::Mutex};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{consts::CACHE_DIR, run_service};
+
+use super::playlist_file;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// Videos whose cover has already been requested by the UI
+static REQUESTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Path of the cached cover of a video, stored next to its audio
+pub fn cover_path(video_id: &str) -> PathBuf {
+    CACHE_DIR.join(format!("downloads/{video_id}.jpg"))
+}
+
+/// `file://` URL of the cached cover, used by the media controls
+pub fn cover_url(video_id: &str) -> Option<String> {
+    let path = cover_path(video_id);
+    path.exists().then(|| playlist_file::file_url(&path))
+}
+
+/// URLs the cover can be fetched from, in order of preference
+fn cover_urls(video: &YoutubeMusicVideoRef) -> Vec<String> {
+    let mut urls = Vec::with_capacity(2);
+    if !video.thumbnail.is_empty() {
+        // Google user content thumbnails are resized through their `=w<width>-h<height>` suffix
+        match video.thumbnail.rsplit_once("=w") {
+            Some((base, _)) if video.thumbnail.contains("googleusercontent.com") => {
+                urls.push(format!("{base}=w544-h544-l90-rj"))
+            }
+            _ => urls.push(video.thumbnail.clone()),
+        }
+    }
+    // Every video has this one, but it is letterboxed
+    urls.push(format!(
+        "https://i.ytimg.com/vi/{}/hqdefault.jpg",
+        video.video_id
+    ));
+    urls
+}
+
+async fn fetch(url: &str) -> reqwest::Result<Vec<u8>> {
+    Ok(CLIENT
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?
+        .to_vec())
+}
+
+/// Downloads the cover of a video into the cache and returns it
+pub async fn download_cover(video: &YoutubeMusicVideoRef) -> Option<Vec<u8>> {
+    let path = cover_path(&video.video_id);
+    if let Ok(cover) = std::fs::read(&path) {
+        return Some(cover);
+    }
+    for url in cover_urls(video) {
+        match fetch(&url).await {
+            Ok(cover) => {
+                if let Err(e) = std::fs::write(&path, &cover) {
+                    warn!("Can't cache cover of {}: {e}", video.video_id);
+                }
+                info!("Downloaded cover of {}", video.video_id);
+                return Some(cover);
+            }
+            Err(e) => warn!("Can't download cover of {} from {url}: {e}", video.video_id),
+        }
+    }
+    None
+}
+
+/// Downloads the cover of a track cached before covers were, once per session
+pub fn spawn_cover_task(video: YoutubeMusicVideoRef) {
+    if !REQUESTED.lock().unwrap().insert(video.video_id.clone()) {
+        return;
+    }
+    run_service(async move {
+        download_cover(&video).await;
+    });
+}
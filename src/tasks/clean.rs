@@ -1,20 +1,67 @@
-use crate::{consts::CACHE_DIR, run_service, structures::performance};
+use log::{info, warn};
+use ytpapi2::YoutubeMusicVideoRef;
 
-/// This function is called on start to clean the database and the files
-/// that are incompletely downloaded due to a crash.
-pub fn spawn_clean_task() {
+use crate::{
+    consts::CACHE_DIR,
+    run_service,
+    structures::performance,
+    utils::{audio_path, AUDIO_EXTENSIONS},
+};
+
+use super::integrity;
+
+/// Called on start, before the downloads, to remove the files that are incompletely
+/// downloaded due to a crash and the lyrics of the musics that were only streamed
+pub fn clean() {
+    for i in std::fs::read_dir(CACHE_DIR.join("downloads")).unwrap() {
+        let path = i.unwrap().path();
+        let extension = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default();
+        let video_id = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default();
+        // Lyrics used to be cached for the musics that were only streamed
+        if extension == "part" || (extension == "lyrics" && !audio_path(video_id).exists()) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                warn!("Can't remove {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+/// Called once the database is loaded, so that the files can be removed from it.
+/// Downloaded files that don't match their integrity record are quarantined, only the
+/// files modified since their last check are read again.
+pub fn spawn_verify_task() {
     run_service(async move {
-        let guard = performance::guard("Clean task");
+        let guard = performance::guard("Verify task");
+        let mut verified = 0;
         for i in std::fs::read_dir(CACHE_DIR.join("downloads")).unwrap() {
             let path = i.unwrap().path();
-            if path.ends_with(".mp4") {
-                let mut path1 = path.clone();
-                path1.set_extension("json");
-                if !path1.exists() {
-                    std::fs::remove_file(&path).unwrap();
-                }
+            let extension = path
+                .extension()
+                .and_then(|x| x.to_str())
+                .unwrap_or_default();
+            if !AUDIO_EXTENSIONS.contains(&extension) {
+                continue;
+            }
+            // Files without sidecar are recovered or quarantined by the database repair
+            let Some(video) = std::fs::read_to_string(path.with_extension("json"))
+                .ok()
+                .and_then(|x| serde_json::from_str::<YoutubeMusicVideoRef>(&x).ok())
+            else {
+                continue;
+            };
+            if let Err(e) = integrity::verify(&video.video_id) {
+                integrity::quarantine(&video, &e);
+            } else {
+                verified += 1;
             }
         }
+        info!("Verified {verified} downloaded files");
         drop(guard);
     });
 }
@@ -0,0 +1,77 @@
+use std::{path::Path, process::Stdio};
+
+use tokio::process::Command;
+
+use crate::{config::AudioCodec, consts::CONFIG};
+
+use super::{BoxFuture, DownloadBackend};
+
+/// Builds a `yt-dlp` format selector following the codec and bitrate preferences
+fn format_selector() -> String {
+    let config = &CONFIG.download;
+    let quality = if config.data_saver {
+        "worstaudio"
+    } else {
+        "bestaudio"
+    };
+    let extension = match config.codec {
+        AudioCodec::Aac => "m4a",
+        AudioCodec::Opus => "webm",
+    };
+    let limit = if config.max_bitrate == 0 {
+        String::new()
+    } else {
+        format!("[abr<={}]", config.max_bitrate)
+    };
+    format!("{quality}[ext={extension}]{limit}/{quality}{limit}/{quality}")
+}
+
+/// Runs an external command (like `yt-dlp`) that writes the audio to the given path.
+/// `{video_id}`, `{output}` and `{format}` are replaced in the arguments.
+pub struct ExternalCommandBackend {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl DownloadBackend for ExternalCommandBackend {
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn download<'a>(
+        &'a self,
+        video_id: &'a str,
+        path: &'a Path,
+        progress: &'a (dyn Fn(usize) + Send + Sync),
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let output = path.to_string_lossy();
+            let format = format_selector();
+            let result = Command::new(&self.command)
+                .args(self.args.iter().map(|arg| {
+                    arg.replace("{video_id}", video_id)
+                        .replace("{output}", &output)
+                        .replace("{format}", &format)
+                }))
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|e| format!("can't run `{}`: {e}", self.command))?;
+            if !result.status.success() {
+                let stderr = String::from_utf8_lossy(&result.stderr);
+                return Err(format!(
+                    "`{}` exited with {}: {}",
+                    self.command,
+                    result.status,
+                    stderr.lines().last().unwrap_or_default()
+                ));
+            }
+            if !path.exists() {
+                return Err(format!("`{}` didn't write `{output}`", self.command));
+            }
+            progress(100);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::{io::Write, path::Path};
+
+use crate::{config::AudioCodec, consts::CONFIG};
+
+use super::{BoxFuture, DownloadBackend};
+
+/// Fetches `<url>/<video_id>.mp4` or `<url>/<video_id>.webm` (in the preferred codec first)
+/// from an HTTP directory, like a local mirror of the cache
+pub struct MirrorBackend {
+    pub url: String,
+    pub client: reqwest::Client,
+}
+
+impl DownloadBackend for MirrorBackend {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn download<'a>(
+        &'a self,
+        video_id: &'a str,
+        path: &'a Path,
+        progress: &'a (dyn Fn(usize) + Send + Sync),
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let mut containers = [AudioCodec::Aac, AudioCodec::Opus].map(AudioCodec::container);
+            if CONFIG.download.codec == AudioCodec::Opus {
+                containers.reverse();
+            }
+            let mut last_error = String::new();
+            let mut found = None;
+            for container in containers {
+                let url = format!("{}/{video_id}.{container}", self.url.trim_end_matches('/'));
+                match self
+                    .client
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                {
+                    Ok(response) => {
+                        found = Some(response);
+                        break;
+                    }
+                    Err(e) => last_error = e.to_string(),
+                }
+            }
+            let Some(mut response) = found else {
+                return Err(last_error);
+            };
+            let length = response.content_length().unwrap_or(0);
+            let mut file = std::fs::File::create(path).map_err(|e| e.to_string())?;
+            let mut total = 0;
+            while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+                total += chunk.len() as u64;
+                if length != 0 {
+                    progress((total as f64 / length as f64 * 100.0) as usize);
+                }
+                file.write_all(&chunk).map_err(|e| e.to_string())?;
+            }
+            if length != 0 && total != length {
+                return Err(format!(
+                    "Truncated download: expected {length} bytes but got {total}"
+                ));
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    future::Future,
+    io::Read,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Mutex,
+};
+
+use flume::Sender;
+use log::{error, warn};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    config::DownloadBackendConfig,
+    consts::{CACHE_DIR, CONFIG},
+    run_service,
+    structures::{app_status::MusicDownloadStatus, sound_action::SoundAction},
+    systems::download::HANDLES,
+    utils::AUDIO_EXTENSIONS,
+};
+
+use super::{api, artwork, integrity, local_playlists, lyrics, offline, playlist_file, tags};
+
+mod external;
+mod mirror;
+mod ytdl;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A way to get the audio of a video into the cache
+pub trait DownloadBackend: Send + Sync {
+    /// Name of the backend used in the logs
+    fn name(&self) -> &str;
+
+    /// Downloads the audio of the video to `path`, reporting the progress in percent
+    fn download<'a>(
+        &'a self,
+        video_id: &'a str,
+        path: &'a Path,
+        progress: &'a (dyn Fn(usize) + Send + Sync),
+    ) -> BoxFuture<'a, Result<(), String>>;
+}
+
+impl DownloadBackendConfig {
+    fn build(&self) -> Box<dyn DownloadBackend> {
+        match self {
+            Self::RustyYtdl => Box::new(ytdl::YtdlBackend),
+            Self::External { command, args } => Box::new(external::ExternalCommandBackend {
+                command: command.clone(),
+                args: args.clone(),
+            }),
+            Self::Mirror { url } => Box::new(mirror::MirrorBackend {
+                url: url.clone(),
+                client: reqwest::Client::new(),
+            }),
+        }
+    }
+}
+
+/// The configured backends, tried in order until one succeeds
+static BACKENDS: Lazy<Vec<Box<dyn DownloadBackend>>> = Lazy::new(|| {
+    CONFIG
+        .download
+        .backends
+        .iter()
+        .map(DownloadBackendConfig::build)
+        .collect()
+});
+
+/// Finds the container of a downloaded file from its first bytes
+fn detect_extension(path: &Path) -> std::io::Result<&'static str> {
+    let mut magic = [0; 4];
+    File::open(path)?.read_exact(&mut magic)?;
+    // WebM files start with an EBML header, everything else is treated as MP4
+    if magic == [0x1A, 0x45, 0xDF, 0xA3] {
+        Ok("webm")
+    } else {
+        Ok("mp4")
+    }
+}
+
+/// Downloads the audio to `<id>.part` and renames it after its container once complete
+async fn handle_download(id: &str, sender: Sender<SoundAction>) -> Result<PathBuf, String> {
+    sender
+        .send(SoundAction::VideoStatusUpdate(
+            id.to_string(),
+            MusicDownloadStatus::Downloading(0),
+        ))
+        .unwrap();
+    let file = CACHE_DIR.join("downloads").join(format!("{id}.part"));
+    let progress = |progress: usize| {
+        sender
+            .send(SoundAction::VideoStatusUpdate(
+                id.to_string(),
+                MusicDownloadStatus::Downloading(progress),
+            ))
+            .unwrap();
+    };
+    download_with(&BACKENDS, id, &file, &progress).await
+}
+
+/// Tries the backends in order until one downloads the audio to `file`, then renames it
+/// after its container
+async fn download_with(
+    backends: &[Box<dyn DownloadBackend>],
+    id: &str,
+    file: &Path,
+    progress: &(dyn Fn(usize) + Send + Sync),
+) -> Result<PathBuf, String> {
+    let mut errors = Vec::new();
+    for backend in backends {
+        match backend.download(id, file, progress).await {
+            Ok(()) => {
+                progress(100);
+                let extension = detect_extension(file).map_err(|e| e.to_string())?;
+                let path = file.with_extension(extension);
+                std::fs::rename(file, &path).map_err(|e| e.to_string())?;
+                return Ok(path);
+            }
+            Err(e) => {
+                warn!("Backend {} can't download {id}: {e}", backend.name());
+                errors.push(format!("{}: {e}", backend.name()));
+                if file.exists() {
+                    let _ = std::fs::remove_file(file);
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        return Err("No download backend configured".to_owned());
+    }
+    Err(errors.join(", "))
+}
+
+/// Removes the cached audio of a video whatever its container
+fn remove_audio(video_id: &str) {
+    for extension in AUDIO_EXTENSIONS {
+        let path = CACHE_DIR.join(format!("downloads/{video_id}.{extension}"));
+        if path.exists() {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
+
+pub static IN_DOWNLOAD: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Fetches the metadata of a video imported from its id only, so that it isn't saved
+/// with its id as title, and updates the playlists holding it
+async fn resolve_metadata(song: YoutubeMusicVideoRef) -> Result<YoutubeMusicVideoRef, String> {
+    if !playlist_file::lacks_metadata(&song) {
+        return Ok(song);
+    }
+    let api = api::shared().ok_or("YouTube Music can't be reached to get its metadata")?;
+    let video = api::resolve(&api, &song.video_id)
+        .await
+        .map_err(|e| format!("can't get its metadata: {e}"))?
+        .ok_or("it can't be found on YouTube Music")?;
+    local_playlists::update_video(&video);
+    offline::update_video(&video);
+    Ok(video)
+}
+
+pub async fn start_download(song: YoutubeMusicVideoRef, s: &Sender<SoundAction>) -> bool {
+    {
+        let mut downloads = IN_DOWNLOAD.lock().unwrap();
+        if downloads.contains(&song.video_id) {
+            return false;
+        }
+        downloads.insert(song.video_id.clone());
+    }
+    s.send(SoundAction::VideoStatusUpdate(
+        song.video_id.clone(),
+        MusicDownloadStatus::Downloading(1),
+    ))
+    .unwrap();
+    let download_path_json = CACHE_DIR.join(format!("downloads/{}.json", &song.video_id));
+    if download_path_json.exists() {
+        s.send(SoundAction::VideoStatusUpdate(
+            song.video_id.clone(),
+            MusicDownloadStatus::Downloaded,
+        ))
+        .unwrap();
+        IN_DOWNLOAD.lock().unwrap().remove(&song.video_id);
+        return true;
+    }
+    let song = match resolve_metadata(song.clone()).await {
+        Ok(song) => song,
+        Err(e) => {
+            s.send(SoundAction::VideoStatusUpdate(
+                song.video_id.clone(),
+                MusicDownloadStatus::DownloadFailed,
+            ))
+            .unwrap();
+            error!("Can't download {}: {e}", song.video_id);
+            IN_DOWNLOAD.lock().unwrap().remove(&song.video_id);
+            return false;
+        }
+    };
+    remove_audio(&song.video_id);
+    let downloaded = match handle_download(&song.video_id, s.clone()).await {
+        Ok(path) => {
+            let cover = artwork::download_cover(&song).await;
+            // Tags have to be written before recording the checksum of the file
+            tags::write_tags(&path, &song, cover);
+            integrity::record(&song.video_id)
+        }
+        Err(e) => Err(e),
+    };
+    match downloaded {
+        Ok(_) => {
+            std::fs::write(download_path_json, serde_json::to_string(&song).unwrap()).unwrap();
+            crate::append(song.clone());
+            lyrics::persist(&song.video_id);
+            s.send(SoundAction::VideoStatusUpdate(
+                song.video_id.clone(),
+                MusicDownloadStatus::Downloaded,
+            ))
+            .unwrap();
+            IN_DOWNLOAD.lock().unwrap().remove(&song.video_id);
+            true
+        }
+        Err(e) => {
+            remove_audio(&song.video_id);
+            s.send(SoundAction::VideoStatusUpdate(
+                song.video_id.clone(),
+                MusicDownloadStatus::DownloadFailed,
+            ))
+            .unwrap();
+            error!("Error downloading {}: {e}", song.video_id);
+            // The download can be tried again
+            IN_DOWNLOAD.lock().unwrap().remove(&song.video_id);
+            false
+        }
+    }
+}
+pub fn start_task_unary(s: Sender<SoundAction>, song: YoutubeMusicVideoRef) {
+    HANDLES.lock().unwrap().push(run_service(async move {
+        start_download(song, &s).await;
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Fails after writing part of the audio
+    struct FailingBackend;
+
+    impl DownloadBackend for FailingBackend {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn download<'a>(
+            &'a self,
+            _: &'a str,
+            path: &'a Path,
+            _: &'a (dyn Fn(usize) + Send + Sync),
+        ) -> BoxFuture<'a, Result<(), String>> {
+            Box::pin(async move {
+                std::fs::write(path, b"partial").unwrap();
+                Err("connection reset".to_owned())
+            })
+        }
+    }
+
+    /// Serves the files over HTTP on a free local port, answering 404 for the others
+    fn serve(files: Vec<(&'static str, Vec<u8>)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let path = request.split(' ').nth(1).unwrap_or_default();
+                let body = files
+                    .iter()
+                    .find(|(name, _)| path.strip_prefix('/') == Some(name))
+                    .map(|(_, body)| body);
+                let status = if body.is_some() {
+                    "200 OK"
+                } else {
+                    "404 Not Found"
+                };
+                let body = body.map(Vec::as_slice).unwrap_or_default();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+        url
+    }
+
+    fn mirror(url: String) -> Box<dyn DownloadBackend> {
+        Box::new(mirror::MirrorBackend {
+            url,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// A `.part` file in a directory of its own
+    fn part_file(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("ytermusic-download-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join("abcdefghijk.part")
+    }
+
+    fn download(backends: &[Box<dyn DownloadBackend>], file: &Path) -> Result<PathBuf, String> {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(download_with(backends, "abcdefghijk", file, &|_| ()))
+    }
+
+    #[test]
+    fn mirror_finds_either_container() {
+        let webm = [&[0x1A, 0x45, 0xDF, 0xA3][..], b"opus audio"].concat();
+        let url = serve(vec![("abcdefghijk.webm", webm.clone())]);
+        let file = part_file("mirror");
+        let path = download(&[mirror(format!("{url}/"))], &file).unwrap();
+        assert_eq!(path, file.with_extension("webm"));
+        assert_eq!(std::fs::read(&path).unwrap(), webm);
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn falls_back_to_the_next_backend() {
+        let url = serve(vec![("abcdefghijk.mp4", b"aac audio".to_vec())]);
+        let file = part_file("fallback");
+        let backends = [
+            Box::new(FailingBackend) as Box<dyn DownloadBackend>,
+            mirror(format!("{url}/missing")),
+            mirror(url),
+        ];
+        let path = download(&backends, &file).unwrap();
+        assert_eq!(path, file.with_extension("mp4"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"aac audio");
+    }
+
+    #[test]
+    fn reports_every_failure() {
+        let url = serve(Vec::new());
+        let file = part_file("failure");
+        let backends = [
+            Box::new(FailingBackend) as Box<dyn DownloadBackend>,
+            mirror(url),
+        ];
+        let error = download(&backends, &file).unwrap_err();
+        assert!(error.starts_with("failing: connection reset, http://"));
+        assert!(error.contains("404"));
+        // The partial download isn't left behind
+        assert!(!file.exists());
+        assert_eq!(
+            download(&[], &file).unwrap_err(),
+            "No download backend configured"
+        );
+    }
+}
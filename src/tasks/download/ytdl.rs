@@ -0,0 +1,100 @@
+use std::{io::Write, path::Path, sync::Arc};
+
+use rusty_ytdl::{
+    DownloadOptions, Video, VideoError, VideoFormat, VideoOptions, VideoQuality, VideoSearchOptions,
+};
+
+use crate::{config::AudioCodec, consts::CONFIG};
+
+use super::{BoxFuture, DownloadBackend};
+
+/// Downloads directly from YouTube using `rusty_ytdl`
+pub struct YtdlBackend;
+
+/// Ranks the formats following the configured preferences, the greatest being downloaded.
+/// Formats under the bitrate limit come first, then the ones in the preferred codec.
+fn rank(format: &VideoFormat) -> (bool, bool, i64) {
+    let config = &CONFIG.download;
+    let bitrate = format.audio_bitrate.unwrap_or_default() as i64;
+    let under_limit = config.max_bitrate == 0 || bitrate as u64 <= config.max_bitrate;
+    let preferred = format.container.as_deref() == Some(config.codec.container());
+    (
+        under_limit,
+        preferred,
+        if config.data_saver { -bitrate } else { bitrate },
+    )
+}
+
+fn new_video_with_id(id: &str) -> Result<Video, VideoError> {
+    let search_options = VideoSearchOptions::Custom(Arc::new(|format| {
+        format.has_audio
+            && !format.has_video
+            && [AudioCodec::Aac, AudioCodec::Opus]
+                .iter()
+                .any(|codec| format.container.as_deref() == Some(codec.container()))
+    }));
+    let video_options = VideoOptions {
+        quality: VideoQuality::Custom(
+            search_options.clone(),
+            Arc::new(|x, y| rank(x).cmp(&rank(y))),
+        ),
+        filter: search_options,
+        download_options: DownloadOptions {
+            dl_chunk_size: Some(1024 * 100_u64),
+        },
+        ..Default::default()
+    };
+
+    Video::new_with_options(id, video_options)
+}
+
+async fn download(
+    video: &Video,
+    path: &Path,
+    progress: &(dyn Fn(usize) + Send + Sync),
+) -> Result<(), VideoError> {
+    let stream = video.stream().await?;
+
+    let length = stream.content_length();
+
+    let mut file =
+        std::fs::File::create(path).map_err(|e| VideoError::DownloadError(e.to_string()))?;
+
+    let mut total = 0;
+    while let Some(chunk) = stream.chunk().await? {
+        total += chunk.len();
+
+        progress((total as f64 / length as f64 * 100.0) as usize);
+
+        file.write_all(&chunk)
+            .map_err(|e| VideoError::DownloadError(e.to_string()))?;
+    }
+
+    if length != 0 && total != length {
+        return Err(VideoError::DownloadError(format!(
+            "Truncated download: expected {length} bytes but got {total}"
+        )));
+    }
+
+    Ok(())
+}
+
+impl DownloadBackend for YtdlBackend {
+    fn name(&self) -> &str {
+        "rusty_ytdl"
+    }
+
+    fn download<'a>(
+        &'a self,
+        video_id: &'a str,
+        path: &'a Path,
+        progress: &'a (dyn Fn(usize) + Send + Sync),
+    ) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let video = new_video_with_id(video_id).map_err(|e| e.to_string())?;
+            download(&video, path, progress)
+                .await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use flume::Sender;
+use log::{error, info};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::CONFIG,
+    run_service,
+    structures::performance,
+    tasks::{local_playlists, offline::PINNED_PLAYLISTS, stats},
+    term::{ManagerMessage, Screens},
+    utils::audio_path,
+    DATABASE,
+};
+
+/// File listing the ratings of every rated track
+const RATINGS_FILE: &str = "ratings.csv";
+
+/// What an export did
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub exported: usize,
+    pub skipped: usize,
+    pub missing: usize,
+    pub playlists: usize,
+}
+
+impl Display for ExportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} exported, {} already up to date, {} missing, {} playlists",
+            self.exported, self.skipped, self.missing, self.playlists
+        )
+    }
+}
+
+/// Copies (or hardlinks) the cached tracks into `target` following the configured template,
+/// generates an M3U file per offline and local playlist and for the favorites,
+/// and lists the ratings.
+pub fn export_library(
+    videos: &[YoutubeMusicVideoRef],
+    target: &Path,
+) -> std::io::Result<ExportReport> {
+    let mut report = ExportReport::default();
+    std::fs::create_dir_all(target)?;
+    let mut exported: HashMap<String, PathBuf> = HashMap::with_capacity(videos.len());
+    for video in videos {
+        if exported.contains_key(&video.video_id) {
+            continue;
+        }
+        let source = audio_path(&video.video_id);
+        if !source.exists() {
+            report.missing += 1;
+            continue;
+        }
+        let mut destination = target.join(render(video));
+        if source.extension().map(|x| x == "webm").unwrap_or(false) {
+            destination.set_extension("webm");
+        }
+        let (destination, up_to_date) = resolve_collision(&source, &destination)?;
+        if up_to_date {
+            report.skipped += 1;
+        } else {
+            if let Some(parent) = destination.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            if !CONFIG.export.hardlink || std::fs::hard_link(&source, &destination).is_err() {
+                std::fs::copy(&source, &destination)?;
+            }
+            report.exported += 1;
+        }
+        exported.insert(video.video_id.clone(), destination);
+    }
+    if CONFIG.export.m3u {
+        for (name, videos) in local_playlists::LOCAL_PLAYLISTS.read().unwrap().iter() {
+            write_m3u(target, name, videos, &exported)?;
+            report.playlists += 1;
+        }
+        // The pinned local playlists were written above
+        for playlist in PINNED_PLAYLISTS.read().unwrap().iter() {
+            if local_playlists::from_id(&playlist.id).is_none() {
+                write_m3u(target, &playlist.name, &playlist.videos, &exported)?;
+                report.playlists += 1;
+            }
+        }
+        let favorites = stats::favorites(&DATABASE.read().unwrap());
+        if !favorites.is_empty() {
+            write_m3u(target, "Favorites", &favorites, &exported)?;
+            report.playlists += 1;
+        }
+    }
+    write_ratings(target, &exported)?;
+    Ok(report)
+}
+
+/// Writes an M3U playlist referencing the exported tracks relatively to `target`
+fn write_m3u(
+    target: &Path,
+    name: &str,
+    playlist: &[YoutubeMusicVideoRef],
+    exported: &HashMap<String, PathBuf>,
+) -> std::io::Result<()> {
+    let mut m3u = String::from("#EXTM3U\n");
+    for video in playlist {
+        let Some(path) = exported.get(&video.video_id) else {
+            continue;
+        };
+        let Ok(path) = path.strip_prefix(target) else {
+            continue;
+        };
+        m3u.push_str(&format!(
+            "#EXTINF:-1,{} - {}\n{}\n",
+            video.author,
+            video.title,
+            path.to_string_lossy().replace('\\', "/")
+        ));
+    }
+    std::fs::write(target.join(format!("{}.m3u", sanitize(name))), m3u)
+}
+
+/// Writes the ratings as CSV, with the path of the exported track when it was exported
+fn write_ratings(target: &Path, exported: &HashMap<String, PathBuf>) -> std::io::Result<()> {
+    let db = DATABASE.read().unwrap();
+    let mut rated = db.rated().collect::<Vec<_>>();
+    if rated.is_empty() {
+        return Ok(());
+    }
+    rated.sort_by(|(a, _), (b, _)| a.video_id.cmp(&b.video_id));
+    let mut csv = String::from("video_id,artist,title,album,favorite,stars,path\n");
+    for (video, rating) in rated {
+        let path = exported
+            .get(&video.video_id)
+            .and_then(|path| path.strip_prefix(target).ok())
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            csv_field(&video.video_id),
+            csv_field(&video.author),
+            csv_field(&video.title),
+            csv_field(&video.album),
+            rating.favorite,
+            rating.stars,
+            csv_field(&path)
+        ));
+    }
+    std::fs::write(target.join(RATINGS_FILE), csv)
+}
+
+/// Quotes a CSV field when needed
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Renders the configured template for a video
+fn render(video: &YoutubeMusicVideoRef) -> PathBuf {
+    let or_unknown = |value: &str, unknown: &str| {
+        if value.trim().is_empty() {
+            unknown.to_owned()
+        } else {
+            sanitize(value)
+        }
+    };
+    CONFIG
+        .export
+        .template
+        .split('/')
+        .map(|component| {
+            component
+                .replace("{author}", &or_unknown(&video.author, "Unknown artist"))
+                .replace("{album}", &or_unknown(&video.album, "Unknown album"))
+                .replace("{title}", &or_unknown(&video.title, "Unknown title"))
+                .replace("{video_id}", &sanitize(&video.video_id))
+        })
+        .collect()
+}
+
+/// Makes a string safe to use as a file name on every platform
+pub fn sanitize(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let name = name.trim().trim_end_matches('.');
+    if name.is_empty() {
+        "_".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Whether two files have the same contents
+fn same_contents(a: &Path, b: &Path) -> std::io::Result<bool> {
+    if std::fs::metadata(a)?.len() != std::fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    let (mut buffer_a, mut buffer_b) = (vec![0; 64 * 1024], vec![0; 64 * 1024]);
+    loop {
+        let read = a.read(&mut buffer_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buffer_b[..read])?;
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Finds a destination path that doesn't overwrite another track.
+/// Returns whether the destination already contains this track.
+fn resolve_collision(source: &Path, destination: &Path) -> std::io::Result<(PathBuf, bool)> {
+    let stem = destination
+        .file_stem()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = destination
+        .extension()
+        .map(|x| format!(".{}", x.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate = destination.to_path_buf();
+    let mut i = 2;
+    while candidate.exists() {
+        if same_contents(source, &candidate)? {
+            return Ok((candidate, true));
+        }
+        candidate.set_file_name(format!("{stem} ({i}){extension}"));
+        i += 1;
+    }
+    Ok((candidate, false))
+}
+
+/// Exports the library to the configured directory from the TUI
+pub fn spawn_export_task(updater_s: Sender<ManagerMessage>) {
+    run_service(async move {
+        let guard = performance::guard("Export");
+        let videos = DATABASE.read().unwrap().videos();
+        let target = &CONFIG.export.directory;
+        match export_library(&videos, target) {
+            Ok(report) => {
+                info!("Exported library to {}: {report}", target.display());
+                updater_s
+                    .send(
+                        ManagerMessage::Status(format!(
+                            " Exported to {} ({report}) ",
+                            target.display()
+                        ))
+                        .pass_to(Screens::Playlist),
+                    )
+                    .unwrap();
+            }
+            Err(e) => {
+                error!("Can't export library to {}: {e}", target.display());
+                updater_s
+                    .send(
+                        ManagerMessage::Error(
+                            format!("Can't export library to {}: {e}", target.display()),
+                            Box::new(None),
+                        )
+                        .pass_to(Screens::DeviceLost),
+                    )
+                    .unwrap();
+            }
+        }
+        drop(guard);
+    });
+}
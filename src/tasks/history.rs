@@ -0,0 +1,86 @@
+use std::sync::RwLock;
+
+use flume::Sender;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::CONFIG,
+    run_service,
+    structures::playlist::Playlist,
+    term::{ManagerMessage, Screens},
+};
+
+use super::api;
+
+/// Name of the chooser entry with the history of the account
+const HISTORY_PLAYLIST: &str = "YouTube Music history";
+/// Browse id of the history, the id of its chooser entry
+const HISTORY_ID: &str = "FEmusic_history";
+
+/// The history of the account, the last played first. `None` until it is fetched
+static HISTORY: Lazy<RwLock<Option<Vec<YoutubeMusicVideoRef>>>> = Lazy::new(|| RwLock::new(None));
+
+fn send(videos: Vec<YoutubeMusicVideoRef>, updater: &Sender<ManagerMessage>) {
+    if videos.is_empty() {
+        return;
+    }
+    let _ = updater.send(
+        ManagerMessage::AddElementToChooser(Playlist::new(HISTORY_ID, HISTORY_PLAYLIST, videos))
+            .pass_to(Screens::Playlist),
+    );
+}
+
+/// Sends the history of the account to the chooser
+pub fn spawn_history_task(updater: Sender<ManagerMessage>) {
+    let Some(api) = api::shared() else {
+        return;
+    };
+    run_service(async move {
+        match api::with_retries("History", || api.get_history()).await {
+            Ok(videos) => {
+                info!("Fetched {} musics from the history", videos.len());
+                *HISTORY.write().unwrap() = Some(videos.clone());
+                send(videos, &updater);
+            }
+            Err(e) => error!("History -> {e}"),
+        }
+    });
+}
+
+/// Reports a play to YouTube Music when `api.report_plays` is set,
+/// then puts the music at the top of the history entry, downloaded or streamed
+pub fn report_play(video: &YoutubeMusicVideoRef, updater: &Sender<ManagerMessage>) {
+    if !CONFIG.api.report_plays {
+        return;
+    }
+    let Some(api) = api::shared() else {
+        return;
+    };
+    let video = video.clone();
+    let updater = updater.clone();
+    run_service(async move {
+        let video_id = &video.video_id;
+        match api::with_retries("Reporting a play", || api.add_history_item(video_id)).await {
+            Ok(()) => {
+                info!("Reported a play of {video_id}");
+                push(video, &updater);
+            }
+            Err(e) => warn!("Can't report a play of {video_id}: {e}"),
+        }
+    });
+}
+
+fn push(video: YoutubeMusicVideoRef, updater: &Sender<ManagerMessage>) {
+    let videos = {
+        let mut history = HISTORY.write().unwrap();
+        let Some(history) = history.as_mut() else {
+            return;
+        };
+        history.retain(|x| x.video_id != video.video_id);
+        history.insert(0, video);
+        history.clone()
+    };
+    send(videos, updater);
+}
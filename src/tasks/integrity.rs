@@ -0,0 +1,154 @@
+use std::{
+    fs::{File, Metadata},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use log::{error, info, warn};
+use player::Decoder;
+use serde::{Deserialize, Serialize};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{consts::CACHE_DIR, database, systems::download::LOW_PRIORITY_LIST, utils::audio_path};
+
+use super::download::IN_DOWNLOAD;
+
+/// What is expected from a downloaded file, stored next to it in `<video_id>.sum`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityRecord {
+    pub length: u64,
+    pub crc32: u32,
+    /// Modification time of the file when its checksum was last checked, in milliseconds
+    #[serde(default)]
+    pub modified: Option<u64>,
+}
+
+fn record_path(video_id: &str) -> PathBuf {
+    CACHE_DIR.join(format!("downloads/{video_id}.sum"))
+}
+
+fn write_record(video_id: &str, record: &IntegrityRecord) -> Result<(), String> {
+    std::fs::write(
+        record_path(video_id),
+        serde_json::to_string(record).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| format!("can't write integrity record: {e}"))
+}
+
+fn modified(metadata: &Metadata) -> Option<u64> {
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(modified.as_millis() as u64)
+}
+
+/// Computes the length and checksum of a file
+fn compute(path: &Path) -> std::io::Result<IntegrityRecord> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut length = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        length += read as u64;
+        hasher.update(&buffer[..read]);
+    }
+    Ok(IntegrityRecord {
+        length,
+        crc32: hasher.finalize(),
+        modified: modified(&reader.get_ref().metadata()?),
+    })
+}
+
+/// Checks that the file contains decodable audio
+fn probe(path: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("can't open file: {e}"))?;
+    let extension = path.extension().and_then(|x| x.to_str());
+    Decoder::new_decoder(BufReader::new(file), extension)
+        .map(|_| ())
+        .map_err(|e| format!("can't decode file: {e}"))
+}
+
+/// Probe-decodes a freshly downloaded file and records its length and checksum
+pub fn record(video_id: &str) -> Result<(), String> {
+    let path = audio_path(video_id);
+    probe(&path)?;
+    let record = compute(&path).map_err(|e| format!("can't read file: {e}"))?;
+    write_record(video_id, &record)
+}
+
+/// Reads the record of a file and checks that the file still has the recorded length
+fn check_length(video_id: &str) -> Result<Option<(IntegrityRecord, Metadata)>, String> {
+    let expected = match std::fs::read_to_string(record_path(video_id)) {
+        Ok(e) => serde_json::from_str::<IntegrityRecord>(&e)
+            .map_err(|e| format!("invalid integrity record: {e}"))?,
+        Err(_) => return Ok(None),
+    };
+    let metadata =
+        std::fs::metadata(audio_path(video_id)).map_err(|e| format!("can't read file: {e}"))?;
+    if metadata.len() != expected.length {
+        return Err(format!(
+            "expected {} bytes but found {}",
+            expected.length,
+            metadata.len()
+        ));
+    }
+    Ok(Some((expected, metadata)))
+}
+
+/// Quick check done before playing a file: it still has the length recorded at download
+/// time. The checksum is checked on startup by [`verify`].
+pub fn verify_length(video_id: &str) -> Result<(), String> {
+    check_length(video_id).map(|_| ())
+}
+
+/// Checks that the downloaded file still matches what was recorded at download time.
+/// The checksum is only computed again when the file was modified since the last check.
+/// Files downloaded before records existed are probed and recorded on the first check.
+pub fn verify(video_id: &str) -> Result<(), String> {
+    let Some((expected, metadata)) = check_length(video_id)? else {
+        return record(video_id);
+    };
+    if expected.modified.is_some() && expected.modified == modified(&metadata) {
+        return Ok(());
+    }
+    let actual = compute(&audio_path(video_id)).map_err(|e| format!("can't read file: {e}"))?;
+    if actual.crc32 != expected.crc32 {
+        return Err("checksum mismatch".to_owned());
+    }
+    write_record(video_id, &actual)
+}
+
+/// Moves a corrupted file to the quarantine folder, removes it from the database and
+/// schedules its download again. Before the database is loaded, its repair drops the file.
+pub fn quarantine(video: &YoutubeMusicVideoRef, reason: &str) {
+    warn!(
+        "Quarantining {} ({}): {reason}",
+        video.video_id, video.title
+    );
+    let quarantine_dir = CACHE_DIR.join("quarantine");
+    if let Err(e) = std::fs::create_dir_all(&quarantine_dir) {
+        error!("Can't create quarantine folder: {e}");
+    }
+    let path = audio_path(&video.video_id);
+    if let Some(file_name) = path.file_name().filter(|_| path.exists()) {
+        if let Err(e) = std::fs::rename(&path, quarantine_dir.join(file_name)) {
+            error!("Can't quarantine {}: {e}", video.video_id);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    let _ = std::fs::remove_file(record_path(&video.video_id));
+    let _ = std::fs::remove_file(CACHE_DIR.join(format!("downloads/{}.json", video.video_id)));
+    if database::is_loaded() {
+        database::remove_video(video);
+    }
+
+    IN_DOWNLOAD.lock().unwrap().remove(&video.video_id);
+    let mut download_list = LOW_PRIORITY_LIST.lock().unwrap();
+    if !download_list.iter().any(|x| x.video_id == video.video_id) {
+        info!("Scheduled the download of {} again", video.video_id);
+        download_list.push_back(video.clone());
+    }
+}
@@ -5,7 +5,7 @@ use ytpapi2::YoutubeMusicVideoRef;
 use crate::{
     consts::CACHE_DIR,
     run_service,
-    structures::performance,
+    structures::{performance, playlist::Playlist},
     term::{ManagerMessage, Screens},
 };
 
@@ -19,8 +19,12 @@ pub fn spawn_last_playlist_task(updater_s: Sender<ManagerMessage>) {
         if !playlist.0.starts_with("Last playlist: ") {
             playlist.0 = format!("Last playlist: {}", playlist.0);
         }
+        let (name, videos) = playlist;
         updater_s
-            .send(ManagerMessage::AddElementToChooser(playlist).pass_to(Screens::Playlist))
+            .send(
+                ManagerMessage::AddElementToChooser(Playlist::new("last-playlist", name, videos))
+                    .pass_to(Screens::Playlist),
+            )
             .unwrap();
         drop(guard);
         Some(())
@@ -0,0 +1,128 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, RwLock,
+    },
+};
+
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use ytpapi2::LikeStatus;
+
+use crate::{consts::CACHE_DIR, run_service};
+
+use super::api;
+
+/// Ratings not sent to YouTube Music yet, in the order they were given
+static PENDING: Lazy<RwLock<Vec<(String, LikeStatus)>>> =
+    Lazy::new(|| RwLock::new(read().unwrap_or_default()));
+
+/// Ratings known in this session, given in the app or read from YouTube Music
+static KNOWN: Lazy<RwLock<HashMap<String, LikeStatus>>> =
+    Lazy::new(|| RwLock::new(PENDING.read().unwrap().iter().cloned().collect()));
+
+/// Videos whose rating has already been asked to YouTube Music in this session
+static REQUESTED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Whether the pending ratings are being sent
+static SYNCING: AtomicBool = AtomicBool::new(false);
+
+/// Reads the pending ratings from the disk
+fn read() -> Option<Vec<(String, LikeStatus)>> {
+    let pending = std::fs::read_to_string(CACHE_DIR.join("pending-likes.json")).ok()?;
+    serde_json::from_str(&pending).ok()
+}
+
+/// Writes the pending ratings to the disk
+fn write() {
+    let pending = PENDING.read().unwrap();
+    match serde_json::to_string(&*pending) {
+        Ok(e) => {
+            if let Err(e) = std::fs::write(CACHE_DIR.join("pending-likes.json"), e) {
+                error!("Can't write pending likes: {e}");
+            }
+        }
+        Err(e) => error!("Can't serialize pending likes: {e}"),
+    }
+}
+
+/// The rating of a video on YouTube Music, asked once per session when it isn't known
+pub fn status(video_id: &str) -> Option<LikeStatus> {
+    if let Some(status) = KNOWN.read().unwrap().get(video_id) {
+        return Some(*status);
+    }
+    spawn_status_task(video_id.to_owned());
+    None
+}
+
+fn spawn_status_task(video_id: String) {
+    let Some(api) = api::shared() else {
+        return;
+    };
+    if !REQUESTED.lock().unwrap().insert(video_id.clone()) {
+        return;
+    }
+    run_service(async move {
+        match api.get_like_status(&video_id).await {
+            Ok(Some(status)) => {
+                // A rating given meanwhile is more recent
+                KNOWN.write().unwrap().entry(video_id).or_insert(status);
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Can't get the rating of {video_id}: {e}"),
+        }
+    });
+}
+
+/// Gives the rating to the video, or removes it if the video already has it.
+/// The rating is kept until YouTube Music can be reached. Returns the new rating.
+pub fn toggle(video_id: &str, rating: LikeStatus) -> LikeStatus {
+    let status = if status(video_id) == Some(rating) {
+        LikeStatus::Indifferent
+    } else {
+        rating
+    };
+    KNOWN.write().unwrap().insert(video_id.to_owned(), status);
+    {
+        let mut pending = PENDING.write().unwrap();
+        pending.retain(|(x, _)| x != video_id);
+        pending.push((video_id.to_owned(), status));
+    }
+    write();
+    spawn_sync_task();
+    status
+}
+
+/// Sends the pending ratings to YouTube Music if it can be reached
+pub fn spawn_sync_task() {
+    let Some(api) = api::shared() else {
+        return;
+    };
+    if PENDING.read().unwrap().is_empty() || SYNCING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    run_service(async move {
+        loop {
+            let Some((video_id, status)) = PENDING.read().unwrap().first().cloned() else {
+                break;
+            };
+            match api::with_retries("Rating", || api.rate_song(&video_id, status)).await {
+                Ok(()) => info!("Rated {video_id} {status:?} on YouTube Music"),
+                // The cookies may be fixed before the next session
+                Err(e) if e.is_retryable() || e.is_auth_error() => {
+                    warn!("Can't rate {video_id}, retrying later: {e}");
+                    break;
+                }
+                Err(e) => error!("Can't rate {video_id}: {e}"),
+            }
+            // The video may have been rated again meanwhile
+            PENDING
+                .write()
+                .unwrap()
+                .retain(|x| x != &(video_id.clone(), status));
+            write();
+        }
+        SYNCING.store(false, Ordering::SeqCst);
+    });
+}
@@ -1,52 +1,47 @@
 use flume::Sender;
 use log::info;
-use rand::seq::SliceRandom;
 use ytpapi2::YoutubeMusicVideoRef;
 
 use crate::{
-    consts::{CACHE_DIR, CONFIG},
-    read, run_service,
-    structures::performance,
+    consts::CONFIG,
+    database, run_service,
+    structures::{performance, playlist::Playlist},
+    tasks::{clean, repair, stats},
     term::{ManagerMessage, Screens},
-    DATABASE,
 };
 
+/// Id of the chooser entry with every downloaded music
+pub const LOCAL_MUSICS_ID: &str = "local-musics";
+
 pub fn spawn_local_musics_task(updater_s: Sender<ManagerMessage>) {
     run_service(async move {
         info!("Database getter task on");
         let guard = performance::guard("Local musics");
-        if let Some(videos) = read() {
-            shuffle_and_send(videos, &updater_s);
-        } else {
-            let mut videos = Vec::new();
-            for files in std::fs::read_dir(CACHE_DIR.join("downloads")).unwrap() {
-                let path = files.unwrap().path();
-                if path.as_os_str().to_string_lossy().ends_with(".json") {
-                    let video =
-                        serde_json::from_str(std::fs::read_to_string(path).unwrap().as_str())
-                            .unwrap();
-                    videos.push(video);
-                }
-            }
-            shuffle_and_send(videos, &updater_s);
-
-            crate::write();
-        }
+        let (database, _) = repair::repair();
+        let videos = database.videos();
+        database::load(database);
+        repair::spawn_redownload_task();
+        clean::spawn_verify_task();
+        shuffle_and_send(videos, &updater_s);
+        stats::evict();
+        stats::send_stats_playlists(&updater_s);
         drop(guard);
     });
 }
 
 fn shuffle_and_send(mut videos: Vec<YoutubeMusicVideoRef>, updater_s: &Sender<ManagerMessage>) {
-    *DATABASE.write().unwrap() = videos.clone();
-
     if CONFIG.player.shuffle {
-        videos.shuffle(&mut rand::thread_rng());
+        stats::shuffle(&mut videos);
     }
 
     updater_s
         .send(
-            ManagerMessage::AddElementToChooser(("Local musics".to_owned(), videos))
-                .pass_to(Screens::Playlist),
+            ManagerMessage::AddElementToChooser(Playlist::new(
+                LOCAL_MUSICS_ID,
+                "Local musics",
+                videos,
+            ))
+            .pass_to(Screens::Playlist),
         )
         .unwrap();
 }
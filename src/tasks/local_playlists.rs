@@ -0,0 +1,216 @@
+use std::sync::RwLock;
+
+use flume::Sender;
+use log::{error, info};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::CACHE_DIR,
+    run_service,
+    structures::{performance, playlist::Playlist},
+    tasks::offline,
+    term::{ManagerMessage, Screens},
+    utils::replace_file,
+};
+
+/// The playlists created by the user, with their track list
+pub static LOCAL_PLAYLISTS: Lazy<RwLock<Vec<(String, Vec<YoutubeMusicVideoRef>)>>> =
+    Lazy::new(|| RwLock::new(read().unwrap_or_default()));
+
+/// Reads the local playlists from the disk
+fn read() -> Option<Vec<(String, Vec<YoutubeMusicVideoRef>)>> {
+    let playlists = std::fs::read_to_string(CACHE_DIR.join("local-playlists.json")).ok()?;
+    serde_json::from_str(&playlists).ok()
+}
+
+/// Writes the local playlists to the disk
+fn write() {
+    // Locked for writing so that two writes don't share the temporary file
+    let playlists = LOCAL_PLAYLISTS.write().unwrap();
+    match serde_json::to_string(&*playlists) {
+        Ok(e) => {
+            if let Err(e) = replace_file(&CACHE_DIR.join("local-playlists.json"), e.as_bytes()) {
+                error!("Can't write local playlists: {e}");
+            }
+        }
+        Err(e) => error!("Can't serialize local playlists: {e}"),
+    }
+}
+
+/// Prefix of the ids of the local playlists in the chooser, before their name
+const ID_PREFIX: &str = "local:";
+
+/// The id of a local playlist in the chooser
+pub fn id(name: &str) -> String {
+    format!("{ID_PREFIX}{name}")
+}
+
+/// The name of the local playlist with this id in the chooser, if it exists
+pub fn from_id(id: &str) -> Option<&str> {
+    id.strip_prefix(ID_PREFIX).filter(|name| exists(name))
+}
+
+/// The local playlist as shown in the chooser
+pub fn playlist(name: &str, videos: Vec<YoutubeMusicVideoRef>) -> Playlist {
+    Playlist::new(id(name), name, videos)
+}
+
+/// Whether the playlist was created by the user
+pub fn exists(name: &str) -> bool {
+    LOCAL_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|(local, _)| local == name)
+}
+
+/// The names of the local playlists in creation order
+pub fn names() -> Vec<String> {
+    LOCAL_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("The name of a playlist can't be empty".to_owned());
+    }
+    if exists(name) {
+        return Err(format!("A playlist named {name} already exists"));
+    }
+    Ok(())
+}
+
+/// Creates an empty playlist
+pub fn create(name: &str) -> Result<(), String> {
+    check_name(name)?;
+    LOCAL_PLAYLISTS
+        .write()
+        .unwrap()
+        .push((name.to_owned(), Vec::new()));
+    write();
+    info!("Created local playlist {name}");
+    Ok(())
+}
+
+/// Renames a playlist, keeping it available offline if it was
+pub fn rename(name: &str, new_name: &str) -> Result<Vec<YoutubeMusicVideoRef>, String> {
+    check_name(new_name)?;
+    let videos = {
+        let mut playlists = LOCAL_PLAYLISTS.write().unwrap();
+        let Some((local, videos)) = playlists.iter_mut().find(|(local, _)| local == name) else {
+            return Err(format!("No playlist named {name}"));
+        };
+        new_name.clone_into(local);
+        videos.clone()
+    };
+    write();
+    offline::rename(&id(name), id(new_name), new_name);
+    info!("Renamed local playlist {name} to {new_name}");
+    Ok(videos)
+}
+
+/// Deletes a playlist. Returns whether it existed.
+pub fn delete(name: &str) -> bool {
+    let videos = {
+        let mut playlists = LOCAL_PLAYLISTS.write().unwrap();
+        let Some(position) = playlists.iter().position(|(local, _)| local == name) else {
+            return false;
+        };
+        playlists.remove(position).1
+    };
+    write();
+    if offline::is_pinned(&id(name)) {
+        offline::toggle(&playlist(name, videos));
+    }
+    info!("Deleted local playlist {name}");
+    true
+}
+
+/// Edits the tracks of a playlist and returns the new track list
+fn edit(
+    name: &str,
+    change: impl FnOnce(&mut Vec<YoutubeMusicVideoRef>),
+) -> Option<Vec<YoutubeMusicVideoRef>> {
+    let videos = {
+        let mut playlists = LOCAL_PLAYLISTS.write().unwrap();
+        let (_, videos) = playlists.iter_mut().find(|(local, _)| local == name)?;
+        change(videos);
+        videos.clone()
+    };
+    write();
+    Some(videos)
+}
+
+/// Appends the videos that aren't in the playlist yet
+pub fn add(name: &str, new_videos: &[YoutubeMusicVideoRef]) -> Option<Vec<YoutubeMusicVideoRef>> {
+    edit(name, |videos| {
+        for video in new_videos {
+            if !videos.iter().any(|x| x.video_id == video.video_id) {
+                videos.push(video.clone());
+            }
+        }
+    })
+}
+
+/// Removes the track at `index`
+pub fn remove(name: &str, index: usize) -> Option<Vec<YoutubeMusicVideoRef>> {
+    edit(name, |videos| {
+        if index < videos.len() {
+            videos.remove(index);
+        }
+    })
+}
+
+/// Moves the track at `from` to `to`
+pub fn move_track(name: &str, from: usize, to: usize) -> Option<Vec<YoutubeMusicVideoRef>> {
+    edit(name, |videos| {
+        if from < videos.len() && to < videos.len() {
+            let video = videos.remove(from);
+            videos.insert(to, video);
+        }
+    })
+}
+
+/// Replaces a video in every playlist once its metadata is known
+pub fn update_video(video: &YoutubeMusicVideoRef) {
+    let updated = {
+        let mut playlists = LOCAL_PLAYLISTS.write().unwrap();
+        let mut updated = false;
+        for x in playlists
+            .iter_mut()
+            .flat_map(|(_, videos)| videos.iter_mut())
+        {
+            if x.video_id == video.video_id {
+                x.clone_from(video);
+                updated = true;
+            }
+        }
+        updated
+    };
+    if updated {
+        write();
+    }
+}
+
+/// Sends the local playlists to the chooser
+pub fn spawn_local_playlists_task(updater_s: Sender<ManagerMessage>) {
+    run_service(async move {
+        let guard = performance::guard("Local playlists");
+        info!("Local playlists task on");
+        let playlists = LOCAL_PLAYLISTS.read().unwrap().clone();
+        for (name, videos) in playlists {
+            updater_s
+                .send(
+                    ManagerMessage::AddElementToChooser(playlist(&name, videos))
+                        .pass_to(Screens::Playlist),
+                )
+                .unwrap();
+        }
+        drop(guard);
+    });
+}
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::PathBuf, sync::Mutex};
+
+use log::{info, warn};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicLyrics;
+
+use crate::{consts::CACHE_DIR, run_service, utils::audio_path};
+
+use super::api;
+
+/// Videos whose lyrics have already been requested in this session,
+/// and whether the request is over
+static REQUESTED: Lazy<Mutex<HashMap<String, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Lyrics of the videos played without being downloaded, kept out of the cache
+/// as nothing would remove them from it until the video is downloaded
+static STREAMED: Lazy<Mutex<HashMap<String, YoutubeMusicLyrics>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Path of the cached lyrics of a video, stored next to its audio as JSON
+pub fn lyrics_path(video_id: &str) -> PathBuf {
+    CACHE_DIR.join(format!("downloads/{video_id}.lyrics"))
+}
+
+/// The lyrics of a video if they were downloaded before
+pub fn cached(video_id: &str) -> Option<YoutubeMusicLyrics> {
+    if let Some(lyrics) = STREAMED.lock().unwrap().get(video_id) {
+        return Some(lyrics.clone());
+    }
+    let text = std::fs::read_to_string(lyrics_path(video_id)).ok()?;
+    match serde_json::from_str(&text) {
+        Ok(lyrics) => Some(lyrics),
+        Err(e) => {
+            warn!("Can't read the lyrics of {video_id}: {e}");
+            None
+        }
+    }
+}
+
+/// Writes the lyrics of a downloaded video into the cache
+fn write(video_id: &str, lyrics: &YoutubeMusicLyrics) {
+    let text = serde_json::to_string(lyrics).unwrap();
+    if let Err(e) = std::fs::write(lyrics_path(video_id), text) {
+        warn!("Can't cache the lyrics of {video_id}: {e}");
+    }
+}
+
+/// Moves the lyrics of a streamed video into the cache once the video is downloaded
+pub fn persist(video_id: &str) {
+    if let Some(lyrics) = STREAMED.lock().unwrap().remove(video_id) {
+        write(video_id, &lyrics);
+    }
+}
+
+/// Downloads the lyrics of a video into the cache, once per session.
+/// Nothing is done while the API isn't connected, so it can be asked again later.
+pub fn spawn_lyrics_task(video_id: String) {
+    let Some(api) = api::shared() else {
+        return;
+    };
+    if REQUESTED
+        .lock()
+        .unwrap()
+        .insert(video_id.clone(), false)
+        .is_some()
+    {
+        return;
+    }
+    run_service(async move {
+        match api::with_retries("Lyrics", || api.get_lyrics(&video_id)).await {
+            Ok(Some(lyrics)) => {
+                if audio_path(&video_id).exists() {
+                    write(&video_id, &lyrics);
+                } else {
+                    STREAMED.lock().unwrap().insert(video_id.clone(), lyrics);
+                }
+                info!("Downloaded the lyrics of {video_id}");
+            }
+            Ok(None) => info!("{video_id} has no lyrics"),
+            Err(e) => warn!("Can't download the lyrics of {video_id}: {e}"),
+        }
+        REQUESTED.lock().unwrap().insert(video_id, true);
+    });
+}
+
+/// Whether the lyrics of a video are being downloaded
+pub fn searching(video_id: &str) -> bool {
+    REQUESTED.lock().unwrap().get(video_id) == Some(&false)
+}
+
+/// Whether the download of the lyrics of a video is over in this session
+pub fn finished(video_id: &str) -> bool {
+    REQUESTED.lock().unwrap().get(video_id) == Some(&true)
+}
@@ -1,5 +1,19 @@
 pub mod api;
+pub mod artwork;
 pub mod clean;
 pub mod download;
+pub mod export;
+pub mod history;
+pub mod integrity;
 pub mod last_playlist;
+pub mod likes;
 pub mod local_musics;
+pub mod local_playlists;
+pub mod lyrics;
+pub mod offline;
+pub mod playlist_file;
+pub mod radio;
+pub mod remote_playlists;
+pub mod repair;
+pub mod stats;
+pub mod tags;
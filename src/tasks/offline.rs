@@ -0,0 +1,190 @@
+use std::{collections::HashSet, sync::RwLock};
+
+use flume::Sender;
+use log::{error, info};
+use once_cell::sync::Lazy;
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::CACHE_DIR,
+    run_service,
+    structures::{performance, playlist::Playlist},
+    systems::download::LOW_PRIORITY_LIST,
+    term::{ManagerMessage, Screens},
+    utils::replace_file,
+    DATABASE,
+};
+
+/// The playlists marked as available offline, with their persisted track list
+pub static PINNED_PLAYLISTS: Lazy<RwLock<Vec<Playlist>>> =
+    Lazy::new(|| RwLock::new(read().unwrap_or_default()));
+
+/// Reads the pinned playlists from the disk
+fn read() -> Option<Vec<Playlist>> {
+    let playlists = std::fs::read_to_string(CACHE_DIR.join("offline-playlists.json")).ok()?;
+    serde_json::from_str(&playlists).ok().or_else(|| {
+        // Playlists used to be pinned by name, the id is set by the next `refresh`
+        let playlists: Vec<(String, Vec<YoutubeMusicVideoRef>)> =
+            serde_json::from_str(&playlists).ok()?;
+        Some(
+            playlists
+                .into_iter()
+                .map(|(name, videos)| Playlist::new(name.clone(), name, videos))
+                .collect(),
+        )
+    })
+}
+
+/// Writes the pinned playlists to the disk
+fn write() {
+    // Locked for writing so that two writes don't share the temporary file
+    let playlists = PINNED_PLAYLISTS.write().unwrap();
+    match serde_json::to_string(&*playlists) {
+        Ok(e) => {
+            if let Err(e) = replace_file(&CACHE_DIR.join("offline-playlists.json"), e.as_bytes()) {
+                error!("Can't write offline playlists: {e}");
+            }
+        }
+        Err(e) => error!("Can't serialize offline playlists: {e}"),
+    }
+}
+
+/// Whether the playlist with the id is marked as available offline
+pub fn is_pinned(id: &str) -> bool {
+    PINNED_PLAYLISTS.read().unwrap().iter().any(|x| x.id == id)
+}
+
+/// Whether the video belongs to a pinned playlist and must never be evicted from the cache
+pub fn is_protected(video_id: &str) -> bool {
+    PINNED_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|x| x.videos.iter().any(|x| x.video_id == video_id))
+}
+
+/// Marks or unmarks a playlist as available offline.
+/// Returns whether the playlist is now pinned.
+pub fn toggle(playlist: &Playlist) -> bool {
+    let pinned = {
+        let mut playlists = PINNED_PLAYLISTS.write().unwrap();
+        if let Some(position) = playlists.iter().position(|x| x.id == playlist.id) {
+            playlists.remove(position);
+            false
+        } else {
+            playlists.push(playlist.clone());
+            true
+        }
+    };
+    write();
+    let Playlist { name, videos, .. } = playlist;
+    if pinned {
+        info!("Playlist {name} is now available offline");
+        queue_downloads();
+    } else {
+        info!("Playlist {name} is no longer available offline");
+        // The pinned playlists are read before locking the download list,
+        // in the same order as `queue_downloads`
+        let unqueued = videos
+            .iter()
+            .filter(|x| !is_protected(&x.video_id))
+            .map(|x| x.video_id.as_str())
+            .collect::<HashSet<_>>();
+        LOW_PRIORITY_LIST
+            .lock()
+            .unwrap()
+            .retain(|x| !unqueued.contains(x.video_id.as_str()));
+    }
+    pinned
+}
+
+/// Marks a playlist as available offline, unless it already is
+pub fn pin(playlist: &Playlist) {
+    if !is_pinned(&playlist.id) {
+        toggle(playlist);
+    }
+}
+
+/// Gives a pinned playlist a new id and name, keeping its place among the pinned playlists
+pub fn rename(id: &str, new_id: String, new_name: &str) {
+    {
+        let mut playlists = PINNED_PLAYLISTS.write().unwrap();
+        let Some(pinned) = playlists.iter_mut().find(|x| x.id == id) else {
+            return;
+        };
+        pinned.id = new_id;
+        new_name.clone_into(&mut pinned.name);
+    }
+    write();
+}
+
+/// Replaces a video in the pinned playlists once its metadata is known
+pub fn update_video(video: &YoutubeMusicVideoRef) {
+    let updated = {
+        let mut playlists = PINNED_PLAYLISTS.write().unwrap();
+        let mut updated = false;
+        for x in playlists.iter_mut().flat_map(|x| x.videos.iter_mut()) {
+            if x.video_id == video.video_id {
+                x.clone_from(video);
+                updated = true;
+            }
+        }
+        updated
+    };
+    if updated {
+        write();
+    }
+}
+
+/// Refreshes the name and track list of a pinned playlist with the ones fetched from the API
+pub fn refresh(playlist: &Playlist) {
+    {
+        let mut playlists = PINNED_PLAYLISTS.write().unwrap();
+        // A playlist pinned by name gets its id here
+        let Some(pinned) = playlists
+            .iter_mut()
+            .find(|x| x.id == playlist.id || x.id == playlist.name)
+        else {
+            return;
+        };
+        if pinned == playlist {
+            return;
+        }
+        pinned.clone_from(playlist);
+    }
+    write();
+    queue_downloads();
+}
+
+/// Queues the download of every pinned track that isn't in the cache yet.
+/// These downloads have a lower priority than the ones from the queue.
+pub fn queue_downloads() {
+    let playlists = PINNED_PLAYLISTS.read().unwrap();
+    let db = DATABASE.read().unwrap();
+    let mut download_list = LOW_PRIORITY_LIST.lock().unwrap();
+    for video in playlists.iter().flat_map(|x| x.videos.iter()) {
+        if db.contains(&video.video_id)
+            || download_list.iter().any(|x| x.video_id == video.video_id)
+        {
+            continue;
+        }
+        download_list.push_back(video.clone());
+    }
+}
+
+/// Sends the pinned playlists to the chooser so they can be browsed without network
+/// and starts downloading their missing tracks.
+pub fn spawn_offline_playlists_task(updater_s: Sender<ManagerMessage>) {
+    run_service(async move {
+        let guard = performance::guard("Offline playlists");
+        info!("Offline playlists task on");
+        let playlists = PINNED_PLAYLISTS.read().unwrap().clone();
+        for playlist in playlists {
+            updater_s
+                .send(ManagerMessage::AddElementToChooser(playlist).pass_to(Screens::Playlist))
+                .unwrap();
+        }
+        queue_downloads();
+        drop(guard);
+    });
+}
@@ -0,0 +1,595 @@
+use std::path::Path;
+
+use log::{error, info};
+use serde::Deserialize;
+use ytpapi2::{format_duration, parse_duration, YoutubeMusicVideoRef};
+
+use crate::{
+    consts::CONFIG,
+    tasks::{
+        export::{csv_field, sanitize},
+        local_playlists, offline,
+    },
+    utils::audio_path,
+};
+
+/// The formats playlists and the library can be exported to and imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    /// The full metadata of the videos
+    Json,
+    /// The full metadata of the videos, one per row
+    Csv,
+    /// Extended M3U in UTF-8, for players
+    M3u,
+    /// XML Shareable Playlist Format, for players
+    Xspf,
+    /// One YouTube video id or URL per line, only imported
+    Text,
+}
+
+impl PlaylistFormat {
+    /// Guesses the format from the extension of the file
+    pub fn from_path(path: &Path) -> Self {
+        Self::from_extension(
+            path.extension()
+                .and_then(|x| x.to_str())
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn from_extension(extension: &str) -> Self {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "json" => Self::Json,
+            "csv" => Self::Csv,
+            "m3u" | "m3u8" => Self::M3u,
+            "xspf" => Self::Xspf,
+            _ => Self::Text,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::M3u => "m3u8",
+            Self::Xspf => "xspf",
+            Self::Text => "txt",
+        }
+    }
+}
+
+/// Writes a playlist to a file, in the format given by its extension
+pub fn export(path: &Path, name: &str, videos: &[YoutubeMusicVideoRef]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, encode(PlaylistFormat::from_path(path), name, videos))?;
+    info!("Exported {name} to {}", path.display());
+    Ok(())
+}
+
+/// Exports a playlist to the export directory in the configured format, from the TUI.
+/// Returns the status to show.
+pub fn export_to_directory(name: &str, videos: &[YoutubeMusicVideoRef]) -> String {
+    let format = PlaylistFormat::from_extension(&CONFIG.export.playlist_format);
+    let path = CONFIG
+        .export
+        .directory
+        .join(format!("{}.{}", sanitize(name), format.extension()));
+    match export(&path, name, videos) {
+        Ok(()) => format!(" Exported {name} to {} ", path.display()),
+        Err(e) => {
+            error!("Can't export {name} to {}: {e}", path.display());
+            format!(" Can't export {name}: {e} ")
+        }
+    }
+}
+
+/// Reads a playlist file and saves it as a local playlist available offline,
+/// so its missing tracks get downloaded. The metadata of the videos imported from their
+/// id only is fetched when they are downloaded.
+/// Returns the name of the new playlist and its tracks.
+pub fn import(path: &Path) -> Result<(String, Vec<YoutubeMusicVideoRef>), String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Can't read {}: {e}", path.display()))?;
+    let (name, videos) = decode(PlaylistFormat::from_path(path), &content)?;
+    if videos.is_empty() {
+        return Err(format!("No YouTube video found in {}", path.display()));
+    }
+    let name = name
+        .filter(|x| !x.trim().is_empty())
+        .or_else(|| path.file_stem().map(|x| x.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Imported".to_owned());
+    let name = (1..)
+        .map(|i| {
+            if i == 1 {
+                name.clone()
+            } else {
+                format!("{name} ({i})")
+            }
+        })
+        .find(|x| local_playlists::create(x).is_ok())
+        .unwrap();
+    let videos = local_playlists::add(&name, &videos).unwrap_or_default();
+    offline::pin(&local_playlists::playlist(&name, videos.clone()));
+    info!(
+        "Imported {} musics from {} as {name}",
+        videos.len(),
+        path.display()
+    );
+    Ok((name, videos))
+}
+
+pub fn encode(format: PlaylistFormat, name: &str, videos: &[YoutubeMusicVideoRef]) -> String {
+    match format {
+        PlaylistFormat::Json => {
+            serde_json::to_string_pretty(&serde_json::json!({ "name": name, "videos": videos }))
+                .unwrap()
+        }
+        PlaylistFormat::Csv => {
+            let mut csv = String::from("video_id,title,artist,album,duration,url\n");
+            for video in videos {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    csv_field(&video.video_id),
+                    csv_field(&video.title),
+                    csv_field(&video.author),
+                    csv_field(&video.album),
+                    video.duration.map(format_duration).unwrap_or_default(),
+                    url(&video.video_id)
+                ));
+            }
+            csv
+        }
+        PlaylistFormat::M3u => {
+            let mut m3u = format!("#EXTM3U\n#PLAYLIST:{name}\n");
+            for video in videos {
+                if !video.album.is_empty() {
+                    m3u.push_str(&format!("#EXTALB:{}\n", video.album));
+                }
+                m3u.push_str(&format!(
+                    "#EXTINF:{},{} - {}\n{}\n",
+                    video.duration.map_or(-1, i64::from),
+                    video.author,
+                    video.title,
+                    path_or_url(&video.video_id)
+                ));
+            }
+            m3u
+        }
+        PlaylistFormat::Xspf => {
+            let mut xspf = format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n  \
+                 <title>{}</title>\n  <trackList>\n",
+                escape(name)
+            );
+            for video in videos {
+                xspf.push_str(&format!(
+                    "    <track>\n      <location>{}</location>\n      \
+                     <identifier>{}</identifier>\n      <title>{}</title>\n      \
+                     <creator>{}</creator>\n",
+                    escape(&file_url_or_url(&video.video_id)),
+                    escape(&url(&video.video_id)),
+                    escape(&video.title),
+                    escape(&video.author)
+                ));
+                if !video.album.is_empty() {
+                    xspf.push_str(&format!("      <album>{}</album>\n", escape(&video.album)));
+                }
+                if let Some(seconds) = video.duration {
+                    xspf.push_str(&format!(
+                        "      <duration>{}</duration>\n",
+                        u64::from(seconds) * 1000
+                    ));
+                }
+                xspf.push_str("    </track>\n");
+            }
+            xspf.push_str("  </trackList>\n</playlist>\n");
+            xspf
+        }
+        PlaylistFormat::Text => videos
+            .iter()
+            .map(|video| format!("{}\n", url(&video.video_id)))
+            .collect(),
+    }
+}
+
+/// Reads the name, if the format has one, and the videos of a playlist file.
+/// Entries that don't reference a YouTube video are skipped.
+pub fn decode(
+    format: PlaylistFormat,
+    content: &str,
+) -> Result<(Option<String>, Vec<YoutubeMusicVideoRef>), String> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        PlaylistFormat::Json => decode_json(content),
+        PlaylistFormat::Csv => Ok((None, decode_csv(content))),
+        PlaylistFormat::M3u => Ok(decode_m3u(content)),
+        PlaylistFormat::Xspf => Ok(decode_xspf(content)),
+        PlaylistFormat::Text => Ok((
+            None,
+            content
+                .lines()
+                .filter_map(video_id_from)
+                .map(|id| video(id, String::new(), String::new()))
+                .collect(),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTrack {
+    Video(YoutubeMusicVideoRef),
+    Url(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPlaylist {
+    Named {
+        name: Option<String>,
+        videos: Vec<JsonTrack>,
+    },
+    Tracks(Vec<JsonTrack>),
+    /// The format of `last-playlist.json`
+    Tuple(String, Vec<JsonTrack>),
+}
+
+fn decode_json(content: &str) -> Result<(Option<String>, Vec<YoutubeMusicVideoRef>), String> {
+    let playlist = serde_json::from_str::<JsonPlaylist>(content)
+        .map_err(|e| format!("Invalid JSON playlist: {e}"))?;
+    let (name, tracks) = match playlist {
+        JsonPlaylist::Named { name, videos } => (name, videos),
+        JsonPlaylist::Tracks(videos) => (None, videos),
+        JsonPlaylist::Tuple(name, videos) => (Some(name), videos),
+    };
+    let videos = tracks
+        .into_iter()
+        .filter_map(|track| match track {
+            JsonTrack::Video(video) => Some(video),
+            JsonTrack::Url(url) => {
+                video_id_from(&url).map(|id| video(id, String::new(), String::new()))
+            }
+        })
+        .collect();
+    Ok((name, videos))
+}
+
+fn decode_csv(content: &str) -> Vec<YoutubeMusicVideoRef> {
+    let rows = parse_csv(content);
+    let Some(header) = rows.first() else {
+        return Vec::new();
+    };
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|x| names.contains(&x.trim().to_lowercase().as_str()))
+    };
+    let Some(id_column) = column(&["video_id", "id", "url", "link"]) else {
+        // Without a header, the first column holds the videos
+        return rows
+            .iter()
+            .filter_map(|row| video_id_from(row.first()?))
+            .map(|id| video(id, String::new(), String::new()))
+            .collect();
+    };
+    let [title, author, album, duration] = [
+        column(&["title", "name"]),
+        column(&["artist", "author", "creator"]),
+        column(&["album"]),
+        column(&["duration"]),
+    ];
+    rows.iter()
+        .skip(1)
+        .filter_map(|row| {
+            let field = |column: Option<usize>| {
+                column.and_then(|x| row.get(x)).cloned().unwrap_or_default()
+            };
+            let mut video = video(
+                video_id_from(row.get(id_column)?)?,
+                field(title),
+                field(author),
+            );
+            video.album = field(album);
+            video.duration = parse_duration(&field(duration));
+            Some(video)
+        })
+        .collect()
+}
+
+/// Splits CSV in rows of fields, handling quoted fields
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|x| !x.trim().is_empty()));
+    rows
+}
+
+fn decode_m3u(content: &str) -> (Option<String>, Vec<YoutubeMusicVideoRef>) {
+    let mut name = None;
+    let mut info = None;
+    let mut album = None;
+    let mut videos = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(playlist) = line.strip_prefix("#PLAYLIST:") {
+            name = Some(playlist.to_owned());
+        } else if let Some(extalb) = line.strip_prefix("#EXTALB:") {
+            album = Some(extalb.to_owned());
+        } else if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            info = extinf.split_once(',').map(|(duration, title)| {
+                let (author, title) = title.split_once(" - ").unwrap_or(("", title));
+                // Unknown durations are -1
+                let duration = duration.trim().parse::<u32>().ok();
+                (author.to_owned(), title.to_owned(), duration)
+            });
+        } else if !line.is_empty() && !line.starts_with('#') {
+            if let Some(id) = video_id_from(line) {
+                let (author, title, duration) = info.take().unwrap_or_default();
+                let mut video = video(id, title, author);
+                video.duration = duration;
+                video.album = album.take().unwrap_or_default();
+                videos.push(video);
+            }
+            info = None;
+            album = None;
+        }
+    }
+    (name, videos)
+}
+
+fn decode_xspf(content: &str) -> (Option<String>, Vec<YoutubeMusicVideoRef>) {
+    let tracks = content
+        .split("<track>")
+        .skip(1)
+        .map(|track| track.split("</track>").next().unwrap_or_default());
+    let name = content
+        .split("<track")
+        .next()
+        .and_then(|head| tag(head, "title"));
+    let videos = tracks
+        .filter_map(|track| {
+            let id = tag(track, "identifier")
+                .and_then(|x| video_id_from(&x))
+                .or_else(|| tag(track, "location").and_then(|x| video_id_from(&x)))?;
+            let mut video = video(
+                id,
+                tag(track, "title").unwrap_or_default(),
+                tag(track, "creator").unwrap_or_default(),
+            );
+            video.album = tag(track, "album").unwrap_or_default();
+            video.duration = tag(track, "duration")
+                .and_then(|x| x.trim().parse::<u64>().ok())
+                .and_then(|ms| u32::try_from(ms / 1000).ok());
+            Some(video)
+        })
+        .collect();
+    (name, videos)
+}
+
+/// The unescaped text of the first `<name>` element
+fn tag(xml: &str, name: &str) -> Option<String> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = xml[start..].find(&format!("</{name}>"))? + start;
+    Some(unescape(&xml[start..end]))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Finds the YouTube video id in an id, a YouTube URL or the path of a downloaded file
+pub fn video_id_from(text: &str) -> Option<String> {
+    let text = text.trim();
+    let candidate = if let Some((_, query)) = text.split_once("v=") {
+        query
+    } else if let Some((_, path)) = text.split_once("youtu.be/") {
+        path
+    } else if text.contains('/') || text.contains('\\') {
+        // A downloaded file is named after its video
+        let decoded = urlencoding::decode(text).ok()?;
+        let name = decoded.rsplit(['/', '\\']).next()?;
+        return name
+            .split('.')
+            .next()
+            .filter(|x| is_video_id(x))
+            .map(str::to_owned);
+    } else {
+        text
+    };
+    let id = candidate.split(['&', '?', '#', '/']).next()?;
+    is_video_id(id).then(|| id.to_owned())
+}
+
+fn is_video_id(text: &str) -> bool {
+    text.len() == 11
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Whether the video was imported from its id only, without any metadata
+pub fn lacks_metadata(video: &YoutubeMusicVideoRef) -> bool {
+    video.title == video.video_id && video.author.is_empty() && video.album.is_empty()
+}
+
+fn video(video_id: String, title: String, author: String) -> YoutubeMusicVideoRef {
+    YoutubeMusicVideoRef {
+        // The title is the only thing shown for a video, so it can't be empty
+        title: if title.is_empty() {
+            video_id.clone()
+        } else {
+            title
+        },
+        author,
+        album: String::new(),
+        video_id,
+        duration: None,
+        thumbnail: String::new(),
+        artist_id: String::new(),
+    }
+}
+
+fn url(video_id: &str) -> String {
+    format!("https://music.youtube.com/watch?v={video_id}")
+}
+
+/// The path of the downloaded file if there is one, the YouTube URL otherwise
+fn path_or_url(video_id: &str) -> String {
+    let path = audio_path(video_id);
+    if path.exists() {
+        path.to_string_lossy().to_string()
+    } else {
+        url(video_id)
+    }
+}
+
+/// The URL of the downloaded file if there is one, the YouTube URL otherwise
+fn file_url_or_url(video_id: &str) -> String {
+    let path = audio_path(video_id);
+    if !path.exists() {
+        return url(video_id);
+    }
+    file_url(&path)
+}
+
+/// The percent-encoded `file://` URL of a local file
+pub fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded = path
+        .split('/')
+        .map(|x| urlencoding::encode(x).into_owned())
+        .collect::<Vec<_>>()
+        .join("/");
+    if encoded.starts_with('/') {
+        format!("file://{encoded}")
+    } else {
+        format!("file:///{encoded}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::fixtures;
+
+    fn videos() -> Vec<YoutubeMusicVideoRef> {
+        (0..5).map(fixtures::video).collect()
+    }
+
+    #[test]
+    fn encodes_file_urls() {
+        assert_eq!(
+            file_url(Path::new("/home/me/My Music/a#b?.jpg")),
+            "file:///home/me/My%20Music/a%23b%3F.jpg"
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        for format in [
+            PlaylistFormat::Json,
+            PlaylistFormat::Csv,
+            PlaylistFormat::M3u,
+            PlaylistFormat::Xspf,
+        ] {
+            let encoded = encode(format, "Road trip", &videos());
+            let (name, decoded) = decode(format, &encoded).unwrap();
+            assert_eq!(decoded, videos(), "{format:?}");
+            if format != PlaylistFormat::Csv {
+                assert_eq!(name.as_deref(), Some("Road trip"), "{format:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn reads_video_ids_and_urls() {
+        let text = "dQw4w9WgXcQ\n\
+                    https://www.youtube.com/watch?v=9bZkp7q19f0&list=PL123\n\
+                    https://youtu.be/kJQP7kiW5Fk?t=42\n\
+                    https://music.youtube.com/watch?v=fJ9rUzIMcZQ\n\
+                    # a comment\n\
+                    /home/me/.cache/ytermusic/downloads/OPf0YbXqDm0.webm\n\
+                    not a video\n";
+        let (_, videos) = decode(PlaylistFormat::Text, text).unwrap();
+        let ids = videos
+            .iter()
+            .map(|x| x.video_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                "dQw4w9WgXcQ",
+                "9bZkp7q19f0",
+                "kJQP7kiW5Fk",
+                "fJ9rUzIMcZQ",
+                "OPf0YbXqDm0"
+            ]
+        );
+        assert_eq!(videos[0].title, "dQw4w9WgXcQ");
+    }
+
+    #[test]
+    fn reads_csv_with_other_columns() {
+        let csv = "Artist,Title,URL\r\n\
+                   \"Someone, else\",\"A \"\"quoted\"\" title\",https://youtu.be/dQw4w9WgXcQ\r\n\
+                   Nobody,No video,\r\n";
+        let (_, videos) = decode(PlaylistFormat::Csv, csv).unwrap();
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].video_id, "dQw4w9WgXcQ");
+        assert_eq!(videos[0].author, "Someone, else");
+        assert_eq!(videos[0].title, "A \"quoted\" title");
+    }
+
+    #[test]
+    fn reads_json_lists() {
+        let (name, videos) = decode(
+            PlaylistFormat::Json,
+            r#"["dQw4w9WgXcQ", "https://youtu.be/9bZkp7q19f0"]"#,
+        )
+        .unwrap();
+        assert_eq!(name, None);
+        assert_eq!(videos.len(), 2);
+        let last_playlist = serde_json::to_string(&("Last", videos.clone())).unwrap();
+        let (name, decoded) = decode(PlaylistFormat::Json, &last_playlist).unwrap();
+        assert_eq!(name.as_deref(), Some("Last"));
+        assert_eq!(decoded, videos);
+    }
+}
@@ -0,0 +1,30 @@
+use flume::Sender;
+use log::{error, warn};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{run_service, structures::sound_action::SoundAction};
+
+use super::api;
+
+/// Fetches the radio of a video and sends its tracks to the player for autoplay.
+/// The player is sent no track when the radio can't be fetched.
+pub fn spawn_radio_task(seed: YoutubeMusicVideoRef, generation: u64, sender: Sender<SoundAction>) {
+    run_service(async move {
+        let videos = match api::shared() {
+            Some(api) => {
+                match api::with_retries("Radio", || api.get_watch_playlist(&seed.video_id)).await {
+                    Ok(videos) => videos,
+                    Err(e) => {
+                        error!("Radio of {} -> {e}", seed.video_id);
+                        Vec::new()
+                    }
+                }
+            }
+            None => {
+                warn!("Autoplay needs the connection to YouTube Music");
+                Vec::new()
+            }
+        };
+        let _ = sender.send(SoundAction::Autoplay(generation, videos));
+    });
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
+use flume::Sender;
+use log::{error, info};
+use once_cell::sync::Lazy;
+use ytpapi2::{YoutubeMusicInstance, YoutubeMusicVideoRef};
+
+use crate::{
+    run_service,
+    structures::playlist::Playlist,
+    term::{ManagerMessage, Screens},
+};
+
+use super::api;
+
+/// A track of a playlist with the id of its entry (`playlistSetVideoId`), which tells apart
+/// the entries of a video that is in the playlist several times. The id is unknown for the
+/// tracks added since the playlist was fetched.
+pub type Entry = (YoutubeMusicVideoRef, Option<String>);
+
+/// A playlist of the library, which can be edited on YouTube Music
+struct RemotePlaylist {
+    /// The name of the playlist in the chooser
+    name: String,
+    browse_id: String,
+    entries: Vec<Entry>,
+}
+
+static REMOTE_PLAYLISTS: Lazy<RwLock<Vec<RemotePlaylist>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Remembers a playlist of the library with its tracks, replacing the one with the same
+/// browse id
+pub fn register(name: String, browse_id: String, entries: Vec<Entry>) {
+    let mut playlists = REMOTE_PLAYLISTS.write().unwrap();
+    playlists.retain(|x| x.browse_id != browse_id);
+    playlists.push(RemotePlaylist {
+        name,
+        browse_id,
+        entries,
+    });
+}
+
+/// Whether the playlist with the browse id is in the library and can be edited
+pub fn exists(browse_id: &str) -> bool {
+    REMOTE_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .any(|x| x.browse_id == browse_id)
+}
+
+/// The browse ids and names of the playlists of the library in the order they were fetched
+pub fn playlists() -> Vec<(String, String)> {
+    REMOTE_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .map(|x| (x.browse_id.clone(), x.name.clone()))
+        .collect()
+}
+
+/// The name and the entries of a playlist of the library
+fn get(browse_id: &str) -> Option<(String, Vec<Entry>)> {
+    REMOTE_PLAYLISTS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|x| x.browse_id == browse_id)
+        .map(|x| (x.name.clone(), x.entries.clone()))
+}
+
+/// The position of an entry, the first entry of the video when its id is unknown
+fn position(entries: &[Entry], (video, set_video_id): &Entry) -> Option<usize> {
+    entries
+        .iter()
+        .position(|(x, id)| x.video_id == video.video_id && id == set_video_id)
+}
+
+fn send_status(updater: &Sender<ManagerMessage>, status: String) {
+    let _ = updater.send(ManagerMessage::Status(status.clone()).pass_to(Screens::Playlist));
+    let _ = updater.send(ManagerMessage::Status(status).pass_to(Screens::PlaylistViewer));
+}
+
+/// Sends the new track list of a playlist to the chooser and to the playlist viewer
+fn send_videos(updater: &Sender<ManagerMessage>, playlist: Playlist) {
+    let _ = updater.send(
+        ManagerMessage::PlaylistUpdated(playlist.id.clone(), playlist.videos.clone())
+            .pass_to(Screens::PlaylistViewer),
+    );
+    let _ = updater.send(ManagerMessage::AddElementToChooser(playlist).pass_to(Screens::Playlist));
+}
+
+/// Sends an edition of a playlist of the library to YouTube Music, then applies `change`
+/// to the known entries and shows the tracks
+fn spawn_edit_task<F: Future<Output = ytpapi2::Result<()>> + Send + 'static>(
+    browse_id: String,
+    done: String,
+    updater: Sender<ManagerMessage>,
+    request: impl FnOnce(Arc<YoutubeMusicInstance>, String) -> F,
+    change: impl FnOnce(&mut Vec<Entry>) + Send + 'static,
+) {
+    let Some((name, _)) = get(&browse_id) else {
+        return;
+    };
+    let Some(api) = api::shared() else {
+        send_status(&updater, " YouTube Music can't be reached ".to_owned());
+        return;
+    };
+    let request = request(api, browse_id.clone());
+    run_service(async move {
+        if let Err(e) = request.await {
+            error!("Can't edit the playlist {name}: {e}");
+            send_status(&updater, format!(" Can't edit {name} on YouTube Music "));
+            return;
+        }
+        info!("Edited the playlist {name} on YouTube Music");
+        let videos = {
+            let mut playlists = REMOTE_PLAYLISTS.write().unwrap();
+            let Some(playlist) = playlists.iter_mut().find(|x| x.browse_id == browse_id) else {
+                return;
+            };
+            change(&mut playlist.entries);
+            playlist
+                .entries
+                .iter()
+                .map(|(video, _)| video.clone())
+                .collect()
+        };
+        send_videos(&updater, Playlist::new(browse_id, name, videos));
+        send_status(&updater, done);
+    });
+}
+
+/// Appends the videos that aren't in the playlist yet
+pub fn spawn_add_task(
+    browse_id: String,
+    mut new_videos: Vec<YoutubeMusicVideoRef>,
+    updater: Sender<ManagerMessage>,
+) {
+    let Some((name, entries)) = get(&browse_id) else {
+        return;
+    };
+    let mut seen = entries
+        .iter()
+        .map(|(x, _)| x.video_id.clone())
+        .collect::<HashSet<_>>();
+    new_videos.retain(|x| seen.insert(x.video_id.clone()));
+    if new_videos.is_empty() {
+        send_status(&updater, format!(" The musics are already in {name} "));
+        return;
+    }
+    let done = format!(" Added {} musics to {name} ", new_videos.len());
+    let video_ids = new_videos
+        .iter()
+        .map(|x| x.video_id.clone())
+        .collect::<Vec<_>>();
+    spawn_edit_task(
+        browse_id,
+        done,
+        updater,
+        |api, browse_id| async move {
+            let video_ids = video_ids.iter().map(String::as_str).collect::<Vec<_>>();
+            api.add_to_playlist(&browse_id, &video_ids).await
+        },
+        move |entries| {
+            // Videos added meanwhile aren't listed twice
+            for video in new_videos {
+                if !entries.iter().any(|(x, _)| x.video_id == video.video_id) {
+                    entries.push((video, None));
+                }
+            }
+        },
+    );
+}
+
+/// Removes the entry at `index`
+pub fn spawn_remove_task(browse_id: String, index: usize, updater: Sender<ManagerMessage>) {
+    let Some((name, entries)) = get(&browse_id) else {
+        return;
+    };
+    let Some(entry) = entries.get(index).cloned() else {
+        return;
+    };
+    let (video, set_video_id) = entry.clone();
+    spawn_edit_task(
+        browse_id,
+        format!(" Removed {} from {name} ", video.title),
+        updater,
+        |api, browse_id| async move {
+            match set_video_id {
+                Some(set_video_id) => {
+                    api.remove_playlist_entries(&browse_id, &[(video.video_id, set_video_id)])
+                        .await
+                }
+                // Added videos are only once in the playlist
+                None => {
+                    api.remove_from_playlist(&browse_id, &[video.video_id.as_str()])
+                        .await
+                }
+            }
+        },
+        move |entries| {
+            if let Some(position) = position(entries, &entry) {
+                entries.remove(position);
+            }
+        },
+    );
+}
+
+/// Moves the entry at `from` to `to`
+pub fn spawn_move_task(browse_id: String, from: usize, to: usize, updater: Sender<ManagerMessage>) {
+    let Some((name, mut entries)) = get(&browse_id) else {
+        return;
+    };
+    if from >= entries.len() || to >= entries.len() {
+        return;
+    }
+    let entry = entries.remove(from);
+    entries.insert(to, entry.clone());
+    let successor = entries.get(to + 1).cloned();
+    let (video, set_video_id) = entry.clone();
+    spawn_edit_task(
+        browse_id,
+        format!(" Moved a music of {name} "),
+        updater,
+        |api, browse_id| async move {
+            match (set_video_id, successor) {
+                (Some(set_video_id), None) => {
+                    api.move_playlist_entry(&browse_id, &set_video_id, None)
+                        .await
+                }
+                (Some(set_video_id), Some((_, Some(successor)))) => {
+                    api.move_playlist_entry(&browse_id, &set_video_id, Some(&successor))
+                        .await
+                }
+                // Added videos are only once in the playlist
+                (_, successor) => {
+                    let before = successor.map(|(x, _)| x.video_id);
+                    api.move_in_playlist(&browse_id, &video.video_id, before.as_deref())
+                        .await
+                }
+            }
+        },
+        move |entries| {
+            if let Some(from) = position(entries, &entry) {
+                let entry = entries.remove(from);
+                entries.insert(to.min(entries.len()), entry);
+            }
+        },
+    );
+}
+
+/// Creates a private playlist with the videos on YouTube Music and adds it to the chooser
+pub fn spawn_create_task(
+    name: String,
+    videos: Vec<YoutubeMusicVideoRef>,
+    updater: Sender<ManagerMessage>,
+) {
+    let Some(api) = api::shared() else {
+        send_status(&updater, " YouTube Music can't be reached ".to_owned());
+        return;
+    };
+    run_service(async move {
+        let video_ids = videos
+            .iter()
+            .map(|x| x.video_id.as_str())
+            .collect::<Vec<_>>();
+        match api.create_playlist(&name, "", &video_ids).await {
+            Ok(browse_id) => {
+                info!("Created the playlist {name} on YouTube Music");
+                let entries = videos.iter().map(|x| (x.clone(), None)).collect();
+                register(name.clone(), browse_id.clone(), entries);
+                let status = format!(" Created {name} with {} musics ", videos.len());
+                send_videos(&updater, Playlist::new(browse_id, name, videos));
+                send_status(&updater, status);
+            }
+            Err(e) => {
+                error!("Can't create the playlist {name}: {e}");
+                send_status(&updater, format!(" Can't create {name} on YouTube Music "));
+            }
+        }
+    });
+}
+
+/// Deletes a playlist of the library from YouTube Music and from the chooser
+pub fn spawn_delete_task(browse_id: String, updater: Sender<ManagerMessage>) {
+    let Some((name, _)) = get(&browse_id) else {
+        return;
+    };
+    let Some(api) = api::shared() else {
+        send_status(&updater, " YouTube Music can't be reached ".to_owned());
+        return;
+    };
+    run_service(async move {
+        match api.delete_playlist(&browse_id).await {
+            Ok(()) => {
+                info!("Deleted the playlist {name} from YouTube Music");
+                REMOTE_PLAYLISTS
+                    .write()
+                    .unwrap()
+                    .retain(|x| x.browse_id != browse_id);
+                let _ = updater
+                    .send(ManagerMessage::RemoveFromChooser(browse_id).pass_to(Screens::Playlist));
+                send_status(&updater, format!(" Deleted {name} from YouTube Music "));
+            }
+            Err(e) => {
+                error!("Can't delete the playlist {name}: {e}");
+                send_status(
+                    &updater,
+                    format!(" Can't delete {name} from YouTube Music "),
+                );
+            }
+        }
+    });
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::Path,
+};
+
+use log::{error, info, warn};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::CACHE_DIR,
+    database::{decode, salvage, write_to, Database, Salvaged, VERSION},
+    run_service,
+    systems::download::LOW_PRIORITY_LIST,
+    utils::{audio_path, AUDIO_EXTENSIONS},
+};
+
+use super::{api, download::IN_DOWNLOAD, tags};
+
+/// What a repair fixed
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    pub dropped: usize,
+    pub duplicates: usize,
+    pub missing: usize,
+    pub readded: usize,
+    pub sidecars: usize,
+    pub removed: usize,
+    pub quarantined: usize,
+}
+
+impl RepairReport {
+    pub fn is_empty(&self) -> bool {
+        self.dropped
+            + self.duplicates
+            + self.missing
+            + self.readded
+            + self.sidecars
+            + self.removed
+            + self.quarantined
+            == 0
+    }
+}
+
+impl Display for RepairReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} unreadable records dropped, {} duplicates removed, {} entries without audio removed, \
+             {} orphaned files re-added, {} sidecars restored, {} stale files removed, \
+             {} unreadable files quarantined",
+            self.dropped,
+            self.duplicates,
+            self.missing,
+            self.readded,
+            self.sidecars,
+            self.removed,
+            self.quarantined
+        )
+    }
+}
+
+/// Reconciles the database with the sidecar JSONs and the audio files of the cache:
+/// keeps every readable record, removes the duplicated videos and the videos without audio,
+/// adds back the audio files missing from the database and restores the missing sidecars.
+/// The files of the videos being downloaded are left alone, and the audio files whose
+/// metadata can't be read are quarantined to be downloaded again.
+/// The database is rewritten when anything was fixed.
+pub fn repair() -> (Database, RepairReport) {
+    let mut report = RepairReport::default();
+    let path = CACHE_DIR.join("db.bin");
+    let (salvaged, mut rewrite) = match std::fs::read(&path) {
+        Ok(bytes) => match decode(&bytes) {
+            Ok((version, _)) => (salvage(&bytes), version < VERSION),
+            Err(e) => {
+                warn!("Database is damaged, salvaging what can be read: {e}");
+                (salvage(&bytes), true)
+            }
+        },
+        Err(_) => (Salvaged::default(), true),
+    };
+    report.dropped = salvaged.dropped;
+
+    // The last copy of a video is the most recently downloaded one
+    let mut videos: Vec<YoutubeMusicVideoRef> = Vec::with_capacity(salvaged.videos.len());
+    let mut positions = HashMap::new();
+    for video in salvaged.videos {
+        if let Some(&position) = positions.get(&video.video_id) {
+            videos[position] = video;
+            report.duplicates += 1;
+        } else {
+            positions.insert(video.video_id.clone(), videos.len());
+            videos.push(video);
+        }
+    }
+
+    videos.retain(|video| {
+        let exists = audio_path(&video.video_id).exists();
+        if !exists {
+            info!("Removed {} from the database: no audio", video.video_id);
+            report.missing += 1;
+        }
+        exists
+    });
+
+    let mut known = videos
+        .iter()
+        .map(|video| video.video_id.clone())
+        .collect::<HashSet<_>>();
+    let downloads = CACHE_DIR.join("downloads");
+    for entry in std::fs::read_dir(&downloads)
+        .into_iter()
+        .flatten()
+        .flatten()
+    {
+        let path = entry.path();
+        let (Some(video_id), Some(extension)) = (
+            path.file_stem().and_then(|x| x.to_str()),
+            path.extension().and_then(|x| x.to_str()),
+        ) else {
+            continue;
+        };
+        // A download renames its audio before writing the sidecar and the database entry
+        if known.contains(video_id) || IN_DOWNLOAD.lock().unwrap().contains(video_id) {
+            continue;
+        }
+        if extension == "json" {
+            // A sidecar marks its video as downloaded, so it can't outlive the audio
+            if path.exists() && !audio_path(video_id).exists() {
+                remove(&path, &mut report);
+            }
+            continue;
+        }
+        if !AUDIO_EXTENSIONS.contains(&extension) {
+            continue;
+        }
+        match recover(&path, video_id) {
+            Some(video) => {
+                info!("Re-added orphaned file {}", path.display());
+                known.insert(video.video_id.clone());
+                videos.push(video);
+                report.readded += 1;
+            }
+            None => quarantine(&path, &mut report),
+        }
+    }
+
+    for video in &videos {
+        let sidecar = downloads.join(format!("{}.json", video.video_id));
+        if sidecar.exists() {
+            continue;
+        }
+        match std::fs::write(&sidecar, serde_json::to_string(video).unwrap()) {
+            Ok(()) => report.sidecars += 1,
+            Err(e) => error!("Can't restore {}: {e}", sidecar.display()),
+        }
+    }
+
+    rewrite |= !report.is_empty();
+    let database = Database::from_videos(videos)
+        .with_stats(salvaged.stats)
+        .with_ratings(salvaged.ratings);
+    if rewrite {
+        if let Err(e) = write_to(&path, &database) {
+            error!("Can't write repaired database: {e}");
+        }
+    }
+    if report.is_empty() {
+        info!("Database is consistent");
+    } else {
+        info!("Repaired database: {report}");
+    }
+    (database, report)
+}
+
+/// Reads the metadata of an audio file missing from the database, from its sidecar
+/// or from its tags
+fn recover(path: &Path, video_id: &str) -> Option<YoutubeMusicVideoRef> {
+    let sidecar = std::fs::read_to_string(path.with_extension("json"))
+        .ok()
+        .and_then(|x| serde_json::from_str::<YoutubeMusicVideoRef>(&x).ok());
+    sidecar
+        .or_else(|| tags::read_tags(path))
+        .filter(|video| video.video_id == video_id)
+}
+
+/// Moves an audio file whose metadata can't be read to the quarantine folder, where
+/// [`spawn_redownload_task`] finds it, and removes the files describing it
+fn quarantine(path: &Path, report: &mut RepairReport) {
+    let quarantine_dir = CACHE_DIR.join("quarantine");
+    if let Err(e) = std::fs::create_dir_all(&quarantine_dir) {
+        error!("Can't create quarantine folder: {e}");
+        return;
+    }
+    let Some(file_name) = path.file_name() else {
+        return;
+    };
+    if let Err(e) = std::fs::rename(path, quarantine_dir.join(file_name)) {
+        error!("Can't quarantine {}: {e}", path.display());
+        return;
+    }
+    warn!("Quarantined {}: its metadata can't be read", path.display());
+    report.quarantined += 1;
+    for extension in ["json", "sum", "jpg"] {
+        let path = path.with_extension(extension);
+        if path.exists() {
+            remove(&path, report);
+        }
+    }
+}
+
+/// Queues the download of the quarantined videos that weren't downloaded again yet,
+/// once their metadata is fetched from YouTube Music
+pub fn spawn_redownload_task() {
+    let Some(api) = api::shared() else {
+        return;
+    };
+    let video_ids = std::fs::read_dir(CACHE_DIR.join("quarantine"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let video_id = path.file_stem()?.to_str()?.to_owned();
+            (!audio_path(&video_id).exists()).then_some(video_id)
+        })
+        .collect::<HashSet<_>>();
+    if video_ids.is_empty() {
+        return;
+    }
+    run_service(async move {
+        for video_id in video_ids {
+            match api::resolve(&api, &video_id).await {
+                Ok(Some(video)) => {
+                    let mut download_list = LOW_PRIORITY_LIST.lock().unwrap();
+                    if !download_list.iter().any(|x| x.video_id == video_id) {
+                        info!("Scheduled the download of {video_id} again");
+                        download_list.push_back(video);
+                    }
+                }
+                Ok(None) => warn!("Can't find {video_id} on YouTube Music"),
+                Err(e) => error!("Can't fetch the metadata of {video_id}: {e}"),
+            }
+        }
+    });
+}
+
+fn remove(path: &Path, report: &mut RepairReport) {
+    match std::fs::remove_file(path) {
+        Ok(()) => {
+            info!("Removed stale file {}", path.display());
+            report.removed += 1;
+        }
+        Err(e) => error!("Can't remove {}: {e}", path.display()),
+    }
+}
@@ -0,0 +1,192 @@
+use flume::Sender;
+use log::{error, info};
+use rand::{seq::SliceRandom, Rng};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    consts::{CACHE_DIR, CONFIG},
+    database::{self, now, Database, PlayStats},
+    structures::playlist::Playlist,
+    tasks::offline,
+    term::{ManagerMessage, Screens},
+    utils::{audio_path, AUDIO_EXTENSIONS},
+    DATABASE,
+};
+
+/// Number of tracks of the "Most played" and "Recently played" playlists
+const PLAYLIST_SIZE: usize = 100;
+
+/// Builds the playlists computed from the ratings and play statistics
+pub fn stats_playlists() -> Vec<(String, Vec<YoutubeMusicVideoRef>)> {
+    let db = DATABASE.read().unwrap();
+    let with_stats = |filter: fn(&PlayStats) -> bool| {
+        db.iter()
+            .map(|video| (video, db.stats(&video.video_id)))
+            .filter(|(_, stats)| filter(stats))
+            .collect::<Vec<_>>()
+    };
+    let collect = |videos: Vec<(&YoutubeMusicVideoRef, PlayStats)>| {
+        videos
+            .into_iter()
+            .take(PLAYLIST_SIZE)
+            .map(|(video, _)| video.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let mut most_played = with_stats(|stats| stats.play_count > 0);
+    most_played
+        .sort_by_key(|(_, stats)| std::cmp::Reverse((stats.play_count, stats.listening_time)));
+    let mut recently_played = with_stats(|stats| !stats.never_played());
+    recently_played.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.last_played));
+    let never_played = with_stats(PlayStats::never_played)
+        .into_iter()
+        .map(|(video, _)| video.clone())
+        .collect();
+
+    vec![
+        ("Favorites".to_owned(), favorites(&db)),
+        ("Most played".to_owned(), collect(most_played)),
+        ("Recently played".to_owned(), collect(recently_played)),
+        ("Never played".to_owned(), never_played),
+    ]
+}
+
+/// The favorite videos, best rated first.
+/// Rated videos are kept when evicted, so favorites aren't limited to the cache.
+pub fn favorites(db: &Database) -> Vec<YoutubeMusicVideoRef> {
+    let mut favorites = db
+        .rated()
+        .filter(|(_, rating)| rating.favorite)
+        .collect::<Vec<_>>();
+    favorites.sort_by_cached_key(|(video, rating)| {
+        (
+            std::cmp::Reverse(rating.stars),
+            video.author.to_lowercase(),
+            video.title.to_lowercase(),
+        )
+    });
+    favorites
+        .into_iter()
+        .map(|(video, _)| video.clone())
+        .collect()
+}
+
+/// Sends the playlists computed from the ratings and play statistics to the chooser
+pub fn send_stats_playlists(updater_s: &Sender<ManagerMessage>) {
+    for (name, videos) in stats_playlists() {
+        if videos.is_empty() {
+            continue;
+        }
+        let playlist = Playlist::new(format!("stats:{name}"), name, videos);
+        updater_s
+            .send(ManagerMessage::AddElementToChooser(playlist).pass_to(Screens::Playlist))
+            .unwrap();
+    }
+}
+
+/// Shuffles the videos. With smart shuffle, the tracks that are often skipped
+/// or were played in the last day tend to come later.
+pub fn shuffle(videos: &mut [YoutubeMusicVideoRef]) {
+    let mut rng = rand::thread_rng();
+    if !CONFIG.player.smart_shuffle {
+        videos.shuffle(&mut rng);
+        return;
+    }
+    let now = now();
+    let db = DATABASE.read().unwrap();
+    // Weighted random sampling: each track gets a key of random^(1/weight)
+    let mut keyed = videos
+        .iter()
+        .map(|video| {
+            let weight = weight(&db.stats(&video.video_id), now);
+            (rng.gen::<f64>().powf(1.0 / weight), video.clone())
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    for (slot, (_, video)) in videos.iter_mut().zip(keyed) {
+        *slot = video;
+    }
+}
+
+fn weight(stats: &PlayStats, now: u64) -> f64 {
+    let completion =
+        (stats.play_count as f64 + 1.0) / ((stats.play_count + stats.skip_count) as f64 + 1.0);
+    let recency = if stats.never_played() {
+        1.0
+    } else {
+        let days = now.saturating_sub(stats.last_played) as f64 / 86_400.0;
+        days.clamp(0.1, 1.0)
+    };
+    completion * recency
+}
+
+/// Removes the least listened tracks until the cache fits in the configured size.
+/// Tracks of offline playlists are never evicted and play statistics are kept.
+pub fn evict() {
+    let limit = CONFIG.download.cache_limit * 1024 * 1024;
+    if limit == 0 {
+        return;
+    }
+    let mut total = 0;
+    let mut candidates = Vec::new();
+    {
+        let db = DATABASE.read().unwrap();
+        for video in db.iter() {
+            let Ok(metadata) = std::fs::metadata(audio_path(&video.video_id)) else {
+                continue;
+            };
+            total += metadata.len();
+            if offline::is_protected(&video.video_id) {
+                continue;
+            }
+            // Among tracks listened to as much, recently downloaded ones count as recently played
+            let added = metadata
+                .modified()
+                .ok()
+                .and_then(|x| x.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|x| x.as_secs())
+                .unwrap_or_default();
+            let stats = db.stats(&video.video_id);
+            candidates.push((
+                video.clone(),
+                metadata.len(),
+                (
+                    stats.play_count,
+                    stats.listening_time,
+                    stats.last_played.max(added),
+                ),
+            ));
+        }
+    }
+    if total <= limit {
+        return;
+    }
+    candidates.sort_by_key(|(_, _, key)| *key);
+    let mut evicted = 0;
+    for (video, size, _) in candidates {
+        if total <= limit {
+            break;
+        }
+        remove_cached(&video.video_id);
+        DATABASE.write().unwrap().remove(&video.video_id);
+        total -= size;
+        evicted += 1;
+    }
+    database::write();
+    info!("Evicted {evicted} tracks from the cache");
+}
+
+/// Removes the audio and every file stored next to it
+fn remove_cached(video_id: &str) {
+    for extension in AUDIO_EXTENSIONS
+        .iter()
+        .chain(&["json", "sum", "jpg", "lyrics"])
+    {
+        let path = CACHE_DIR.join(format!("downloads/{video_id}.{extension}"));
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                error!("Can't remove {}: {e}", path.display());
+            }
+        }
+    }
+}
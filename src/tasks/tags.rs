@@ -0,0 +1,71 @@
+use std::path::Path;
+
+use log::{error, info};
+use mp4ameta::{Data, FreeformIdent, Tag};
+use ytpapi2::{format_duration, parse_duration, YoutubeMusicVideoRef};
+
+/// Freeform atom holding the YouTube video id of the track
+pub const YOUTUBE_ID: FreeformIdent<'static> = FreeformIdent::new("com.ytermusic", "YOUTUBE_ID");
+/// Freeform atom holding the duration of the track as given by the API
+pub const DURATION: FreeformIdent<'static> = FreeformIdent::new("com.ytermusic", "DURATION");
+/// Freeform atom holding the channel id of the artist
+pub const ARTIST_ID: FreeformIdent<'static> = FreeformIdent::new("com.ytermusic", "ARTIST_ID");
+
+/// Embeds the metadata of the video as iTunes-style atoms in the downloaded file
+/// so the cache is usable by other players.
+pub fn write_tags(path: &Path, video: &YoutubeMusicVideoRef, cover: Option<Vec<u8>>) {
+    if path.extension().map(|x| x != "mp4").unwrap_or(true) {
+        info!(
+            "Skipped tags of {}: only MP4 files are tagged",
+            video.video_id
+        );
+        return;
+    }
+    if let Err(e) = try_write_tags(path, video, cover) {
+        error!("Can't write tags of {}: {e}", video.video_id);
+    } else {
+        info!("Wrote tags of {}", video.video_id);
+    }
+}
+
+/// Reads back the metadata written by [`write_tags`], to recover a file without sidecar
+pub fn read_tags(path: &Path) -> Option<YoutubeMusicVideoRef> {
+    let tag = Tag::read_from_path(path).ok()?;
+    Some(YoutubeMusicVideoRef {
+        title: tag.title()?.to_owned(),
+        author: tag.artist().unwrap_or_default().to_owned(),
+        album: tag.album().unwrap_or_default().to_owned(),
+        video_id: tag.strings_of(&YOUTUBE_ID).next()?.to_owned(),
+        duration: tag.strings_of(&DURATION).next().and_then(parse_duration),
+        thumbnail: String::new(),
+        artist_id: tag
+            .strings_of(&ARTIST_ID)
+            .next()
+            .unwrap_or_default()
+            .to_owned(),
+    })
+}
+
+fn try_write_tags(
+    path: &Path,
+    video: &YoutubeMusicVideoRef,
+    cover: Option<Vec<u8>>,
+) -> mp4ameta::Result<()> {
+    let mut tag = Tag::read_from_path(path)?;
+    tag.set_title(&video.title);
+    tag.set_artist(&video.author);
+    if !video.album.is_empty() {
+        tag.set_album(&video.album);
+    }
+    tag.set_data(YOUTUBE_ID, Data::Utf8(video.video_id.clone()));
+    if let Some(duration) = video.duration {
+        tag.set_data(DURATION, Data::Utf8(format_duration(duration)));
+    }
+    if !video.artist_id.is_empty() {
+        tag.set_data(ARTIST_ID, Data::Utf8(video.artist_id.clone()));
+    }
+    if let Some(cover) = cover {
+        tag.set_artwork(mp4ameta::Img::jpeg(cover));
+    }
+    tag.write_to_path(path)
+}
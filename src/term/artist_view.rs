@@ -0,0 +1,305 @@
+use std::sync::{Arc, RwLock};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use flume::Sender;
+use log::error;
+use ratatui::{layout::Rect, style::Style, Frame};
+use tokio::task::JoinHandle;
+use ytpapi2::{
+    YoutubeMusicArtist, YoutubeMusicArtistRef, YoutubeMusicInstance, YoutubeMusicPlaylistRef,
+    YoutubeMusicVideoRef,
+};
+
+use crate::{
+    consts::CONFIG,
+    run_service,
+    structures::{playlist::Playlist, sound_action::SoundAction},
+    tasks,
+    utils::invert,
+    DATABASE,
+};
+
+use super::{
+    duration_column,
+    item_list::{ListItem, ListItemAction},
+    EventResponse, ManagerMessage, Screen, Screens,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArtistItem {
+    /// The title of a section
+    Section,
+    Song(YoutubeMusicVideoRef),
+    /// An album or a single
+    Release(YoutubeMusicPlaylistRef),
+    Artist(YoutubeMusicArtistRef),
+}
+
+impl ListItemAction for ArtistItem {
+    fn render_style(&self, _: &str, selected: bool) -> Style {
+        let style = match self {
+            Self::Section => CONFIG.player.text_playing_style,
+            Self::Song(video) if !DATABASE.read().unwrap().contains(&video.video_id) => {
+                CONFIG.player.text_downloading_style
+            }
+            _ => CONFIG.player.text_next_style,
+        };
+        if selected {
+            invert(style)
+        } else {
+            style
+        }
+    }
+}
+
+/// The top songs, albums, singles, videos and related artists of an artist,
+/// with its downloaded songs
+pub struct ArtistView {
+    pub list: Arc<RwLock<ListItem<ArtistItem>>>,
+    pub goto: Screens,
+    pub api: Option<Arc<YoutubeMusicInstance>>,
+    pub handle: Option<JoinHandle<()>>,
+    pub action_sender: Sender<SoundAction>,
+    pub updater: Sender<ManagerMessage>,
+}
+
+impl Screen for ArtistView {
+    fn on_mouse_press(&mut self, mouse_event: MouseEvent, frame_data: &Rect) -> EventResponse {
+        let item = self
+            .list
+            .write()
+            .unwrap()
+            .on_mouse_press(mouse_event, frame_data);
+        match item {
+            Some(item) => self.execute(item, mouse_event.modifiers),
+            None => EventResponse::None,
+        }
+    }
+
+    fn on_key_press(&mut self, key: KeyEvent, _: &Rect) -> EventResponse {
+        let item = self.list.write().unwrap().on_key_press(key).cloned();
+        if let Some(item) = item {
+            return self.execute(item, key.modifiers);
+        }
+        match key.code {
+            KeyCode::Esc => ManagerMessage::ChangeState(self.goto).event(),
+            KeyCode::Char('f') => ManagerMessage::SearchFrom(Screens::Artist).event(),
+            KeyCode::Char('a') => match self.list.read().unwrap().select() {
+                Some(ArtistItem::Song(video)) => {
+                    ManagerMessage::AddToPlaylist(Screens::Artist, vec![video.clone()])
+                        .pass_to(Screens::PlaylistPicker)
+                        .event()
+                }
+                _ => EventResponse::None,
+            },
+            _ => EventResponse::None,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        frame.render_widget(&*self.list.read().unwrap(), frame.size());
+    }
+
+    fn handle_global_message(&mut self, message: ManagerMessage) -> EventResponse {
+        match message {
+            ManagerMessage::OpenArtist(screen, artist_id, name) => {
+                self.goto = screen;
+                self.load(artist_id, name);
+                ManagerMessage::ChangeState(Screens::Artist).event()
+            }
+            _ => EventResponse::None,
+        }
+    }
+
+    fn close(&mut self, _: Screens) -> EventResponse {
+        EventResponse::None
+    }
+
+    fn open(&mut self) -> EventResponse {
+        EventResponse::None
+    }
+}
+
+impl ArtistView {
+    /// Fetches the page of an artist, looking for the artist by name when the id is unknown
+    fn load(&mut self, artist_id: String, name: String) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+        let list = self.list.clone();
+        let Some(api) = self.api.clone() else {
+            let mut list = list.write().unwrap();
+            list.set_title(format!(" {name} (offline) "));
+            list.update_contents(downloaded(&name));
+            return;
+        };
+        list.write().unwrap().update_contents(Vec::new());
+        list.write().unwrap().set_title(format!(" Loading {name} "));
+        self.handle = Some(run_service(async move {
+            let artist_id = if artist_id.is_empty() {
+                find_artist(&api, &name).await
+            } else {
+                Some(artist_id)
+            };
+            let Some(artist_id) = artist_id else {
+                list.write()
+                    .unwrap()
+                    .set_title(format!(" Can't find the page of {name} "));
+                return;
+            };
+            match api.get_artist(&artist_id).await {
+                Ok(artist) => {
+                    let mut list = list.write().unwrap();
+                    list.set_title(format!(" {} ", artist.name));
+                    let mut rows = rows(artist);
+                    rows.extend(downloaded(&name));
+                    list.update_contents(rows);
+                }
+                Err(e) => {
+                    error!("{name} -> {e}");
+                    list.write()
+                        .unwrap()
+                        .set_title(format!(" Can't load the page of {name} "));
+                }
+            }
+        }));
+    }
+
+    fn execute(&mut self, item: ArtistItem, modifiers: KeyModifiers) -> EventResponse {
+        match item {
+            ArtistItem::Section => EventResponse::None,
+            ArtistItem::Song(video) => {
+                self.action_sender
+                    .send(SoundAction::AddVideoUnary(video.clone()))
+                    .unwrap();
+                tasks::download::start_task_unary(self.action_sender.clone(), video);
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    EventResponse::None
+                } else {
+                    ManagerMessage::PlayerFrom(Screens::Artist).event()
+                }
+            }
+            ArtistItem::Release(release) => {
+                let Some(api) = self.api.clone() else {
+                    return EventResponse::None;
+                };
+                let updater = self.updater.clone();
+                run_service(async move {
+                    let videos = match api.get_playlist(&release, 0).await {
+                        Ok(videos) => videos,
+                        Err(e) => {
+                            error!("{} -> {e}", release.name);
+                            // The downloaded tracks of the release can still be played
+                            let videos = DATABASE
+                                .read()
+                                .unwrap()
+                                .by_album(&release.name)
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            if videos.is_empty() {
+                                return;
+                            }
+                            videos
+                        }
+                    };
+                    updater
+                        .send(
+                            ManagerMessage::Inspect(
+                                Playlist::new(release.browse_id, release.name, videos),
+                                Screens::Artist,
+                            )
+                            .pass_to(Screens::PlaylistViewer),
+                        )
+                        .unwrap();
+                });
+                EventResponse::None
+            }
+            ArtistItem::Artist(artist) => {
+                self.load(artist.browse_id, artist.name);
+                EventResponse::None
+            }
+        }
+    }
+}
+
+/// The channel id of the first artist found when searching for the name
+async fn find_artist(api: &YoutubeMusicInstance, name: &str) -> Option<String> {
+    // Only the first artist is looked for when there are several
+    let name = name.split(" • ").next().unwrap_or(name);
+    match api
+        .search(&name.replace('\\', "\\\\").replace('\"', "\\\""), 0)
+        .await
+    {
+        Ok(results) => results
+            .playlists
+            .into_iter()
+            .find(|x| x.browse_id.starts_with("UC"))
+            .map(|x| x.browse_id),
+        Err(e) => {
+            error!("Search {name} -> {e}");
+            None
+        }
+    }
+}
+
+/// Appends a section of rows, unless it's empty
+fn section(rows: &mut Vec<(String, ArtistItem)>, title: &str, items: Vec<(String, ArtistItem)>) {
+    if !items.is_empty() {
+        rows.push((format!(" {title} "), ArtistItem::Section));
+        rows.extend(items);
+    }
+}
+
+fn songs(videos: Vec<YoutubeMusicVideoRef>) -> Vec<(String, ArtistItem)> {
+    videos
+        .into_iter()
+        .map(|video| {
+            (
+                format!("  {video}{}", duration_column(video.duration)),
+                ArtistItem::Song(video),
+            )
+        })
+        .collect()
+}
+
+/// The section of the downloaded songs of the artist
+fn downloaded(name: &str) -> Vec<(String, ArtistItem)> {
+    let videos = DATABASE.read().unwrap().by_artist(name).cloned().collect();
+    let mut rows = Vec::new();
+    section(&mut rows, "Downloaded", songs(videos));
+    rows
+}
+
+fn rows(artist: YoutubeMusicArtist) -> Vec<(String, ArtistItem)> {
+    let mut rows = Vec::new();
+    let mut section = |title: &str, items| section(&mut rows, title, items);
+    let releases = |releases: Vec<YoutubeMusicPlaylistRef>| -> Vec<_> {
+        releases
+            .into_iter()
+            .map(|release| {
+                (
+                    format!("  {} ({})", release.name, release.subtitle),
+                    ArtistItem::Release(release),
+                )
+            })
+            .collect()
+    };
+    section("Top songs", songs(artist.top_songs));
+    section("Albums", releases(artist.albums));
+    section("Singles", releases(artist.singles));
+    section("Videos", songs(artist.videos));
+    section(
+        "Related artists",
+        artist
+            .related
+            .into_iter()
+            .map(|artist| {
+                (
+                    format!("  {} ({})", artist.name, artist.subtitle),
+                    ArtistItem::Artist(artist),
+                )
+            })
+            .collect(),
+    );
+    rows
+}
@@ -0,0 +1,116 @@
+use log::warn;
+use once_cell::unsync::OnceCell;
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, Borders},
+    Frame,
+};
+use ratatui_image::{
+    picker::{Picker, ProtocolType},
+    protocol::StatefulProtocol,
+    StatefulImage,
+};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::{
+    config::CoverMode,
+    consts::CONFIG,
+    tasks::artwork::{cover_path, spawn_cover_task},
+};
+
+use super::split_x;
+
+/// The cover of the current music, shown next to the queue
+#[derive(Default)]
+pub struct CoverArt {
+    /// Created on the first render, once the terminal is in raw mode
+    picker: OnceCell<Option<Picker>>,
+    /// The video the cover was loaded for, `None` if it couldn't be decoded
+    current: Option<(String, Option<Box<dyn StatefulProtocol>>)>,
+}
+
+/// Renders with unicode half blocks, which works in every terminal with colors
+fn halfblocks_picker() -> Picker {
+    let mut picker = Picker::new((8, 16));
+    picker.protocol_type = ProtocolType::Halfblocks;
+    picker
+}
+
+fn new_picker() -> Option<Picker> {
+    match CONFIG.player.cover {
+        CoverMode::Off => None,
+        CoverMode::Halfblocks => Some(halfblocks_picker()),
+        // Uses kitty or sixel graphics when the terminal supports them
+        #[cfg(unix)]
+        CoverMode::Auto => Some(
+            Picker::from_termios()
+                .map(|mut picker| {
+                    picker.guess_protocol();
+                    picker
+                })
+                .unwrap_or_else(|e| {
+                    warn!("Can't query the terminal font size: {e}");
+                    halfblocks_picker()
+                }),
+        ),
+        #[cfg(not(unix))]
+        CoverMode::Auto => Some(halfblocks_picker()),
+    }
+}
+
+impl CoverArt {
+    /// Loads the cover of the video if it isn't already.
+    /// Returns whether there is a cover to show.
+    fn load(&mut self, video: Option<&YoutubeMusicVideoRef>) -> bool {
+        let Some(video) = video else {
+            self.current = None;
+            return false;
+        };
+        if let Some((video_id, protocol)) = &self.current {
+            if video_id == &video.video_id {
+                return protocol.is_some();
+            }
+        }
+        self.current = None;
+        self.picker.get_or_init(new_picker);
+        let Some(picker) = self.picker.get_mut().and_then(Option::as_mut) else {
+            return false;
+        };
+        let Ok(cover) = std::fs::read(cover_path(&video.video_id)) else {
+            spawn_cover_task(video.clone());
+            return false;
+        };
+        let protocol = match image::load_from_memory(&cover) {
+            Ok(image) => Some(picker.new_resize_protocol(image)),
+            Err(e) => {
+                warn!("Can't decode cover of {}: {e}", video.video_id);
+                None
+            }
+        };
+        let loaded = protocol.is_some();
+        self.current = Some((video.video_id.clone(), protocol));
+        loaded
+    }
+
+    /// Splits the area between the rest of the screen and the cover of the video
+    pub fn split(&mut self, area: Rect, video: Option<&YoutubeMusicVideoRef>) -> [Rect; 2] {
+        if !self.load(video) {
+            return split_x(area, 0);
+        }
+        // Terminal cells are about twice as high as they are wide
+        split_x(area, (area.height * 2).min(area.width / 3))
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let Some((_, Some(protocol))) = &mut self.current else {
+            return;
+        };
+        if area.width == 0 {
+            return;
+        }
+        let block = Block::default().borders(Borders::ALL);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        f.render_stateful_widget(StatefulImage::new(None), inner, protocol);
+    }
+}
@@ -147,6 +147,15 @@ impl<Action: Clone> ListItem<Action> {
         self.list.push(element);
     }
 
+    pub fn retain(&mut self, keep: impl FnMut(&(String, Action)) -> bool) {
+        self.list.retain(keep);
+        self.current_position = self.current_position.min(self.list.len().saturating_sub(1));
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut (String, Action)> {
+        self.list.iter_mut()
+    }
+
     pub fn set_title(&mut self, a: String) {
         self.title = a;
     }
@@ -14,6 +14,7 @@ pub struct ListSelector<Action> {
     list: Vec<(String, Action)>,
     current_position: usize,
     scroll_position: usize,
+    title: String,
 }
 
 impl<Action> Default for ListSelector<Action> {
@@ -22,6 +23,7 @@ impl<Action> Default for ListSelector<Action> {
             list: Default::default(),
             current_position: Default::default(),
             scroll_position: Default::default(),
+            title: " Playlist ".to_owned(),
         }
     }
 }
@@ -98,6 +100,10 @@ impl<Action> ListSelector<Action> {
         self.current_position = position.min(self.list.len().saturating_sub(1));
     }
 
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
     pub fn update(&mut self, list: Vec<(String, Action)>, current: usize) {
         if !self.is_scrolling() {
             self.scroll_position = current;
@@ -125,7 +131,11 @@ impl<Action: ListSelectorAction> Widget for &ListSelector<Action> {
                     })
                     .collect::<Vec<_>>(),
             )
-            .block(Block::default().borders(Borders::ALL).title(" Playlist ")),
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str()),
+            ),
             area,
             buf,
             &mut ListState::default(),
@@ -0,0 +1,132 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use ytpapi2::{YoutubeMusicLyrics, YoutubeMusicVideoRef};
+
+use crate::{consts::CONFIG, tasks::lyrics};
+
+use super::split_x;
+
+/// The lyrics of the current music, shown next to the queue
+pub struct LyricsPanel {
+    pub shown: bool,
+    /// The video the lyrics were loaded for, with its lyrics if they are cached
+    current: Option<(String, Option<YoutubeMusicLyrics>)>,
+    /// Whether the lyrics weren't cached and their download wasn't over when they were
+    /// loaded, so they have to be loaded again once it is
+    waiting: bool,
+}
+
+impl Default for LyricsPanel {
+    fn default() -> Self {
+        Self {
+            shown: CONFIG.player.lyrics,
+            current: None,
+            waiting: false,
+        }
+    }
+}
+
+impl LyricsPanel {
+    /// Loads the lyrics of the video from the cache, downloading them when they aren't in it.
+    /// The cache is only read again for the same video once the download is over.
+    fn load(&mut self, video: &YoutubeMusicVideoRef) {
+        if let Some((video_id, loaded)) = &self.current {
+            if video_id == &video.video_id {
+                if loaded.is_some() || !self.waiting {
+                    return;
+                }
+                // Nothing was requested if the API wasn't connected yet
+                lyrics::spawn_lyrics_task(video_id.clone());
+                if !lyrics::finished(video_id) {
+                    return;
+                }
+            }
+        }
+        let cached = lyrics::cached(&video.video_id);
+        if cached.is_none() {
+            lyrics::spawn_lyrics_task(video.video_id.clone());
+        }
+        self.waiting = cached.is_none() && !lyrics::finished(&video.video_id);
+        self.current = Some((video.video_id.clone(), cached));
+    }
+
+    /// Splits the area between the rest of the screen and the lyrics of the video
+    pub fn split(&mut self, area: Rect, video: Option<&YoutubeMusicVideoRef>) -> [Rect; 2] {
+        match video {
+            Some(video) if self.shown => {
+                self.load(video);
+                split_x(area, area.width / 2)
+            }
+            _ => split_x(area, 0),
+        }
+    }
+
+    /// Renders the lyrics scrolled to `elapsed` seconds, or to `progress` percent of the
+    /// lyrics when they aren't synced
+    pub fn render(&self, f: &mut Frame, area: Rect, elapsed: u32, progress: f64) {
+        if area.width == 0 {
+            return;
+        }
+        let Some((video_id, lyrics)) = &self.current else {
+            return;
+        };
+        let Some(lyrics) = lyrics else {
+            let text = if lyrics::searching(video_id) {
+                "Looking for the lyrics..."
+            } else {
+                "No lyrics"
+            };
+            f.render_widget(
+                Paragraph::new(text)
+                    .alignment(Alignment::Center)
+                    .style(CONFIG.player.text_next_style)
+                    .block(Block::default().title(" Lyrics ").borders(Borders::ALL)),
+                area,
+            );
+            return;
+        };
+        let height = area.height.saturating_sub(2) as usize;
+        let current = lyrics.line_at(elapsed * 1000);
+        let scroll = match current {
+            // The line being sung stays in the middle
+            Some(line) => line.saturating_sub(height / 2),
+            None if lyrics.is_synced() => 0,
+            None => {
+                (lyrics.lines.len().saturating_sub(height) as f64 * progress.clamp(0.0, 1.0))
+                    as usize
+            }
+        };
+        let lines = lyrics
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                Line::styled(
+                    line.text.as_str(),
+                    if Some(i) == current {
+                        CONFIG.player.text_playing_style
+                    } else {
+                        CONFIG.player.text_next_style
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let title = if lyrics.source.is_empty() {
+            " Lyrics ".to_owned()
+        } else {
+            format!(" Lyrics | {} ", lyrics.source)
+        };
+        f.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .style(CONFIG.player.text_next_style)
+                .scroll((scroll as u16, 0))
+                .block(Block::default().title(title).borders(Borders::ALL)),
+            area,
+        );
+    }
+}
@@ -1,34 +1,47 @@
+pub mod artist_view;
+pub mod cover;
 pub mod device_lost;
 pub mod item_list;
 pub mod list_selector;
+pub mod lyrics;
 pub mod music_player;
 pub mod playlist;
+pub mod playlist_picker;
 pub mod playlist_view;
 pub mod search;
 pub mod vertical_gauge;
 
 use std::{
     io::{self},
+    sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
 
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyEvent, KeyEventKind, KeyModifiers,
-        MouseEvent,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseEvent,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use flume::{Receiver, Sender};
 use ratatui::{backend::CrosstermBackend, layout::Rect, Frame, Terminal};
-use ytpapi2::YoutubeMusicVideoRef;
+use ytpapi2::{format_duration, YoutubeMusicVideoRef};
 
-use crate::{structures::sound_action::SoundAction, systems::player::PlayerState, SIGNALING_STOP};
+use crate::{
+    database,
+    structures::{playlist::Playlist, sound_action::SoundAction},
+    systems::player::PlayerState,
+    SIGNALING_STOP,
+};
 
-use self::{device_lost::DeviceLost, item_list::ListItem, playlist::Chooser, search::Search};
+use self::{
+    artist_view::ArtistView, device_lost::DeviceLost, item_list::ListItem, playlist::Chooser,
+    playlist_picker::PlaylistPicker, search::Search,
+};
 
-use crate::term::playlist_view::PlaylistView;
+use crate::term::playlist_view::{PlaylistSort, PlaylistView};
 
 // A trait to handle the different screens
 pub trait Screen {
@@ -51,7 +64,7 @@ pub enum EventResponse {
 pub enum ManagerMessage {
     Error(String, Box<Option<ManagerMessage>>),
     PassTo(Screens, Box<ManagerMessage>),
-    Inspect(String, Screens, Vec<YoutubeMusicVideoRef>),
+    Inspect(Playlist, Screens),
     ChangeState(Screens),
     SearchFrom(Screens),
     PlayerFrom(Screens),
@@ -59,7 +72,23 @@ pub enum ManagerMessage {
     PlaylistFrom(Screens),
     RestartPlayer,
     Quit,
-    AddElementToChooser((String, Vec<YoutubeMusicVideoRef>)),
+    /// Adds a playlist to the chooser or replaces the one with the same id
+    AddElementToChooser(Playlist),
+    /// Removes the playlist with the id from the chooser
+    RemoveFromChooser(String),
+    /// Picks a local playlist to add the videos to, then goes back to the screen
+    AddToPlaylist(Screens, Vec<YoutubeMusicVideoRef>),
+    /// Asks for the name of a new local playlist or the new name of a local playlist
+    NamePlaylist(Screens, Option<String>),
+    /// Asks for the path of a playlist file to import as a local playlist
+    ImportPlaylist(Screens),
+    /// Shows the page of an artist from its channel id, or from its name when the id
+    /// is unknown, then goes back to the screen
+    OpenArtist(Screens, String, String),
+    /// The new track list of a playlist edited on YouTube Music from its browse id,
+    /// shown again if it is inspected
+    PlaylistUpdated(String, Vec<YoutubeMusicVideoRef>),
+    Status(String),
 }
 
 impl ManagerMessage {
@@ -80,6 +109,8 @@ pub enum Screens {
     Search = 0x2,
     DeviceLost = 0x3,
     PlaylistViewer = 0x4,
+    PlaylistPicker = 0x5,
+    Artist = 0x6,
 }
 
 // The screen manager that handles the different screens
@@ -90,24 +121,50 @@ pub struct Manager {
     device_lost: DeviceLost,
     current_screen: Screens,
     playlist_viewer: PlaylistView,
+    playlist_picker: PlaylistPicker,
+    artist_view: ArtistView,
 }
 
 impl Manager {
     pub async fn new(action_sender: Sender<SoundAction>, music_player: PlayerState) -> Self {
+        let search = Search::new(action_sender.clone()).await;
         Self {
-            music_player,
+            artist_view: ArtistView {
+                list: Arc::new(RwLock::new(ListItem::new(" Artist ".to_owned()))),
+                goto: Screens::MusicPlayer,
+                api: search.api.clone(),
+                handle: None,
+                action_sender: action_sender.clone(),
+                updater: music_player.updater.clone(),
+            },
             chooser: Chooser {
                 action_sender: action_sender.clone(),
+                updater: music_player.updater.clone(),
                 goto: Screens::MusicPlayer,
                 item_list: ListItem::new(" Choose a playlist ".to_owned()),
+                delete: None,
             },
             playlist_viewer: PlaylistView {
                 sender: action_sender.clone(),
+                updater: music_player.updater.clone(),
                 items: ListItem::new(" Playlist ".to_owned()),
+                id: String::new(),
+                name: String::new(),
                 goto: Screens::Playlist,
                 videos: Vec::new(),
+                source: Vec::new(),
+                sort: PlaylistSort::default(),
+            },
+            playlist_picker: PlaylistPicker {
+                items: ListItem::new(" Playlists ".to_owned()),
+                videos: Vec::new(),
+                prompt: None,
+                error: None,
+                goto: Screens::Playlist,
+                updater: music_player.updater.clone(),
             },
-            search: Search::new(action_sender).await,
+            search,
+            music_player,
             current_screen: Screens::Playlist,
             device_lost: DeviceLost(Vec::new(), None),
         }
@@ -122,6 +179,8 @@ impl Manager {
             Screens::Search => &mut self.search,
             Screens::DeviceLost => &mut self.device_lost,
             Screens::PlaylistViewer => &mut self.playlist_viewer,
+            Screens::PlaylistPicker => &mut self.playlist_picker,
+            Screens::Artist => &mut self.artist_view,
         }
     }
     pub fn set_current_screen(&mut self, screen: Screens) {
@@ -299,3 +358,25 @@ pub fn relative_pos(rect: &Rect, x: u16, y: u16, margin: u16) -> (u16, u16) {
         y.saturating_sub(rect.y + margin),
     )
 }
+
+/// Shows the duration of a video after its title, nothing when it's unknown
+pub fn duration_column(duration: Option<u32>) -> String {
+    duration
+        .map(|x| format!(" [{}]", format_duration(x)))
+        .unwrap_or_default()
+}
+
+/// Rates a video with <kbd>*</kbd> to toggle it as favorite or a digit to give it stars
+/// (<kbd>0</kbd> removes them). Returns whether the key was a rating key.
+pub fn rate_with_key(key: KeyCode, video: &YoutubeMusicVideoRef) -> bool {
+    match key {
+        KeyCode::Char('*') => {
+            database::rate(video, |rating| rating.favorite = !rating.favorite);
+        }
+        KeyCode::Char(c @ '0'..='5') => {
+            database::rate(video, |rating| rating.stars = c as u8 - b'0');
+        }
+        _ => return false,
+    }
+    true
+}
@@ -1,7 +1,7 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEventKind};
 
-use rand::seq::SliceRandom;
 use ratatui::widgets::{Block, Borders, Gauge};
+use ytpapi2::{format_duration, LikeStatus};
 
 use crate::{
     consts::CONFIG,
@@ -14,11 +14,12 @@ use crate::{
         download::DOWNLOAD_LIST,
         player::{generate_music, PlayerAction, PlayerState},
     },
+    tasks::{likes, playlist_file, stats},
 };
 
 use super::{
-    rect_contains, relative_pos, split_x, split_y, vertical_gauge::VerticalGauge, EventResponse,
-    ManagerMessage, Screen, Screens,
+    rate_with_key, rect_contains, relative_pos, split_x, split_y, vertical_gauge::VerticalGauge,
+    EventResponse, ManagerMessage, Screen, Screens,
 };
 
 impl Screen for PlayerState {
@@ -31,6 +32,8 @@ impl Screen for PlayerState {
         let y = mouse_event.row;
         let [top_rect, bottom] = split_y(*frame_data, 3);
         let [list_rect, volume_rect] = split_x(top_rect, 10);
+        let [list_rect, _] = self.cover.split(list_rect, self.current.as_ref());
+        let [list_rect, _] = self.lyrics.split(list_rect, self.current.as_ref());
         if let MouseEventKind::Down(_) = &mouse_event.kind {
             if rect_contains(&list_rect, x, y, 1) {
                 let (_, y) = relative_pos(&list_rect, x, y, 1);
@@ -118,6 +121,16 @@ impl Screen for PlayerState {
                 EventResponse::None
             }
             KeyCode::Char('f') => ManagerMessage::SearchFrom(Screens::MusicPlayer).event(),
+            KeyCode::Char('g') => match &self.current {
+                Some(video) => ManagerMessage::OpenArtist(
+                    Screens::MusicPlayer,
+                    video.artist_id.clone(),
+                    video.author.clone(),
+                )
+                .pass_to(Screens::Artist)
+                .event(),
+                None => EventResponse::None,
+            },
             KeyCode::Char('s') => {
                 let mut musics = Vec::with_capacity(self.previous.len() + self.queue.len() + 1);
                 musics.append(&mut self.previous);
@@ -126,15 +139,58 @@ impl Screen for PlayerState {
                 }
                 let queue = std::mem::take(&mut self.queue);
                 musics.extend(queue);
-                musics.shuffle(&mut rand::thread_rng());
+                stats::shuffle(&mut musics);
                 self.queue = musics.into();
                 handle_error(&self.updater, "sink stop", self.sink.stop(&self.guard));
                 EventResponse::None
             }
+            KeyCode::Char('r') => {
+                self.autoplay.toggle();
+                EventResponse::None
+            }
+            KeyCode::Char('L') => {
+                self.lyrics.shown = !self.lyrics.shown;
+                EventResponse::None
+            }
             KeyCode::Char('C') => {
                 SoundAction::Cleanup.apply_sound_action(self);
                 EventResponse::None
             }
+            KeyCode::Char('x') => {
+                let mut musics = self.previous.clone();
+                musics.extend(self.current.clone());
+                musics.extend(self.queue.iter().cloned());
+                let status = playlist_file::export_to_directory("Queue", &musics);
+                ManagerMessage::Status(status)
+                    .pass_to(Screens::Playlist)
+                    .event()
+            }
+            KeyCode::Char('a') => match self.highlighted() {
+                Some(video) => {
+                    ManagerMessage::AddToPlaylist(Screens::MusicPlayer, vec![video.clone()])
+                        .pass_to(Screens::PlaylistPicker)
+                        .event()
+                }
+                None => EventResponse::None,
+            },
+            KeyCode::Char('*' | '0'..='5') => {
+                if let Some(video) = self.highlighted().cloned() {
+                    rate_with_key(key.code, &video);
+                }
+                EventResponse::None
+            }
+            KeyCode::Char('y') => {
+                if let Some(video) = self.highlighted() {
+                    likes::toggle(&video.video_id, LikeStatus::Like);
+                }
+                EventResponse::None
+            }
+            KeyCode::Char('n') => {
+                if let Some(video) = self.highlighted() {
+                    likes::toggle(&video.video_id, LikeStatus::Dislike);
+                }
+                EventResponse::None
+            }
             KeyCode::Char(' ') => {
                 SoundAction::PlayPause.apply_sound_action(self);
                 EventResponse::None
@@ -195,6 +251,11 @@ impl Screen for PlayerState {
         let render_volume_slider = CONFIG.player.volume_slider;
         let [top_rect, progress_rect] = split_y(f.size(), 3);
         let [list_rect, volume_rect] = split_x(top_rect, if render_volume_slider { 10 } else { 0 });
+        let [list_rect, cover_rect] = self.cover.split(list_rect, self.current.as_ref());
+        self.cover.render(f, cover_rect);
+        let [list_rect, lyrics_rect] = self.lyrics.split(list_rect, self.current.as_ref());
+        self.lyrics
+            .render(f, lyrics_rect, self.sink.elapsed(), self.sink.percentage());
         let colors = if self.sink.is_paused() {
             AppStatus::Paused
         } else if self.sink.is_finished() {
@@ -213,7 +274,12 @@ impl Screen for PlayerState {
             );
         }
         let current_time = self.sink.elapsed();
-        let total_time = self.sink.duration().map(|x| x as u32).unwrap_or(0);
+        let total_time = self
+            .sink
+            .duration()
+            .map(|x| x as u32)
+            .or_else(|| self.current.as_ref()?.duration)
+            .unwrap_or(0);
         f.render_widget(
             Gauge::default()
                 .block(
@@ -221,7 +287,11 @@ impl Screen for PlayerState {
                         .title(
                             self.current
                                 .as_ref()
-                                .map(|x| format!(" {x} "))
+                                .map(|x| match likes::status(&x.video_id) {
+                                    Some(LikeStatus::Like) => format!(" {x} [liked] "),
+                                    Some(LikeStatus::Dislike) => format!(" {x} [disliked] "),
+                                    _ => format!(" {x} "),
+                                })
                                 .unwrap_or_else(|| " No music playing ".to_owned()),
                         )
                         .borders(Borders::ALL),
@@ -244,6 +314,21 @@ impl Screen for PlayerState {
                 )),
             progress_rect,
         );
+        let (queue_total, queue_left) = self.queue_time();
+        let autoplay = if self.autoplay.enabled {
+            "| autoplay "
+        } else {
+            ""
+        };
+        self.list_selector.set_title(if queue_total == 0 {
+            format!(" Playlist {autoplay}")
+        } else {
+            format!(
+                " Playlist | {} left of {} {autoplay}",
+                format_duration(queue_left),
+                format_duration(queue_total)
+            )
+        });
         // Create a List from all list items and highlight the currently selected one
         self.list_selector.update(
             generate_music(
@@ -252,6 +337,7 @@ impl Screen for PlayerState {
                 &self.previous,
                 &self.current,
                 &self.sink,
+                &self.autoplay,
             ),
             self.previous.len(),
         );
@@ -10,7 +10,14 @@ use ratatui::{
 use ytpapi2::YoutubeMusicVideoRef;
 
 use crate::{
-    consts::CACHE_DIR, structures::sound_action::SoundAction, systems::download, DATABASE,
+    consts::CACHE_DIR,
+    structures::{playlist::Playlist, sound_action::SoundAction},
+    systems::download,
+    tasks::{
+        export, local_musics::LOCAL_MUSICS_ID, local_playlists, offline, playlist_file,
+        remote_playlists, stats,
+    },
+    DATABASE,
 };
 
 use super::{
@@ -37,40 +44,54 @@ pub struct Chooser {
     pub item_list: ListItem<ChooserAction>,
     pub goto: Screens,
     pub action_sender: Sender<SoundAction>,
+    pub updater: Sender<ManagerMessage>,
+    /// The browse id of the playlist of the library to delete from YouTube Music if `D` is
+    /// pressed again
+    pub delete: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct PlayListEntry {
-    pub name: String,
-    pub videos: Vec<YoutubeMusicVideoRef>,
+    pub playlist: Playlist,
     pub text_to_show: String,
 }
 
 impl PlayListEntry {
-    pub fn new(name: String, videos: Vec<YoutubeMusicVideoRef>) -> Self {
+    pub fn new(playlist: Playlist) -> Self {
         Self {
-            text_to_show: format_playlist(&name, &videos),
-            name,
-            videos,
+            text_to_show: format_playlist(&playlist.id, &playlist.name, &playlist.videos),
+            playlist,
         }
     }
 
     pub fn tupplelize(&self) -> (&String, &Vec<YoutubeMusicVideoRef>) {
-        (&self.name, &self.videos)
+        (&self.playlist.name, &self.playlist.videos)
     }
 }
-pub fn format_playlist(name: &str, videos: &[YoutubeMusicVideoRef]) -> String {
+pub fn format_playlist(id: &str, name: &str, videos: &[YoutubeMusicVideoRef]) -> String {
     let db = DATABASE.read().unwrap();
-    let local_videos = videos
-        .iter()
-        .filter(|x| db.iter().any(|y| x.video_id == y.video_id))
-        .count();
+    let local_videos = videos.iter().filter(|x| db.contains(&x.video_id)).count();
     format!(
-        "{}     ({}/{} {}%)",
+        "{}     ({}/{} {}%){}{}{}",
         name,
         local_videos,
         videos.len(),
-        (local_videos as f32 / videos.len() as f32 * 100.0) as u8
+        (local_videos as f32 / videos.len() as f32 * 100.0) as u8,
+        if local_playlists::from_id(id).is_some() {
+            " [local]"
+        } else {
+            ""
+        },
+        if remote_playlists::exists(id) {
+            " [YouTube Music]"
+        } else {
+            ""
+        },
+        if offline::is_pinned(id) {
+            " [offline]"
+        } else {
+            ""
+        }
     )
 }
 impl Screen for Chooser {
@@ -83,9 +104,8 @@ impl Screen for Chooser {
         {
             if PLAYER_RUNNING.load(std::sync::atomic::Ordering::SeqCst) {
                 return EventResponse::Message(vec![ManagerMessage::Inspect(
-                    a.name,
+                    a.playlist,
                     Screens::Playlist,
-                    a.videos,
                 )
                 .pass_to(Screens::PlaylistViewer)]);
             }
@@ -100,18 +120,81 @@ impl Screen for Chooser {
         if let Some(ChooserAction::Play(a)) = self.item_list.on_key_press(key).cloned() {
             if PLAYER_RUNNING.load(std::sync::atomic::Ordering::SeqCst) {
                 return EventResponse::Message(vec![ManagerMessage::Inspect(
-                    a.name,
+                    a.playlist,
                     Screens::Playlist,
-                    a.videos,
                 )
                 .pass_to(Screens::PlaylistViewer)]);
             }
             self.play(&a);
             return EventResponse::Message(vec![ManagerMessage::ChangeState(Screens::MusicPlayer)]);
         }
+        let delete = self.delete.take();
         match key.code {
             KeyCode::Esc => return ManagerMessage::ChangeState(Screens::MusicPlayer).event(),
             KeyCode::Char('f') => return ManagerMessage::SearchFrom(Screens::Playlist).event(),
+            KeyCode::Char('o') => {
+                if let Some(ChooserAction::Play(a)) = self.item_list.select() {
+                    offline::toggle(&a.playlist);
+                    self.refresh();
+                }
+            }
+            KeyCode::Char('n') => {
+                return ManagerMessage::NamePlaylist(Screens::Playlist, None)
+                    .pass_to(Screens::PlaylistPicker)
+                    .event();
+            }
+            KeyCode::Char('r') => {
+                if let Some(ChooserAction::Play(a)) = self.item_list.select() {
+                    if let Some(name) = local_playlists::from_id(&a.playlist.id) {
+                        return ManagerMessage::NamePlaylist(
+                            Screens::Playlist,
+                            Some(name.to_owned()),
+                        )
+                        .pass_to(Screens::PlaylistPicker)
+                        .event();
+                    }
+                }
+            }
+            KeyCode::Char('D') | KeyCode::Delete => {
+                if let Some(ChooserAction::Play(a)) = self.item_list.select().cloned() {
+                    let Playlist { id, name, .. } = a.playlist;
+                    if local_playlists::from_id(&id).is_some_and(local_playlists::delete) {
+                        self.item_list
+                            .retain(|(_, ChooserAction::Play(x))| x.playlist.id != id);
+                    } else if delete.as_ref() == Some(&id) {
+                        self.item_list
+                            .set_title(format!(" Deleting {name} from YouTube Music "));
+                        remote_playlists::spawn_delete_task(id, self.updater.clone());
+                    } else if remote_playlists::exists(&id) {
+                        self.item_list.set_title(format!(
+                            " Press D again to delete {name} from YouTube Music "
+                        ));
+                        self.delete = Some(id);
+                    }
+                }
+            }
+            KeyCode::Char('i') => {
+                return ManagerMessage::ImportPlaylist(Screens::Playlist)
+                    .pass_to(Screens::PlaylistPicker)
+                    .event();
+            }
+            KeyCode::Char('x') => {
+                if let Some(ChooserAction::Play(a)) = self.item_list.select() {
+                    let status =
+                        playlist_file::export_to_directory(&a.playlist.name, &a.playlist.videos);
+                    self.item_list.set_title(status);
+                }
+            }
+            KeyCode::Char('X') => {
+                let videos = DATABASE.read().unwrap().videos();
+                let status = playlist_file::export_to_directory("Library", &videos);
+                self.item_list.set_title(status);
+            }
+            KeyCode::Char('e') => {
+                self.item_list
+                    .set_title(" Exporting the library... ".to_owned());
+                export::spawn_export_task(self.updater.clone());
+            }
             _ => {}
         }
         EventResponse::None
@@ -122,8 +205,13 @@ impl Screen for Chooser {
     }
 
     fn handle_global_message(&mut self, message: super::ManagerMessage) -> EventResponse {
-        if let ManagerMessage::AddElementToChooser(a) = message {
-            self.add_element(a);
+        match message {
+            ManagerMessage::AddElementToChooser(a) => self.add_element(a),
+            ManagerMessage::RemoveFromChooser(id) => self
+                .item_list
+                .retain(|(_, ChooserAction::Play(x))| x.playlist.id != id),
+            ManagerMessage::Status(a) => self.item_list.set_title(a),
+            _ => {}
         }
         EventResponse::None
     }
@@ -133,6 +221,9 @@ impl Screen for Chooser {
     }
 
     fn open(&mut self) -> EventResponse {
+        // Ratings and play statistics may have changed since the chooser was last shown
+        stats::send_stats_playlists(&self.updater);
+        self.refresh();
         EventResponse::None
     }
 }
@@ -140,7 +231,7 @@ pub static PLAYER_RUNNING: AtomicBool = AtomicBool::new(false);
 
 impl Chooser {
     fn play(&mut self, a: &PlayListEntry) {
-        if a.name != "Local musics" {
+        if a.playlist.id != LOCAL_MUSICS_ID {
             std::fs::write(
                 CACHE_DIR.join("last-playlist.json"),
                 serde_json::to_string(&a.tupplelize()).unwrap(),
@@ -150,12 +241,34 @@ impl Chooser {
         self.action_sender.send(SoundAction::Cleanup).unwrap();
         download::clean(&self.action_sender);
         self.action_sender
-            .send(SoundAction::AddVideosToQueue(a.videos.clone()))
+            .send(SoundAction::AddVideosToQueue(a.playlist.videos.clone()))
             .unwrap();
     }
-    fn add_element(&mut self, element: (String, Vec<YoutubeMusicVideoRef>)) {
-        let entry = PlayListEntry::new(element.0, element.1);
+    fn add_element(&mut self, playlist: Playlist) {
+        offline::refresh(&playlist);
+        let entry = PlayListEntry::new(playlist);
+        // A pinned playlist is sent once from the disk and once from the API.
+        // The ones pinned before ids were used are sent from the disk with their name as id.
+        if let Some((text, ChooserAction::Play(existing))) =
+            self.item_list
+                .iter_mut()
+                .find(|(_, ChooserAction::Play(x))| {
+                    x.playlist.id == entry.playlist.id || x.playlist.id == entry.playlist.name
+                })
+        {
+            text.clone_from(&entry.text_to_show);
+            *existing = entry;
+            return;
+        }
         self.item_list
             .add_element((entry.text_to_show.clone(), ChooserAction::Play(entry)));
     }
+    /// Recomputes the download progress and offline badge of every playlist
+    fn refresh(&mut self) {
+        for (text, ChooserAction::Play(entry)) in self.item_list.iter_mut() {
+            let Playlist { id, name, videos } = &entry.playlist;
+            entry.text_to_show = format_playlist(id, name, videos);
+            text.clone_from(&entry.text_to_show);
+        }
+    }
 }
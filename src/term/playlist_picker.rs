@@ -0,0 +1,303 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use flume::Sender;
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Paragraph},
+    Frame,
+};
+use ytpapi2::YoutubeMusicVideoRef;
+
+use crate::tasks::{api, local_playlists, playlist_file, remote_playlists};
+
+use super::{
+    item_list::{ListItem, ListItemAction},
+    split_y_start, EventResponse, ManagerMessage, Screen, Screens,
+};
+
+#[derive(Clone)]
+pub enum PickerAction {
+    Add(String),
+    New,
+    /// A playlist of the library on YouTube Music, from its browse id and name
+    AddRemote(String, String),
+    NewRemote,
+}
+
+impl ListItemAction for PickerAction {
+    fn render_style(&self, _: &str, selected: bool) -> Style {
+        if selected {
+            Style::default().fg(Color::Black).bg(Color::White)
+        } else {
+            Style::default().fg(Color::White).bg(Color::Black)
+        }
+    }
+}
+
+/// What the typed name is for
+#[derive(Clone)]
+pub enum Prompt {
+    Create,
+    CreateRemote,
+    Rename(String),
+    Import,
+}
+
+/// Picks the local playlist or the playlist of the library to add videos to, or asks for
+/// the name of a local playlist or for the playlist file to import
+pub struct PlaylistPicker {
+    pub items: ListItem<PickerAction>,
+    /// The videos to add to the picked playlist
+    pub videos: Vec<YoutubeMusicVideoRef>,
+    pub prompt: Option<(Prompt, String)>,
+    pub error: Option<String>,
+    pub goto: Screens,
+    pub updater: Sender<ManagerMessage>,
+}
+
+impl Screen for PlaylistPicker {
+    fn on_mouse_press(
+        &mut self,
+        mouse_event: crossterm::event::MouseEvent,
+        frame_data: &Rect,
+    ) -> EventResponse {
+        let [_, list_rect] = split_y_start(*frame_data, 3);
+        if self.prompt.is_some() {
+            return EventResponse::None;
+        }
+        match self.items.on_mouse_press(mouse_event, &list_rect) {
+            Some(action) => self.pick(action),
+            None => EventResponse::None,
+        }
+    }
+
+    fn on_key_press(&mut self, key: KeyEvent, _: &Rect) -> EventResponse {
+        if let Some((prompt, text)) = &mut self.prompt {
+            self.error = None;
+            match key.code {
+                KeyCode::Esc => {
+                    if self.videos.is_empty() {
+                        return ManagerMessage::ChangeState(self.goto).event();
+                    }
+                    self.prompt = None;
+                }
+                KeyCode::Enter => {
+                    let (prompt, name) = (prompt.clone(), text.trim().to_owned());
+                    return self.submit(prompt, name);
+                }
+                KeyCode::Delete | KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => {}
+            }
+            return EventResponse::None;
+        }
+        if let Some(action) = self.items.on_key_press(key).cloned() {
+            return self.pick(action);
+        }
+        match key.code {
+            KeyCode::Esc => ManagerMessage::ChangeState(self.goto).event(),
+            _ => EventResponse::None,
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let [input_rect, list_rect] = split_y_start(frame.size(), 3);
+        let (title, text) = match &self.prompt {
+            Some((Prompt::Create, text)) => (" Name of the new playlist ".to_owned(), text.clone()),
+            Some((Prompt::CreateRemote, text)) => (
+                " Name of the new YouTube Music playlist ".to_owned(),
+                text.clone(),
+            ),
+            Some((Prompt::Rename(name), text)) => (format!(" Rename {name} "), text.clone()),
+            Some((Prompt::Import, text)) => {
+                (" Path of the file to import ".to_owned(), text.clone())
+            }
+            None => (
+                format!(" Add {} musics to a playlist ", self.videos.len()),
+                String::new(),
+            ),
+        };
+        let (text, color) = match &self.error {
+            Some(error) => (error.clone(), Color::Red),
+            None => (text, Color::LightCyan),
+        };
+        frame.render_widget(
+            Paragraph::new(text)
+                .style(Style::default().fg(color))
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::White))
+                        .title(title)
+                        .border_type(BorderType::Plain),
+                ),
+            input_rect,
+        );
+        frame.render_widget(&self.items, list_rect);
+    }
+
+    fn handle_global_message(&mut self, m: ManagerMessage) -> EventResponse {
+        match m {
+            ManagerMessage::AddToPlaylist(screen, videos) => {
+                self.goto = screen;
+                self.videos = videos;
+                self.prompt = None;
+            }
+            ManagerMessage::NamePlaylist(screen, name) => {
+                self.goto = screen;
+                self.videos = Vec::new();
+                self.prompt = Some(match name {
+                    Some(name) => (Prompt::Rename(name.clone()), name),
+                    None => (Prompt::Create, String::new()),
+                });
+            }
+            ManagerMessage::ImportPlaylist(screen) => {
+                self.goto = screen;
+                self.videos = Vec::new();
+                self.prompt = Some((Prompt::Import, String::new()));
+            }
+            _ => return EventResponse::None,
+        }
+        self.error = None;
+        let mut items = local_playlists::names()
+            .into_iter()
+            .map(|name| (format!("  {name}"), PickerAction::Add(name)))
+            .collect::<Vec<_>>();
+        items.extend(
+            remote_playlists::playlists()
+                .into_iter()
+                .map(|(browse_id, name)| {
+                    (
+                        format!("  {name} [YouTube Music]"),
+                        PickerAction::AddRemote(browse_id, name),
+                    )
+                }),
+        );
+        items.push(("  + New playlist".to_owned(), PickerAction::New));
+        if api::shared().is_some() {
+            items.push((
+                "  + New YouTube Music playlist".to_owned(),
+                PickerAction::NewRemote,
+            ));
+        }
+        self.items.update(items, 0);
+        ManagerMessage::ChangeState(Screens::PlaylistPicker).event()
+    }
+
+    fn close(&mut self, _: Screens) -> EventResponse {
+        EventResponse::None
+    }
+
+    fn open(&mut self) -> EventResponse {
+        EventResponse::None
+    }
+}
+
+impl PlaylistPicker {
+    fn pick(&mut self, action: PickerAction) -> EventResponse {
+        match action {
+            PickerAction::Add(name) => self.add_to(name),
+            PickerAction::New => {
+                self.prompt = Some((Prompt::Create, String::new()));
+                EventResponse::None
+            }
+            PickerAction::AddRemote(browse_id, name) => {
+                let status = format!(" Adding {} musics to {name} ", self.videos.len());
+                remote_playlists::spawn_add_task(
+                    browse_id,
+                    self.videos.clone(),
+                    self.updater.clone(),
+                );
+                self.go_back(status)
+            }
+            PickerAction::NewRemote => {
+                self.prompt = Some((Prompt::CreateRemote, String::new()));
+                EventResponse::None
+            }
+        }
+    }
+
+    /// Creates a playlist of the library with the videos on YouTube Music
+    fn create_remote(&mut self, name: String) -> EventResponse {
+        if name.is_empty() {
+            self.error = Some("The name of a playlist can't be empty".to_owned());
+            return EventResponse::None;
+        }
+        if local_playlists::exists(&name)
+            || remote_playlists::playlists()
+                .iter()
+                .any(|(_, remote)| remote == &name)
+        {
+            self.error = Some(format!("A playlist named {name} already exists"));
+            return EventResponse::None;
+        }
+        let status = format!(" Creating {name} on YouTube Music ");
+        remote_playlists::spawn_create_task(name, self.videos.clone(), self.updater.clone());
+        self.go_back(status)
+    }
+
+    /// Shows the status in the chooser and goes back to the previous screen
+    fn go_back(&self, status: String) -> EventResponse {
+        EventResponse::Message(vec![
+            ManagerMessage::Status(status).pass_to(Screens::Playlist),
+            ManagerMessage::ChangeState(self.goto),
+        ])
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: String) -> EventResponse {
+        let result = match &prompt {
+            Prompt::CreateRemote => return self.create_remote(text),
+            Prompt::Create => local_playlists::create(&text).map(|_| (text, Vec::new())),
+            Prompt::Rename(old) => local_playlists::rename(old, &text).map(|x| (text, x)),
+            Prompt::Import => playlist_file::import(Path::new(&text)),
+        };
+        let (name, videos) = match result {
+            Ok(x) => x,
+            Err(e) => {
+                self.error = Some(e);
+                return EventResponse::None;
+            }
+        };
+        if !self.videos.is_empty() {
+            return self.add_to(name);
+        }
+        let mut messages = Vec::new();
+        match prompt {
+            Prompt::Rename(old) => messages.push(
+                ManagerMessage::RemoveFromChooser(local_playlists::id(&old))
+                    .pass_to(Screens::Playlist),
+            ),
+            Prompt::Import => messages.push(
+                ManagerMessage::Status(format!(" Imported {} musics as {name} ", videos.len()))
+                    .pass_to(Screens::Playlist),
+            ),
+            Prompt::Create | Prompt::CreateRemote => {}
+        }
+        messages.push(
+            ManagerMessage::AddElementToChooser(local_playlists::playlist(&name, videos))
+                .pass_to(Screens::Playlist),
+        );
+        messages.push(ManagerMessage::ChangeState(self.goto));
+        EventResponse::Message(messages)
+    }
+
+    /// Adds the videos to a playlist and goes back to the previous screen
+    fn add_to(&mut self, name: String) -> EventResponse {
+        let Some(videos) = local_playlists::add(&name, &self.videos) else {
+            self.error = Some(format!("No playlist named {name}"));
+            return EventResponse::None;
+        };
+        let status = format!(" Added {} musics to {name} ", self.videos.len());
+        EventResponse::Message(vec![
+            ManagerMessage::AddElementToChooser(local_playlists::playlist(&name, videos))
+                .pass_to(Screens::Playlist),
+            ManagerMessage::Status(status).pass_to(Screens::Playlist),
+            ManagerMessage::ChangeState(self.goto),
+        ])
+    }
+}
@@ -3,11 +3,19 @@ use flume::Sender;
 use ratatui::{layout::Rect, style::Style, Frame};
 use ytpapi2::YoutubeMusicVideoRef;
 
-use crate::{consts::CONFIG, structures::sound_action::SoundAction, utils::invert, DATABASE};
+use crate::{
+    consts::CONFIG,
+    database::{now, Database, PlayStats},
+    structures::{playlist::Playlist, sound_action::SoundAction},
+    tasks::{local_playlists, offline, playlist_file, remote_playlists},
+    utils::invert,
+    DATABASE,
+};
 
 use super::{
+    duration_column,
     item_list::{ListItem, ListItemAction},
-    EventResponse, ManagerMessage, Screen, Screens,
+    rate_with_key, EventResponse, ManagerMessage, Screen, Screens,
 };
 
 #[derive(Clone)]
@@ -29,12 +37,53 @@ impl ListItemAction for PlayListAction {
     }
 }
 
+/// Order of the tracks in the playlist viewer
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaylistSort {
+    #[default]
+    Playlist,
+    MostPlayed,
+    RecentlyPlayed,
+    Title,
+    Artist,
+}
+
+impl PlaylistSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Playlist => Self::MostPlayed,
+            Self::MostPlayed => Self::RecentlyPlayed,
+            Self::RecentlyPlayed => Self::Title,
+            Self::Title => Self::Artist,
+            Self::Artist => Self::Playlist,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Playlist => "playlist order",
+            Self::MostPlayed => "most played",
+            Self::RecentlyPlayed => "recently played",
+            Self::Title => "title",
+            Self::Artist => "artist",
+        }
+    }
+}
+
 // Audio device not connected!
 pub struct PlaylistView {
     pub items: ListItem<PlayListAction>,
+    /// The id of the playlist in the chooser
+    pub id: String,
+    pub name: String,
+    /// The videos in the displayed order
     pub videos: Vec<YoutubeMusicVideoRef>,
+    /// The videos in the playlist order
+    pub source: Vec<YoutubeMusicVideoRef>,
+    pub sort: PlaylistSort,
     pub goto: Screens,
     pub sender: Sender<SoundAction>,
+    pub updater: Sender<ManagerMessage>,
 }
 
 impl Screen for PlaylistView {
@@ -63,6 +112,56 @@ impl Screen for PlaylistView {
         match key.code {
             KeyCode::Esc => ManagerMessage::ChangeState(self.goto).event(),
             KeyCode::Char('f') => ManagerMessage::SearchFrom(Screens::PlaylistViewer).event(),
+            KeyCode::Char('o') => {
+                offline::toggle(&Playlist::new(
+                    self.id.clone(),
+                    self.name.clone(),
+                    self.source.clone(),
+                ));
+                self.update_title();
+                EventResponse::None
+            }
+            KeyCode::Char('*' | '0'..='5') => {
+                if let Some(PlayListAction(i, _)) = self.items.select().cloned() {
+                    if rate_with_key(key.code, &self.videos[i]) {
+                        self.refresh_row(i);
+                    }
+                }
+                EventResponse::None
+            }
+            KeyCode::Char('a') => {
+                let Some(PlayListAction(i, _)) = self.items.select() else {
+                    return EventResponse::None;
+                };
+                ManagerMessage::AddToPlaylist(
+                    Screens::PlaylistViewer,
+                    vec![self.videos[*i].clone()],
+                )
+                .pass_to(Screens::PlaylistPicker)
+                .event()
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                self.edit(|source, i| Some(Edit::Remove(source.position(i)?)))
+            }
+            KeyCode::Char('K') => self.edit(|source, i| {
+                let from = source.position_in_order(i)?;
+                Some(Edit::Move(from, from.checked_sub(1)?))
+            }),
+            KeyCode::Char('J') => self.edit(|source, i| {
+                let from = source.position_in_order(i)?;
+                Some(Edit::Move(from, from + 1))
+            }),
+            KeyCode::Char('x') => {
+                let status = playlist_file::export_to_directory(&self.name, &self.source);
+                self.items.set_title(status);
+                EventResponse::None
+            }
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.update_title();
+                self.refresh(0);
+                EventResponse::None
+            }
             _ => EventResponse::None,
         }
     }
@@ -73,26 +172,27 @@ impl Screen for PlaylistView {
 
     fn handle_global_message(&mut self, m: ManagerMessage) -> EventResponse {
         match m {
-            ManagerMessage::Inspect(a, screen, m) => {
-                self.items.set_title(format!(" Inspecting {a} "));
+            ManagerMessage::Inspect(playlist, screen) => {
+                self.id = playlist.id;
+                self.name = playlist.name;
+                self.update_title();
                 self.goto = screen;
-                let db = DATABASE.read().unwrap();
-                self.items.update(
-                    m.iter()
-                        .enumerate()
-                        .map(|(i, m)| {
-                            (
-                                format!("  {m}"),
-                                PlayListAction(i, !db.iter().any(|x| x.video_id == m.video_id)),
-                            )
-                        })
-                        .collect(),
-                    0,
-                );
-                self.videos = m;
+                self.source = playlist.videos;
+                self.refresh(0);
 
                 EventResponse::Message(vec![ManagerMessage::ChangeState(Screens::PlaylistViewer)])
             }
+            ManagerMessage::PlaylistUpdated(id, videos) => {
+                if id == self.id {
+                    self.update_title();
+                    self.show(videos);
+                }
+                EventResponse::None
+            }
+            ManagerMessage::Status(status) => {
+                self.items.set_title(status);
+                EventResponse::None
+            }
             _ => EventResponse::None,
         }
     }
@@ -105,3 +205,193 @@ impl Screen for PlaylistView {
         EventResponse::None
     }
 }
+
+impl PlaylistView {
+    fn update_title(&mut self) {
+        let sort = if self.sort == PlaylistSort::Playlist {
+            String::new()
+        } else {
+            format!(" (by {})", self.sort.name())
+        };
+        if offline::is_pinned(&self.id) {
+            self.items
+                .set_title(format!(" Inspecting {}{sort} [offline] ", self.name));
+        } else {
+            self.items
+                .set_title(format!(" Inspecting {}{sort} ", self.name));
+        }
+    }
+
+    /// Edits the playlist being inspected from the selected video. A local playlist is
+    /// shown again and updated in the chooser right away, a playlist of the library once
+    /// YouTube Music made the change.
+    fn edit(&mut self, change: impl FnOnce(Source, usize) -> Option<Edit>) -> EventResponse {
+        let local = local_playlists::from_id(&self.id).is_some();
+        if !local && !remote_playlists::exists(&self.id) {
+            return EventResponse::None;
+        }
+        let Some(PlayListAction(i, _)) = self.items.select().cloned() else {
+            return EventResponse::None;
+        };
+        let source = Source {
+            videos: &self.source,
+            displayed: &self.videos,
+            sorted: self.sort != PlaylistSort::Playlist,
+        };
+        let Some(edit) = change(source, i) else {
+            return EventResponse::None;
+        };
+        if !local {
+            let id = self.id.clone();
+            match edit {
+                Edit::Remove(index) => {
+                    remote_playlists::spawn_remove_task(id, index, self.updater.clone())
+                }
+                Edit::Move(from, to) => {
+                    remote_playlists::spawn_move_task(id, from, to, self.updater.clone())
+                }
+            }
+            self.items
+                .set_title(format!(" Editing {} on YouTube Music ", self.name));
+            return EventResponse::None;
+        }
+        let videos = match edit {
+            Edit::Remove(index) => local_playlists::remove(&self.name, index),
+            Edit::Move(from, to) => local_playlists::move_track(&self.name, from, to),
+        };
+        let Some(videos) = videos else {
+            return EventResponse::None;
+        };
+        self.show(videos.clone());
+        ManagerMessage::AddElementToChooser(local_playlists::playlist(&self.name, videos))
+            .pass_to(Screens::Playlist)
+            .event()
+    }
+
+    /// Shows the new track list of the playlist, keeping the selection on the same video
+    fn show(&mut self, videos: Vec<YoutubeMusicVideoRef>) {
+        let Some(PlayListAction(i, _)) = self.items.select().cloned() else {
+            self.source = videos;
+            self.refresh(0);
+            return;
+        };
+        let selected = self.videos[i].video_id.clone();
+        self.source = videos;
+        self.refresh(0);
+        if let Some(position) = self.videos.iter().position(|x| x.video_id == selected) {
+            self.items.select_to(position);
+        } else {
+            self.items.select_to(i);
+        }
+    }
+
+    /// Sorts the videos and recomputes the rows with their play statistics
+    fn refresh(&mut self, selected: usize) {
+        let db = DATABASE.read().unwrap();
+        let mut videos = self
+            .source
+            .iter()
+            .map(|video| (db.stats(&video.video_id), video.clone()))
+            .collect::<Vec<_>>();
+        match self.sort {
+            PlaylistSort::Playlist => {}
+            PlaylistSort::MostPlayed => videos.sort_by_key(|(stats, _)| {
+                std::cmp::Reverse((stats.play_count, stats.listening_time))
+            }),
+            PlaylistSort::RecentlyPlayed => {
+                videos.sort_by_key(|(stats, _)| std::cmp::Reverse(stats.last_played))
+            }
+            PlaylistSort::Title => {
+                videos.sort_by_cached_key(|(_, video)| video.title.to_lowercase())
+            }
+            PlaylistSort::Artist => videos.sort_by_cached_key(|(_, video)| {
+                (video.author.to_lowercase(), video.title.to_lowercase())
+            }),
+        }
+        let now = now();
+        self.items.update(
+            videos
+                .iter()
+                .enumerate()
+                .map(|(i, (_, video))| {
+                    (
+                        row(video, &db, now),
+                        PlayListAction(i, !db.contains(&video.video_id)),
+                    )
+                })
+                .collect(),
+            selected,
+        );
+        self.videos = videos.into_iter().map(|(_, video)| video).collect();
+    }
+
+    /// Recomputes the row of the video at `index` without moving the selection
+    fn refresh_row(&mut self, index: usize) {
+        let db = DATABASE.read().unwrap();
+        let text = row(&self.videos[index], &db, now());
+        if let Some((line, _)) = self
+            .items
+            .iter_mut()
+            .find(|(_, PlayListAction(i, _))| *i == index)
+        {
+            *line = text;
+        }
+    }
+}
+
+/// A change of the inspected playlist, with positions in the playlist order
+enum Edit {
+    Remove(usize),
+    Move(usize, usize),
+}
+
+/// The track list of the inspected playlist, to find a displayed video in it
+struct Source<'a> {
+    videos: &'a [YoutubeMusicVideoRef],
+    displayed: &'a [YoutubeMusicVideoRef],
+    sorted: bool,
+}
+
+impl Source<'_> {
+    /// The position in the playlist of the displayed video at `index`
+    fn position(&self, index: usize) -> Option<usize> {
+        if !self.sorted {
+            return Some(index);
+        }
+        let video_id = &self.displayed.get(index)?.video_id;
+        self.videos.iter().position(|x| &x.video_id == video_id)
+    }
+
+    /// The position in the playlist of the displayed video at `index`,
+    /// if the videos are displayed in the playlist order
+    fn position_in_order(&self, index: usize) -> Option<usize> {
+        (!self.sorted).then_some(index)
+    }
+}
+
+fn row(video: &YoutubeMusicVideoRef, db: &Database, now: u64) -> String {
+    format!(
+        "  {}{video}{}{}",
+        db.rating(&video.video_id).glyph(),
+        duration_column(db.duration(video)),
+        stats_column(&db.stats(&video.video_id), now)
+    )
+}
+
+/// Shows how much and when a track was last played
+fn stats_column(stats: &PlayStats, now: u64) -> String {
+    if stats.never_played() {
+        return String::new();
+    }
+    let elapsed = now.saturating_sub(stats.last_played);
+    let ago = match elapsed {
+        0..=59 => "just now".to_owned(),
+        60..=3599 => format!("{} min ago", elapsed / 60),
+        3600..=86399 => format!("{} h ago", elapsed / 3600),
+        _ => format!("{} days ago", elapsed / 86400),
+    };
+    format!(
+        "  ({} plays, {} skips, {ago})",
+        stats.play_count, stats.skip_count
+    )
+}
@@ -10,17 +10,26 @@ use ratatui::{
     Frame,
 };
 use tokio::task::JoinHandle;
-use ytpapi2::{SearchResults, YoutubeMusicInstance, YoutubeMusicPlaylistRef, YoutubeMusicVideoRef};
+use ytpapi2::{
+    LikeStatus, SearchResults, YoutubeMusicAlbum, YoutubeMusicArtistRef, YoutubeMusicInstance,
+    YoutubeMusicPlaylistRef, YoutubeMusicVideoRef,
+};
 
 use crate::{
-    consts::CONFIG, get_header_file, run_service, structures::sound_action::SoundAction, tasks,
-    utils::invert, DATABASE,
+    consts::CONFIG,
+    database::Rating,
+    run_service,
+    structures::{playlist::Playlist, sound_action::SoundAction},
+    tasks::{self, likes},
+    utils::invert,
+    DATABASE,
 };
 
 use super::{
+    duration_column,
     item_list::{ListItem, ListItemAction},
     playlist::format_playlist,
-    split_y_start, EventResponse, ManagerMessage, Screen, Screens,
+    rate_with_key, split_y_start, EventResponse, ManagerMessage, Screen, Screens,
 };
 
 pub struct Search {
@@ -36,13 +45,18 @@ pub enum Status {
     Local(YoutubeMusicVideoRef),
     Unknown(YoutubeMusicVideoRef),
     PlayList(YoutubeMusicPlaylistRef, Vec<YoutubeMusicVideoRef>),
+    Artist(YoutubeMusicArtistRef),
+    /// Played in the order of its tracks, even when shuffling is enabled
+    Album(YoutubeMusicAlbum),
 }
 impl ListItemAction for Status {
     fn render_style(&self, _: &str, selected: bool) -> Style {
         let k = match self {
             Self::Local(_) => CONFIG.player.text_next_style,
             Self::Unknown(_) => CONFIG.player.text_downloading_style,
-            Self::PlayList(_, _) => CONFIG.player.text_next_style,
+            Self::PlayList(_, _) | Self::Artist(_) | Self::Album(_) => {
+                CONFIG.player.text_next_style
+            }
         };
         if selected {
             invert(k)
@@ -75,6 +89,61 @@ impl Screen for Search {
         if KeyCode::Esc == key.code {
             return ManagerMessage::ChangeState(self.goto).event();
         }
+        // Letters and digits go to the search text, rating and adding to a playlist need Alt
+        if key.modifiers.contains(KeyModifiers::ALT) {
+            let selected = self.list.read().unwrap().select().cloned();
+            match selected {
+                Some(Status::PlayList(_, videos)) if key.code == KeyCode::Char('a') => {
+                    return ManagerMessage::AddToPlaylist(Screens::Search, videos)
+                        .pass_to(Screens::PlaylistPicker)
+                        .event();
+                }
+                Some(Status::Album(album)) => match key.code {
+                    KeyCode::Char('a') => {
+                        return ManagerMessage::AddToPlaylist(Screens::Search, album.videos())
+                            .pass_to(Screens::PlaylistPicker)
+                            .event();
+                    }
+                    KeyCode::Char('g') => {
+                        return ManagerMessage::OpenArtist(
+                            Screens::Search,
+                            album.artist_id,
+                            album.artist,
+                        )
+                        .pass_to(Screens::Artist)
+                        .event();
+                    }
+                    _ => {}
+                },
+                Some(Status::Local(video) | Status::Unknown(video)) => {
+                    if key.code == KeyCode::Char('g') {
+                        return ManagerMessage::OpenArtist(
+                            Screens::Search,
+                            video.artist_id,
+                            video.author,
+                        )
+                        .pass_to(Screens::Artist)
+                        .event();
+                    }
+                    if key.code == KeyCode::Char('a') {
+                        return ManagerMessage::AddToPlaylist(Screens::Search, vec![video])
+                            .pass_to(Screens::PlaylistPicker)
+                            .event();
+                    }
+                    if key.code == KeyCode::Char('y') {
+                        likes::toggle(&video.video_id, LikeStatus::Like);
+                    }
+                    if key.code == KeyCode::Char('n') {
+                        likes::toggle(&video.video_id, LikeStatus::Dislike);
+                    }
+                    if rate_with_key(key.code, &video) {
+                        self.refresh_rating(&video);
+                    }
+                }
+                _ => {}
+            }
+            return EventResponse::None;
+        }
         if let Some(e) = self.list.write().unwrap().on_key_press(key) {
             return self.execute_status(e.clone(), key.modifiers);
         }
@@ -98,17 +167,22 @@ impl Screen for Search {
 
         let text = self.text.to_lowercase();
 
-        let local = DATABASE
-            .read()
-            .unwrap()
+        let db = DATABASE.read().unwrap();
+        let local = db
             .iter()
             .filter(|x| {
                 x.title.to_lowercase().contains(&text) || x.author.to_lowercase().contains(&text)
             })
             .cloned()
-            .map(|video| (format!(" {video} "), Status::Local(video)))
+            .map(|video| {
+                (
+                    video_row(&video, db.rating(&video.video_id)),
+                    Status::Local(video),
+                )
+            })
             .take(100)
             .collect::<Vec<_>>();
+        drop(db);
         self.list.write().unwrap().update_contents(local.clone());
 
         if let Some(api) = self.api.clone() {
@@ -128,9 +202,10 @@ impl Screen for Search {
                     }) => {
                         for video in e.into_iter() {
                             let id = video.video_id.clone();
+                            let rating = DATABASE.read().unwrap().rating(&id);
                             item.push((
-                                format!(" {video} "),
-                                if DATABASE.read().unwrap().iter().any(|x| x.video_id == id) {
+                                video_row(&video, rating),
+                                if DATABASE.read().unwrap().contains(&id) {
                                     Status::Local(video)
                                 } else {
                                     Status::Unknown(video)
@@ -138,8 +213,44 @@ impl Screen for Search {
                             ));
                         }
                         for playlist in p.into_iter() {
+                            // Artists are found with the playlists, their page is opened
+                            // when they are selected
+                            if playlist.browse_id.starts_with("UC") {
+                                item.push((
+                                    format!(" [A] {} ({})", playlist.name, playlist.subtitle),
+                                    Status::Artist(YoutubeMusicArtistRef {
+                                        name: playlist.name,
+                                        subtitle: playlist.subtitle,
+                                        browse_id: playlist.browse_id,
+                                    }),
+                                ));
+                                continue;
+                            }
                             let api = api.clone();
                             let items = items.clone();
+                            if playlist.browse_id.starts_with("MPREb_") {
+                                run_service(async move {
+                                    match api.get_album(&playlist.browse_id).await {
+                                        Ok(album) => {
+                                            if album.tracks.is_empty() {
+                                                return;
+                                            }
+                                            items.write().unwrap().add_element((
+                                                format_playlist(
+                                                    &playlist.browse_id,
+                                                    &album_name(&album),
+                                                    &album.videos(),
+                                                ),
+                                                Status::Album(album),
+                                            ));
+                                        }
+                                        Err(e) => {
+                                            error!("{} -> {e}", playlist.name);
+                                        }
+                                    };
+                                });
+                                continue;
+                            }
                             run_service(async move {
                                 match api.get_playlist(&playlist, 0).await {
                                     Ok(e) => {
@@ -148,6 +259,7 @@ impl Screen for Search {
                                         }
                                         items.write().unwrap().add_element((
                                             format_playlist(
+                                                &playlist.browse_id,
                                                 &format!(
                                                     " [P] {} ({})",
                                                     playlist.name, playlist.subtitle
@@ -158,14 +270,14 @@ impl Screen for Search {
                                         ));
                                     }
                                     Err(e) => {
-                                        error!("{e:?}");
+                                        error!("{} -> {e}", playlist.name);
                                     }
                                 };
                             });
                         }
                     }
                     Err(e) => {
-                        error!("{e:?}");
+                        error!("Search -> {e}");
                     }
                 }
                 let mut local = local;
@@ -218,14 +330,23 @@ impl Search {
             ))),
             goto: Screens::MusicPlayer,
             search_handle: None,
-            api: YoutubeMusicInstance::from_header_file(get_header_file().unwrap().1.as_path())
-                .await
-                .ok()
-                .map(Arc::new),
+            api: tasks::api::connect().await.ok().map(Arc::new),
             action_sender,
         }
     }
 
+    /// Shows the new rating of a video in the results
+    fn refresh_rating(&self, video: &YoutubeMusicVideoRef) {
+        let rating = DATABASE.read().unwrap().rating(&video.video_id);
+        for (text, status) in self.list.write().unwrap().iter_mut() {
+            if let Status::Local(x) | Status::Unknown(x) = status {
+                if x.video_id == video.video_id {
+                    *text = video_row(x, rating);
+                }
+            }
+        }
+    }
+
     pub fn execute_status(&self, e: Status, modifiers: KeyModifiers) -> EventResponse {
         match e {
             Status::Local(e) | Status::Unknown(e) => {
@@ -239,9 +360,35 @@ impl Search {
                     ManagerMessage::PlayerFrom(Screens::Playlist).event()
                 }
             }
-            Status::PlayList(e, v) => ManagerMessage::Inspect(e.name, Screens::Search, v)
-                .pass_to(Screens::PlaylistViewer)
+            Status::PlayList(e, v) => {
+                ManagerMessage::Inspect(Playlist::new(e.browse_id, e.name, v), Screens::Search)
+                    .pass_to(Screens::PlaylistViewer)
+                    .event()
+            }
+            Status::Artist(e) => ManagerMessage::OpenArtist(Screens::Search, e.browse_id, e.name)
+                .pass_to(Screens::Artist)
                 .event(),
+            Status::Album(e) => {
+                self.action_sender
+                    .send(SoundAction::ReplaceQueue(e.videos()))
+                    .unwrap();
+                ManagerMessage::PlayerFrom(Screens::Playlist).event()
+            }
         }
     }
 }
+
+fn album_name(album: &YoutubeMusicAlbum) -> String {
+    match album.year {
+        Some(year) => format!(" [Album] {} - {} ({year})", album.title, album.artist),
+        None => format!(" [Album] {} - {}", album.title, album.artist),
+    }
+}
+
+fn video_row(video: &YoutubeMusicVideoRef, rating: Rating) -> String {
+    format!(
+        " {}{video}{} ",
+        rating.glyph(),
+        duration_column(video.duration)
+    )
+}
@@ -1,10 +1,40 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
 use directories::ProjectDirs;
 use ratatui::style::Style;
 
+use crate::consts::CACHE_DIR;
+
+/// Extensions of the audio files in the cache
+pub const AUDIO_EXTENSIONS: [&str; 2] = ["mp4", "webm"];
+
 /// Get directories for the project for config, cache, etc.
 pub fn get_project_dirs() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "ccgauche", "ytermusic")
 }
+/// Get the path of the cached audio of a video, `<video_id>.mp4` if it isn't downloaded
+pub fn audio_path(video_id: &str) -> PathBuf {
+    let downloads = CACHE_DIR.join("downloads");
+    AUDIO_EXTENSIONS
+        .iter()
+        .map(|extension| downloads.join(format!("{video_id}.{extension}")))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| downloads.join(format!("{video_id}.mp4")))
+}
+/// Writes to a temporary file which then replaces the file,
+/// so a crash can't leave a partially written file behind
+pub fn replace_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(temporary, path)
+}
 /// Invert a style
 pub fn invert(style: Style) -> Style {
     Style {
@@ -0,0 +1,18 @@
+//! Serves the recorded fixtures like YouTube Music, for testing the app without it.
+//! Set `api.base_url` in the config to the printed URL and use a `headers.txt` with a
+//! cookie like `Cookie: SAPISID=mock`.
+//!
+//! `cargo run -p ytpapi2 --features mock --example mock_server [address]`
+
+use ytpapi2::mock::MockServer;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:8789".to_owned());
+    let server = MockServer::bind(address).await?;
+    println!("Mock InnerTube server on {}", server.url());
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    json_extractor::{
+        from_json, get_artist_id, get_text, get_thumbnail, get_video_from_album,
+        YoutubeMusicVideoRef,
+    },
+    Result, YoutubeMusicError,
+};
+
+/// An album, EP or single with its tracks in the order of the release
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YoutubeMusicAlbum {
+    pub title: String,
+    pub artist: String,
+    /// Channel id of the artist, empty when unknown
+    pub artist_id: String,
+    /// Year of the release, `None` when unknown
+    pub year: Option<u16>,
+    /// URL of the largest cover, empty when unknown
+    pub thumbnail: String,
+    pub tracks: Vec<YoutubeMusicAlbumTrack>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YoutubeMusicAlbumTrack {
+    /// Position of the track on the release, starting at 1
+    pub number: u32,
+    pub video: YoutubeMusicVideoRef,
+}
+
+impl YoutubeMusicAlbum {
+    /// The videos of the tracks, in order
+    pub fn videos(&self) -> Vec<YoutubeMusicVideoRef> {
+        self.tracks.iter().map(|x| x.video.clone()).collect()
+    }
+}
+
+/// The header of an album page, which changed name with the redesign of the album pages
+fn get_header(value: &Value) -> Option<Value> {
+    value
+        .get("musicDetailHeaderRenderer")
+        .or_else(|| value.get("musicResponsiveHeaderRenderer"))
+        .cloned()
+}
+
+/// A track and the number shown next to it
+fn get_track(value: &Value) -> Option<(Option<u32>, YoutubeMusicVideoRef)> {
+    let video = get_video_from_album(value)?;
+    let number = value
+        .get("index")
+        .and_then(|x| get_text(x, false, false))
+        .and_then(|x| x.trim().parse().ok());
+    Some((number, video))
+}
+
+pub(crate) fn parse_album(json: &Value) -> Result<YoutubeMusicAlbum> {
+    let header = from_json(json, get_header)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| YoutubeMusicError::Other("The page isn't an album".to_owned()))?;
+    let title = header
+        .get("title")
+        .and_then(|x| get_text(x, false, false))
+        .unwrap_or_default();
+    let subtitles = header
+        .get("subtitle")
+        .and_then(|x| x.get("runs"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let year = subtitles
+        .iter()
+        .filter_map(|x| get_text(x, false, false))
+        .find(|x| x.len() == 4 && x.chars().all(|x| x.is_ascii_digit()))
+        .and_then(|x| x.parse().ok());
+    // The redesigned header shows the artist under the title instead of in the subtitle
+    let artist = header
+        .get("straplineTextOne")
+        .and_then(|x| get_text(x, false, true))
+        .or_else(|| {
+            subtitles
+                .iter()
+                .find(|x| get_artist_id(x).is_some())
+                .and_then(|x| get_text(x, false, false))
+        })
+        .unwrap_or_default();
+    let artist_id = get_artist_id(&header).unwrap_or_default();
+    let thumbnail = get_thumbnail(&header).unwrap_or_default();
+
+    let tracks = from_json(json, get_track)?
+        .into_iter()
+        .enumerate()
+        .map(|(i, (number, mut video))| {
+            if video.author.is_empty() {
+                video.author = artist.clone();
+            }
+            if video.artist_id.is_empty() {
+                video.artist_id = artist_id.clone();
+            }
+            video.album = title.clone();
+            if video.thumbnail.is_empty() {
+                video.thumbnail = thumbnail.clone();
+            }
+            YoutubeMusicAlbumTrack {
+                number: number.unwrap_or(i as u32 + 1),
+                video,
+            }
+        })
+        .collect();
+    Ok(YoutubeMusicAlbum {
+        title,
+        artist,
+        artist_id,
+        year,
+        thumbnail,
+        tracks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::fixture;
+
+    #[test]
+    fn album() {
+        let json = fixture("album");
+        let album = parse_album(&json).unwrap();
+        assert_eq!(album.title, "Fixture Album");
+        assert_eq!(album.artist, "Album Artist");
+        assert_eq!(album.artist_id, "UCalbumartist");
+        assert_eq!(album.year, Some(2019));
+        assert_eq!(
+            album.thumbnail,
+            "https://lh3.googleusercontent.com/fixture-album=w544-h544"
+        );
+        assert_eq!(
+            album
+                .tracks
+                .iter()
+                .map(|x| (x.number, x.video.title.as_str(), x.video.duration))
+                .collect::<Vec<_>>(),
+            vec![(1, "Opening", Some(241)), (2, "Interlude", Some(58))]
+        );
+        for video in album.videos() {
+            assert_eq!(video.album, "Fixture Album");
+            assert_eq!(video.author, "Album Artist");
+            assert_eq!(video.artist_id, "UCalbumartist");
+            assert_eq!(video.thumbnail, album.thumbnail);
+        }
+    }
+
+    #[test]
+    fn not_an_album() {
+        let json = fixture("search");
+        assert!(matches!(
+            parse_album(&json),
+            Err(YoutubeMusicError::Other(_))
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    json_extractor::{
+        from_json, get_playlist, get_text, get_video, get_video_from_carousel, YoutubeMusicVideoRef,
+    },
+    Result, YoutubeMusicPlaylistRef,
+};
+
+#[derive(Debug, Clone, PartialOrd, Eq, Ord, PartialEq, Hash, Serialize, Deserialize)]
+pub struct YoutubeMusicArtistRef {
+    pub name: String,
+    /// Usually the number of subscribers
+    pub subtitle: String,
+    /// Channel id of the artist
+    pub browse_id: String,
+}
+
+/// The sections of an artist page. Albums and singles can be opened like playlists.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YoutubeMusicArtist {
+    pub name: String,
+    pub top_songs: Vec<YoutubeMusicVideoRef>,
+    pub albums: Vec<YoutubeMusicPlaylistRef>,
+    pub singles: Vec<YoutubeMusicPlaylistRef>,
+    pub videos: Vec<YoutubeMusicVideoRef>,
+    pub related: Vec<YoutubeMusicArtistRef>,
+}
+
+/// A list or a carousel of an artist page
+#[derive(PartialEq)]
+enum Shelf {
+    List(Value),
+    Carousel(Value),
+}
+
+fn get_shelf(value: &Value) -> Option<Shelf> {
+    if let Some(shelf) = value.get("musicShelfRenderer") {
+        return Some(Shelf::List(shelf.clone()));
+    }
+    value
+        .get("musicCarouselShelfRenderer")
+        .map(|shelf| Shelf::Carousel(shelf.clone()))
+}
+
+/// Whether the releases are singles: they are only given a year, when albums are given
+/// their type (album or EP) and their year
+fn are_singles(releases: &[YoutubeMusicPlaylistRef]) -> bool {
+    releases.iter().all(|x| !x.subtitle.contains('•'))
+}
+
+/// Sorts the shelves of an artist page by what they contain rather than by their
+/// titles, which are translated
+pub(crate) fn parse_artist(json: &Value) -> Result<YoutubeMusicArtist> {
+    let name = json
+        .get("header")
+        .and_then(Value::as_object)
+        .and_then(|x| x.values().next())
+        .and_then(|x| x.get("title"))
+        .and_then(|x| get_text(x, false, false))
+        .unwrap_or_default();
+    let mut artist = YoutubeMusicArtist {
+        name,
+        ..Default::default()
+    };
+    for shelf in from_json(json, get_shelf)? {
+        let shelf = match shelf {
+            Shelf::List(shelf) => {
+                artist.top_songs.extend(from_json(&shelf, get_video)?);
+                continue;
+            }
+            Shelf::Carousel(shelf) => shelf,
+        };
+        let videos = from_json(&shelf, get_video_from_carousel)?;
+        if !videos.is_empty() {
+            artist.videos.extend(videos);
+            continue;
+        }
+        let items = from_json(&shelf, get_playlist)?;
+        if items.is_empty() {
+            continue;
+        }
+        if items.iter().all(|x| x.browse_id.starts_with("UC")) {
+            artist
+                .related
+                .extend(items.into_iter().map(|x| YoutubeMusicArtistRef {
+                    name: x.name,
+                    subtitle: x.subtitle,
+                    browse_id: x.browse_id,
+                }));
+        } else if items.iter().all(|x| x.browse_id.starts_with("MPREb_")) {
+            if are_singles(&items) {
+                artist.singles.extend(items);
+            } else {
+                artist.albums.extend(items);
+            }
+        }
+    }
+    Ok(artist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::fixture;
+
+    #[test]
+    fn artist() {
+        let json = fixture("artist");
+        let artist = parse_artist(&json).unwrap();
+        assert_eq!(artist.name, "First Artist");
+        assert_eq!(
+            artist
+                .top_songs
+                .iter()
+                .map(|x| (x.title.as_str(), x.album.as_str(), x.duration))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Greatest Hit", "First Album", Some(212)),
+                ("Second Hit", "Second Album", Some(187)),
+            ]
+        );
+        assert!(artist
+            .top_songs
+            .iter()
+            .all(|x| x.artist_id == "UCartist000000000000001"));
+        let names = |releases: &[YoutubeMusicPlaylistRef]| {
+            releases
+                .iter()
+                .map(|x| (x.name.clone(), x.browse_id.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&artist.albums),
+            vec![("First Album".to_owned(), "MPREb_firstalbum".to_owned())]
+        );
+        assert_eq!(
+            names(&artist.singles),
+            vec![("Summer Single".to_owned(), "MPREb_single".to_owned())]
+        );
+        assert_eq!(artist.videos.len(), 1);
+        assert_eq!(artist.videos[0].title, "Greatest Hit (Official Video)");
+        assert_eq!(artist.videos[0].video_id, "artistVideo1");
+        assert_eq!(artist.videos[0].artist_id, "UCartist000000000000001");
+        assert_eq!(
+            artist.related,
+            vec![YoutubeMusicArtistRef {
+                name: "Second Artist".to_owned(),
+                subtitle: "1.2M subscribers".to_owned(),
+                browse_id: "UCartist000000000000002".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn singles_before_albums() {
+        let mut json = fixture("artist");
+        json["contents"]["singleColumnBrowseResultsRenderer"]["tabs"][0]["tabRenderer"]["content"]
+            ["sectionListRenderer"]["contents"]
+            .as_array_mut()
+            .unwrap()
+            .reverse();
+        let artist = parse_artist(&json).unwrap();
+        assert_eq!(artist.albums[0].browse_id, "MPREb_firstalbum");
+        assert_eq!(artist.singles[0].browse_id, "MPREb_single");
+    }
+}
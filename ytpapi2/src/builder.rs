@@ -0,0 +1,125 @@
+use std::{path::Path, time::Duration};
+
+use reqwest::header::HeaderMap;
+
+use crate::{Result, YoutubeMusicError, YoutubeMusicInstance, YTM_DOMAIN};
+
+/// Configures how a [`YoutubeMusicInstance`] connects to YouTube Music
+#[derive(Debug, Clone)]
+pub struct YoutubeMusicInstanceBuilder {
+    base_url: String,
+    client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+}
+
+impl Default for YoutubeMusicInstanceBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: YTM_DOMAIN.to_owned(),
+            client: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+        }
+    }
+}
+
+impl YoutubeMusicInstanceBuilder {
+    /// URL the requests are sent to instead of `https://music.youtube.com`,
+    /// for a mirror or a mock server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Client used for every request instead of a new one.
+    /// The timeouts and the proxy of the builder aren't applied to it, the `User-Agent`
+    /// header is sent with each request.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Time after which a whole request is given up
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time after which connecting to the server is given up
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Proxy every request goes through, like `http://127.0.0.1:8080` or
+    /// `socks5://127.0.0.1:9050`
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Reads the `Cookie` and `User-Agent` headers from a file like the one copied from
+    /// the network tab of the browser, then connects
+    pub async fn from_header_file(self, path: &Path) -> Result<YoutubeMusicInstance> {
+        let mut headers = HeaderMap::new();
+        for header in tokio::fs::read_to_string(path)
+            .await
+            .map_err(YoutubeMusicError::IoError)?
+            .lines()
+        {
+            if let Some((key, value)) = header.split_once(": ") {
+                headers.insert(
+                    match key.to_lowercase().as_str() {
+                        "cookie" => reqwest::header::COOKIE,
+                        "user-agent" => reqwest::header::USER_AGENT,
+                        _ => {
+                            #[cfg(test)]
+                            println!("Unknown header key: {key}");
+                            continue;
+                        }
+                    },
+                    value.parse().unwrap(),
+                );
+            }
+        }
+        if !headers.contains_key(reqwest::header::COOKIE) {
+            return Err(YoutubeMusicError::InvalidHeaders);
+        }
+        if !headers.contains_key(reqwest::header::USER_AGENT) {
+            headers.insert(
+                reqwest::header::USER_AGENT,
+                "Mozilla/5.0 (X11; Linux x86_64; rv:108.0) Gecko/20100101 Firefox/108.0"
+                    .parse()
+                    .unwrap(),
+            );
+        }
+        self.build(headers).await
+    }
+
+    /// Connects with the given headers, which must contain the cookies of the account
+    pub async fn build(self, headers: HeaderMap) -> Result<YoutubeMusicInstance> {
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut builder =
+                    reqwest::ClientBuilder::default().default_headers(headers.clone());
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    builder = builder.proxy(
+                        reqwest::Proxy::all(proxy).map_err(YoutubeMusicError::RequestError)?,
+                    );
+                }
+                builder.build().map_err(YoutubeMusicError::RequestError)?
+            }
+        };
+        YoutubeMusicInstance::connect(client, self.base_url, headers).await
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Deserializer};
+
+/// Parses a duration like `3:25` or `1:02:03`, or a number of seconds
+pub fn parse_duration(text: &str) -> Option<u32> {
+    let mut parts = text.trim().split(':');
+    let first = parts.next()?;
+    if first.is_empty() || !first.bytes().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let mut seconds = first.parse::<u32>().ok()?;
+    for (i, part) in parts.enumerate() {
+        // Hours are the most a duration has
+        if i >= 2 || part.len() != 2 || !part.bytes().all(|x| x.is_ascii_digit()) {
+            return None;
+        }
+        let part = part.parse::<u32>().ok()?;
+        if part >= 60 {
+            return None;
+        }
+        seconds = seconds.checked_mul(60)?.checked_add(part)?;
+    }
+    Some(seconds)
+}
+
+/// Formats seconds like YouTube does: `3:25` or `1:02:03`
+pub fn format_duration(seconds: u32) -> String {
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Reads a duration stored as seconds, or as text like it was before it was structured
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Seconds(u32),
+        Text(String),
+    }
+    Ok(match Option::<Stored>::deserialize(deserializer)? {
+        Some(Stored::Seconds(seconds)) => Some(seconds),
+        Some(Stored::Text(text)) => parse_duration(&text),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("3:25"), Some(205));
+        assert_eq!(parse_duration("0:07"), Some(7));
+        assert_eq!(parse_duration("1:02:03"), Some(3723));
+        assert_eq!(parse_duration(" 215 "), Some(215));
+        for invalid in [
+            "",
+            "3:5",
+            "3:75",
+            "1:02:03:04",
+            "Song",
+            "-1:00",
+            "2024 Remaster",
+        ] {
+            assert_eq!(parse_duration(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn formats_durations() {
+        for seconds in [0, 7, 205, 3599, 3600, 3723, 86400] {
+            assert_eq!(parse_duration(&format_duration(seconds)), Some(seconds));
+        }
+        assert_eq!(format_duration(205), "3:25");
+        assert_eq!(format_duration(3723), "1:02:03");
+    }
+
+    #[test]
+    fn reads_stored_durations() {
+        #[derive(Deserialize)]
+        struct Video {
+            #[serde(default, deserialize_with = "deserialize")]
+            duration: Option<u32>,
+        }
+        let read = |json: &str| serde_json::from_str::<Video>(json).unwrap().duration;
+        assert_eq!(read(r#"{"duration": 205}"#), Some(205));
+        assert_eq!(read(r#"{"duration": "3:25"}"#), Some(205));
+        assert_eq!(read(r#"{"duration": ""}"#), None);
+        assert_eq!(read(r#"{"duration": null}"#), None);
+        assert_eq!(read("{}"), None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Display;
+
+use serde_json::Value;
+
+/// Longest part of a response kept in an error, responses can be whole HTML pages
+const EXCERPT_LENGTH: usize = 200;
+
+#[derive(Debug)]
+pub enum YoutubeMusicError {
+    RequestError(reqwest::Error),
+    Other(String),
+    NoCookieAttribute,
+    NoSapsidInCookie,
+    InvalidCookie,
+    NeedToLogin,
+    /// The start of the page the key wasn't found in
+    CantFindInnerTubeApiKey(String),
+    /// The start of the page the version wasn't found in
+    CantFindInnerTubeClientVersion(String),
+    CantFindVisitorData(String),
+    SerdeJson(serde_json::Error),
+    IoError(std::io::Error),
+    /// The error object of an InnerTube response
+    YoutubeMusicError(Value),
+    InvalidHeaders,
+    /// HTTP 401 or 403: the cookies were refused
+    Unauthorized(u16),
+    /// HTTP 404
+    NotFound,
+    /// HTTP 429
+    RateLimited,
+    /// HTTP 5xx
+    ServerError(u16),
+    /// Any other unsuccessful HTTP status
+    HttpStatus(u16),
+}
+
+impl YoutubeMusicError {
+    /// The error matching an unsuccessful HTTP status
+    pub(crate) fn from_status(status: reqwest::StatusCode) -> Self {
+        match status.as_u16() {
+            code @ (401 | 403) => Self::Unauthorized(code),
+            404 => Self::NotFound,
+            429 => Self::RateLimited,
+            code @ 500..=599 => Self::ServerError(code),
+            code => Self::HttpStatus(code),
+        }
+    }
+
+    /// Whether doing the same request again later may succeed
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RequestError(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            Self::RateLimited | Self::ServerError(_) => true,
+            Self::YoutubeMusicError(error) => {
+                matches!(error_code(error), Some(429 | 500..=599))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the error comes from the header file or the cookies, which have to be fixed
+    /// by the user
+    pub fn is_auth_error(&self) -> bool {
+        match self {
+            Self::NoCookieAttribute
+            | Self::NoSapsidInCookie
+            | Self::InvalidCookie
+            | Self::NeedToLogin
+            | Self::CantFindInnerTubeApiKey(_)
+            | Self::CantFindInnerTubeClientVersion(_)
+            | Self::CantFindVisitorData(_)
+            | Self::IoError(_)
+            | Self::InvalidHeaders
+            | Self::Unauthorized(_) => true,
+            Self::YoutubeMusicError(error) => matches!(error_code(error), Some(401 | 403)),
+            _ => false,
+        }
+    }
+}
+
+impl Display for YoutubeMusicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RequestError(e) => write!(f, "Request failed: {e}"),
+            Self::Other(e) => write!(f, "{e}"),
+            Self::NoCookieAttribute => write!(f, "No cookie in the headers"),
+            Self::NoSapsidInCookie => write!(f, "No SAPISID in the cookies"),
+            Self::InvalidCookie => write!(f, "The cookies aren't valid text"),
+            Self::NeedToLogin => write!(f, "YouTube Music asks to log in"),
+            Self::CantFindInnerTubeApiKey(page) => {
+                write!(f, "Can't find the InnerTube API key in: {page}")
+            }
+            Self::CantFindInnerTubeClientVersion(page) => {
+                write!(f, "Can't find the InnerTube client version in: {page}")
+            }
+            Self::CantFindVisitorData(page) => write!(f, "Can't find the visitor data in: {page}"),
+            Self::SerdeJson(e) => write!(f, "Invalid JSON response: {e}"),
+            Self::IoError(e) => write!(f, "Can't read the header file: {e}"),
+            Self::YoutubeMusicError(error) => {
+                let message = error
+                    .get("error")
+                    .and_then(|x| x.get("message"))
+                    .and_then(Value::as_str)
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| excerpt(&error.to_string()));
+                match error_code(error) {
+                    Some(code) => write!(f, "YouTube Music error {code}: {message}"),
+                    None => write!(f, "YouTube Music error: {message}"),
+                }
+            }
+            Self::InvalidHeaders => write!(f, "The header file has no cookie"),
+            Self::Unauthorized(code) => write!(f, "HTTP {code}: the cookies were refused"),
+            Self::NotFound => write!(f, "HTTP 404: not found"),
+            Self::RateLimited => write!(f, "HTTP 429: too many requests"),
+            Self::ServerError(code) => write!(f, "HTTP {code}: YouTube Music server error"),
+            Self::HttpStatus(code) => write!(f, "HTTP {code}"),
+        }
+    }
+}
+
+impl std::error::Error for YoutubeMusicError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::RequestError(e) => Some(e),
+            Self::SerdeJson(e) => Some(e),
+            Self::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The code of the error object of an InnerTube response
+fn error_code(error: &Value) -> Option<u16> {
+    error
+        .get("error")
+        .and_then(|x| x.get("code"))
+        .and_then(Value::as_u64)
+        .and_then(|x| u16::try_from(x).ok())
+}
+
+/// The start of a response, on one line
+pub(crate) fn excerpt(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(EXCERPT_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_pages() {
+        let page = format!("<html>\n  <head>{}</head></html>", "é".repeat(1000));
+        let error = YoutubeMusicError::CantFindInnerTubeApiKey(excerpt(&page));
+        let message = error.to_string();
+        assert!(message.starts_with("Can't find the InnerTube API key in: <html> <head>éé"));
+        assert!(message.ends_with('…'));
+        assert!(message.chars().count() < EXCERPT_LENGTH + 50);
+        assert_eq!(excerpt("short  page\n"), "short page");
+    }
+
+    #[test]
+    fn classifies_statuses() {
+        let error =
+            |code| YoutubeMusicError::from_status(reqwest::StatusCode::from_u16(code).unwrap());
+        assert!(error(401).is_auth_error() && !error(401).is_retryable());
+        assert!(error(403).is_auth_error());
+        assert!(error(429).is_retryable() && !error(429).is_auth_error());
+        assert!(error(503).is_retryable());
+        assert!(!error(404).is_retryable() && !error(404).is_auth_error());
+        assert!(matches!(error(400), YoutubeMusicError::HttpStatus(400)));
+    }
+
+    #[test]
+    fn reads_innertube_errors() {
+        let error = YoutubeMusicError::YoutubeMusicError(serde_json::json!({
+            "error": { "code": 401, "message": "Request is missing required authentication credential.", "status": "UNAUTHENTICATED" }
+        }));
+        assert!(error.is_auth_error());
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "YouTube Music error 401: Request is missing required authentication credential."
+        );
+        let error = YoutubeMusicError::YoutubeMusicError(serde_json::json!({
+            "error": { "code": 503, "message": "The service is currently unavailable." }
+        }));
+        assert!(error.is_retryable());
+    }
+}
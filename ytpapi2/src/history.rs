@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+
+/// Characters a client playback nonce is made of
+const NONCE_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// The URL the player reports the plays of a video to, from the player response
+pub(crate) fn get_playback_url(json: &Value) -> Option<String> {
+    json.get("playbackTracking")?
+        .get("videostatsPlaybackUrl")?
+        .get("baseUrl")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// The 16 characters identifying a playback, different for each play of the video
+pub(crate) fn playback_nonce(video_id: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = Sha1::new();
+    hasher.update(format!("{nanos} {video_id}"));
+    hasher
+        .finalize()
+        .iter()
+        .take(16)
+        .map(|x| NONCE_ALPHABET[(x & 63) as usize] as char)
+        .collect()
+}
+
+/// The request reporting a play, with the parameters the web player adds
+pub(crate) fn playback_ping_url(base_url: &str, nonce: &str) -> String {
+    let separator = if base_url.contains('?') { '&' } else { '?' };
+    format!("{base_url}{separator}ver=2&c=WEB_REMIX&cpn={nonce}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::{fixture, from_json, get_video};
+
+    #[test]
+    fn history() {
+        let videos = from_json(&fixture("history"), get_video).unwrap();
+        // The musics played on several days are listed once, at their last play
+        assert_eq!(
+            videos
+                .iter()
+                .map(|x| (x.video_id.as_str(), x.title.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("history001", "Morning Tune"),
+                ("history002", "Coffee Break"),
+                ("history003", "Late Drive"),
+            ]
+        );
+        assert_eq!(videos[1].author, "Second Artist");
+        assert_eq!(videos[2].duration, Some(340));
+    }
+
+    #[test]
+    fn playback_ping() {
+        let url = get_playback_url(&fixture("player")).unwrap();
+        assert!(url.starts_with("https://s.youtube.com/api/stats/playback?"));
+        let nonce = playback_nonce("searchSong1");
+        assert_eq!(nonce.len(), 16);
+        assert!(nonce.bytes().all(|x| NONCE_ALPHABET.contains(&x)));
+        assert_eq!(
+            playback_ping_url("https://example.com/playback?docid=a", "nonce"),
+            "https://example.com/playback?docid=a&ver=2&c=WEB_REMIX&cpn=nonce"
+        );
+        assert_eq!(
+            playback_ping_url("https://example.com/playback", "nonce"),
+            "https://example.com/playback?ver=2&c=WEB_REMIX&cpn=nonce"
+        );
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::YoutubeMusicPlaylistRef;
+use crate::{duration::parse_duration, YoutubeMusicPlaylistRef};
 
 /// Applies recursively the `transformer` function to the given json value
 /// and returns the transformed values.
@@ -46,7 +46,15 @@ pub struct YoutubeMusicVideoRef {
     pub author: String,
     pub album: String,
     pub video_id: String,
-    pub duration: String,
+    /// Duration in seconds, `None` when unknown
+    #[serde(default, deserialize_with = "crate::duration::deserialize")]
+    pub duration: Option<u32>,
+    /// URL of the largest thumbnail, empty when unknown
+    #[serde(default)]
+    pub thumbnail: String,
+    /// Channel id of the artist, empty when unknown
+    #[serde(default)]
+    pub artist_id: String,
 }
 
 impl Display for YoutubeMusicVideoRef {
@@ -153,14 +161,16 @@ pub fn get_video_from_album(value: &Value) -> Option<YoutubeMusicVideoRef> {
         author: String::new(),
         album: String::new(),
         video_id: video_id.to_string(),
-        duration: String::new(),
+        duration: get_duration(value),
+        thumbnail: String::new(),
+        artist_id: String::new(),
     })
 }
 
 /// Tries to extract the text from a json value.
 /// text_clean: Weather to include singleton text.
 /// dot: Weather to use the dotted text instead of the space
-fn get_text(value: &Value, text_clean: bool, dot: bool) -> Option<String> {
+pub(crate) fn get_text(value: &Value, text_clean: bool, dot: bool) -> Option<String> {
     if let Some(e) = value.as_str() {
         Some(e.to_string())
     } else {
@@ -209,6 +219,68 @@ pub fn get_videoid(value: &Value) -> Option<String> {
     }
 }
 
+/// Tries to find the URL of the largest thumbnail in the json
+pub fn get_thumbnail(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(e) => e.iter().find_map(get_thumbnail),
+        Value::Object(e) => e
+            .get("thumbnails")
+            .and_then(Value::as_array)
+            .and_then(|thumbnails| {
+                thumbnails
+                    .iter()
+                    .filter_map(|x| {
+                        Some((
+                            x.get("width").and_then(Value::as_u64).unwrap_or_default(),
+                            x.get("url").and_then(Value::as_str)?,
+                        ))
+                    })
+                    .max_by_key(|(width, _)| *width)
+                    .map(|(_, url)| url.to_string())
+            })
+            .or_else(|| e.values().find_map(get_thumbnail)),
+        _ => None,
+    }
+}
+
+/// Tries to find the duration of a video: in its fixed column for playlists and albums,
+/// in its length text for queues, or as a dotted part of a flex column for search results
+pub(crate) fn get_duration(value: &Value) -> Option<u32> {
+    let column_texts = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| {
+                x.as_object()
+                    .and_then(|x| x.values().next())
+                    .and_then(|x| x.get("text"))
+                    .and_then(|x| get_text(x, false, true))
+            })
+            .collect::<Vec<_>>()
+    };
+    let fixed = column_texts("fixedColumns");
+    let length = value
+        .get("lengthText")
+        .and_then(|x| get_text(x, false, false));
+    // The first flex column is the title, which may look like a duration
+    let flex = column_texts("flexColumns")
+        .into_iter()
+        .skip(1)
+        .flat_map(|text| {
+            text.split('•')
+                .map(|x| x.trim().to_owned())
+                .filter(|x| x.contains(':'))
+                .collect::<Vec<_>>()
+        });
+    fixed
+        .into_iter()
+        .chain(length)
+        .chain(flex)
+        .find_map(|x| parse_duration(&x))
+}
+
 /// Tries to extract a video from a json value.
 /// Quite flexible to reduce odds of API change breaking this.
 pub(crate) fn get_video(value: &Value) -> Option<YoutubeMusicVideoRef> {
@@ -229,6 +301,303 @@ pub(crate) fn get_video(value: &Value) -> Option<YoutubeMusicVideoRef> {
         title: texts.next()?,
         author: texts.next()?,
         album: texts.next().unwrap_or_default(),
-        duration: String::new(),
+        duration: get_duration(value),
+        thumbnail: value
+            .get("thumbnail")
+            .and_then(get_thumbnail)
+            .unwrap_or_default(),
+        artist_id: value
+            .get("flexColumns")
+            .and_then(get_artist_id)
+            .unwrap_or_default(),
     })
 }
+
+/// Tries to extract a video from a carousel item, like the videos on an artist page
+pub(crate) fn get_video_from_carousel(value: &Value) -> Option<YoutubeMusicVideoRef> {
+    let video_id = value
+        .get("navigationEndpoint")
+        .and_then(|x| x.get("watchEndpoint"))
+        .and_then(|x| x.get("videoId"))
+        .and_then(Value::as_str)?;
+    let subtitle = value.get("subtitle");
+    Some(YoutubeMusicVideoRef {
+        title: get_text(value.get("title")?, false, false)?,
+        author: subtitle
+            .and_then(|x| x.get("runs"))
+            .and_then(|x| x.get(0))
+            .and_then(|x| get_text(x, false, false))
+            .unwrap_or_default(),
+        album: String::new(),
+        video_id: video_id.to_string(),
+        duration: None,
+        thumbnail: value
+            .get("thumbnailRenderer")
+            .and_then(get_thumbnail)
+            .unwrap_or_default(),
+        artist_id: subtitle.and_then(get_artist_id).unwrap_or_default(),
+    })
+}
+
+/// Tries to extract a video from a queue item, like the tracks of a radio
+pub(crate) fn get_video_from_queue(value: &Value) -> Option<YoutubeMusicVideoRef> {
+    let video_id = value.get("videoId").and_then(Value::as_str)?;
+    let byline = value.get("longBylineText")?;
+    let runs = byline.get("runs")?.as_array()?;
+    // The artists come before the album and the year, separated by dots
+    let author = runs
+        .iter()
+        .filter_map(|x| x.get("text").and_then(Value::as_str))
+        .take_while(|x| x.trim() != "•")
+        .collect::<String>();
+    let album = runs
+        .iter()
+        .find(|x| {
+            x.get("navigationEndpoint")
+                .and_then(|x| x.get("browseEndpoint"))
+                .and_then(|x| x.get("browseId"))
+                .and_then(Value::as_str)
+                .is_some_and(|x| x.starts_with("MPREb_"))
+        })
+        .and_then(|x| get_text(x, false, false))
+        .unwrap_or_default();
+    Some(YoutubeMusicVideoRef {
+        title: get_text(value.get("title")?, false, false)?,
+        author: author.trim().to_string(),
+        album,
+        video_id: video_id.to_string(),
+        duration: get_duration(value),
+        thumbnail: value
+            .get("thumbnail")
+            .and_then(get_thumbnail)
+            .unwrap_or_default(),
+        artist_id: get_artist_id(byline).unwrap_or_default(),
+    })
+}
+
+/// Tries to find the channel id of the first artist linked in the json
+pub(crate) fn get_artist_id(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(e) => e.iter().find_map(get_artist_id),
+        Value::Object(e) => e
+            .get("browseEndpoint")
+            .and_then(|x| x.get("browseId"))
+            .and_then(Value::as_str)
+            .filter(|x| x.starts_with("UC"))
+            .map(|x| x.to_string())
+            .or_else(|| e.values().find_map(get_artist_id)),
+        _ => None,
+    }
+}
+
+/// Recorded responses, anonymized and trimmed to what the extractors look at
+#[cfg(test)]
+pub(crate) fn fixture(name: &str) -> Value {
+    let path = format!("{}/fixtures/{name}.json", env!("CARGO_MANIFEST_DIR"));
+    serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn continuation(token: &str) -> Continuation {
+        Continuation {
+            continuation: format!("{token}ContinuationToken"),
+            click_tracking_params: format!("{token}ClickTracking"),
+        }
+    }
+
+    #[test]
+    fn home() {
+        let home = fixture("home");
+        let videos = from_json(&home, get_video).unwrap();
+        assert_eq!(
+            videos,
+            vec![YoutubeMusicVideoRef {
+                title: "Morning Song".to_owned(),
+                author: "First Artist".to_owned(),
+                album: "First Album".to_owned(),
+                video_id: "homeVideo01".to_owned(),
+                duration: None,
+                thumbnail: "https://lh3.example.com/home1=w120-h120".to_owned(),
+                artist_id: "UCartist000000000000001".to_owned(),
+            }]
+        );
+        let playlists = from_json(&home, get_playlist).unwrap();
+        assert_eq!(
+            playlists,
+            vec![
+                YoutubeMusicPlaylistRef {
+                    name: "My Supermix".to_owned(),
+                    subtitle: "First Artist, Second Artist and more".to_owned(),
+                    browse_id: "VLRDTMAKfixture0001".to_owned(),
+                },
+                YoutubeMusicPlaylistRef {
+                    name: "Discover Mix".to_owned(),
+                    subtitle: "Third Artist, Fourth Artist and more".to_owned(),
+                    browse_id: "VLRDTMAKfixture0002".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            from_json(&home, get_continuation).unwrap(),
+            vec![continuation("home")]
+        );
+    }
+
+    #[test]
+    fn library() {
+        let library = fixture("library");
+        let playlists = from_json(&library, get_playlist).unwrap();
+        // The "New playlist" button isn't a playlist
+        assert_eq!(
+            playlists
+                .iter()
+                .map(|x| (x.name.as_str(), x.subtitle.as_str(), x.browse_id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Liked music", "Auto playlist", "VLLM"),
+                (
+                    "Road trip",
+                    "Playlist • Someone • 42 songs",
+                    "VLPLfixture0000000001"
+                ),
+            ]
+        );
+        assert!(from_json(&library, get_video).unwrap().is_empty());
+        assert_eq!(
+            from_json(&library, get_continuation).unwrap(),
+            vec![continuation("library")]
+        );
+    }
+
+    #[test]
+    fn playlist() {
+        let playlist = fixture("playlist");
+        let videos = crate::parse_playlist(&playlist).unwrap();
+        // The unavailable video has no id and is skipped
+        assert_eq!(
+            videos,
+            vec![
+                YoutubeMusicVideoRef {
+                    title: "Highway Song".to_owned(),
+                    author: "First Artist".to_owned(),
+                    album: "First Album".to_owned(),
+                    video_id: "playlist001".to_owned(),
+                    duration: Some(205),
+                    thumbnail: "https://lh3.example.com/playlist001=w120-h120".to_owned(),
+                    artist_id: "UCFirstArtist".to_owned(),
+                },
+                YoutubeMusicVideoRef {
+                    title: "Long Night".to_owned(),
+                    author: "Second Artist".to_owned(),
+                    album: "Second Album".to_owned(),
+                    video_id: "playlist002".to_owned(),
+                    duration: Some(3723),
+                    thumbnail: "https://lh3.example.com/playlist002=w120-h120".to_owned(),
+                    artist_id: "UCSecondArtist".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            extract_playlist_info(&playlist),
+            Some(("Road trip".to_owned(), "Someone".to_owned()))
+        );
+        assert_eq!(
+            from_json(&playlist, get_continuation).unwrap(),
+            vec![continuation("playlist")]
+        );
+    }
+
+    #[test]
+    fn album() {
+        let album = fixture("album");
+        // Album tracks have no artist column, so only the album extractor finds them
+        assert!(from_json(&album, get_video).unwrap().is_empty());
+        let tracks = from_json(&album, get_video_from_album).unwrap();
+        assert_eq!(
+            tracks
+                .iter()
+                .map(|x| (x.title.as_str(), x.video_id.as_str(), x.duration))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Opening", "albumTrack1", Some(241)),
+                ("Interlude", "albumTrack2", Some(58)),
+            ]
+        );
+        assert_eq!(
+            extract_playlist_info(&album),
+            Some(("Fixture Album".to_owned(), "Album Artist".to_owned()))
+        );
+        let videos = crate::parse_playlist(&album).unwrap();
+        assert_eq!(videos.len(), 2);
+        for video in videos {
+            assert_eq!(video.album, "Fixture Album");
+            assert_eq!(video.author, "Album Artist");
+        }
+        assert!(from_json(&album, get_continuation).unwrap().is_empty());
+    }
+
+    #[test]
+    fn search() {
+        let search = fixture("search");
+        let videos = from_json(&search, get_video).unwrap();
+        assert_eq!(videos.len(), 1);
+        assert_eq!(videos[0].title, "Dance Song");
+        assert_eq!(videos[0].video_id, "searchSong1");
+        assert_eq!(videos[0].duration, Some(167));
+        assert!(videos[0].author.starts_with("Dancing Artist"));
+        assert_eq!(
+            from_json(&search, get_playlist_search).unwrap(),
+            vec![YoutubeMusicPlaylistRef {
+                name: "Dance Classics".to_owned(),
+                subtitle: "Playlist • Someone • 1.2M views".to_owned(),
+                browse_id: "VLPLsearch000000001".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn radio() {
+        let radio = fixture("next");
+        let videos = from_json(&radio, get_video_from_queue).unwrap();
+        assert_eq!(
+            videos
+                .iter()
+                .map(|x| (x.video_id.as_str(), x.author.as_str(), x.duration))
+                .collect::<Vec<_>>(),
+            vec![
+                ("searchSong1", "Dance Artist", Some(205)),
+                ("radioSong2", "Synth Band", Some(252)),
+                ("radioSong3", "Dance Artist", Some(178)),
+            ]
+        );
+        assert_eq!(videos[1].title, "Night Drive");
+        assert_eq!(videos[1].album, "Neon");
+        assert_eq!(videos[1].artist_id, "UCsynthband00000000001");
+        assert_eq!(
+            videos[1].thumbnail,
+            "https://i.ytimg.com/vi/radioSong2/sddefault.jpg"
+        );
+        // Queue items have no flex columns
+        assert!(from_json(&radio, get_video).unwrap().is_empty());
+    }
+
+    #[test]
+    fn continuation_page() {
+        let page = fixture("continuation");
+        let videos = crate::parse_playlist(&page).unwrap();
+        assert_eq!(
+            videos
+                .iter()
+                .map(|x| (x.title.as_str(), x.author.as_str(), x.duration))
+                .collect::<Vec<_>>(),
+            vec![("Last Exit", "Third Artist", Some(130))]
+        );
+        assert_eq!(
+            from_json(&page, get_continuation).unwrap(),
+            vec![continuation("nextPlaylist")]
+        );
+    }
+}
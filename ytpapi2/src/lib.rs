@@ -3,21 +3,48 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
+use album::parse_album;
+use artist::parse_artist;
+use error::excerpt;
+use history::{get_playback_url, playback_nonce, playback_ping_url};
 use json_extractor::{
     extract_playlist_info, from_json, get_continuation, get_playlist, get_playlist_search,
-    get_video, get_video_from_album, Continuation,
+    get_video, get_video_from_album, get_video_from_queue, Continuation,
 };
-use log::{error, trace, debug};
-use reqwest::header::HeaderMap;
+use like::get_like_status;
+use log::{debug, error, trace};
+use lyrics::{get_lyrics_browse_id, parse_lyrics};
+use playlist_edit::{
+    add_video, fields, get_playlist_entry, get_playlist_item, move_video, playlist_id, remove_video,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use sha1::{Digest, Sha1};
 use string_utils::StringUtils;
 
+mod album;
+mod artist;
+mod builder;
+mod duration;
+mod error;
+mod history;
 mod json_extractor;
+mod like;
+mod lyrics;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+mod playlist_edit;
 mod string_utils;
 
+pub use album::{YoutubeMusicAlbum, YoutubeMusicAlbumTrack};
+pub use artist::{YoutubeMusicArtist, YoutubeMusicArtistRef};
+pub use builder::YoutubeMusicInstanceBuilder;
+pub use duration::{format_duration, parse_duration};
+pub use error::YoutubeMusicError;
 pub use json_extractor::YoutubeMusicVideoRef;
+pub use like::LikeStatus;
+pub use lyrics::{YoutubeMusicLyricLine, YoutubeMusicLyrics};
 
 pub type Result<T> = std::result::Result<T, YoutubeMusicError>;
 
@@ -48,14 +75,16 @@ fn get_headers() -> HeaderMap {
 }
 
 #[test]
+#[ignore = "needs ../headers.txt and network access"]
 fn advanced_like() {
     use tokio::runtime::Runtime;
     Runtime::new().unwrap().block_on(async {
-        let ytm = YoutubeMusicInstance::new(get_headers())
+        let ytm = YoutubeMusicInstance::new(get_headers()).await.unwrap();
+        println!("{}", ytm.compute_sapi_hash());
+        let search = ytm
+            .get_library(&Endpoint::MusicLibraryLanding, 0)
             .await
             .unwrap();
-        println!("{}", ytm.compute_sapi_hash());
-        let search = ytm.get_library(0, &Endpoint::MusicLibraryLanding).await.unwrap();
         assert_eq!(search.is_empty(), false);
         println!("{:?}", search[1]);
         println!("{:?}", ytm.get_playlist(&search[1], 0).await.unwrap());
@@ -63,12 +92,11 @@ fn advanced_like() {
 }
 
 #[test]
+#[ignore = "needs ../headers.txt and network access"]
 fn advanced_test() {
     use tokio::runtime::Runtime;
     Runtime::new().unwrap().block_on(async {
-        let ytm = YoutubeMusicInstance::new(get_headers())
-            .await
-            .unwrap();
+        let ytm = YoutubeMusicInstance::new(get_headers()).await.unwrap();
         let search = ytm.search("j'ai la danse qui va avec", 0).await.unwrap();
         assert_eq!(search.videos.is_empty(), false);
         assert_eq!(search.playlists.is_empty(), false);
@@ -78,12 +106,11 @@ fn advanced_test() {
 }
 
 #[test]
+#[ignore = "needs ../headers.txt and network access"]
 fn home_test() {
     use tokio::runtime::Runtime;
     Runtime::new().unwrap().block_on(async {
-        let ytm = YoutubeMusicInstance::new(get_headers())
-            .await
-            .unwrap();
+        let ytm = YoutubeMusicInstance::new(get_headers()).await.unwrap();
         let search = ytm.get_home(0).await.unwrap();
         println!("{:?}", search.playlists);
         assert_eq!(search.playlists.is_empty(), false);
@@ -104,58 +131,42 @@ pub struct YoutubeMusicInstance {
     innertube_api_key: String,
     client_version: String,
     cookies: String,
+    /// Sent with every request, as a client given to the builder doesn't have it
+    user_agent: Option<HeaderValue>,
+    /// `YTM_DOMAIN` unless the builder was given another one
+    base_url: String,
+    /// Shared by all the requests to reuse the connections
+    client: reqwest::Client,
 }
 
 impl YoutubeMusicInstance {
+    /// Configures the base URL, the HTTP client, the timeouts or the proxy
+    pub fn builder() -> YoutubeMusicInstanceBuilder {
+        YoutubeMusicInstanceBuilder::default()
+    }
+
     pub async fn from_header_file(path: &Path) -> Result<Self> {
-        let mut headers = HeaderMap::new();
-        for header in tokio::fs::read_to_string(path)
-            .await
-            .map_err(YoutubeMusicError::IoError)?
-            .lines()
-        {
-            if let Some((key, value)) = header.split_once(": ") {
-                headers.insert(
-                    match key.to_lowercase().as_str() {
-                        "cookie" => reqwest::header::COOKIE,
-                        "user-agent" => reqwest::header::USER_AGENT,
-                        _ => {
-                            #[cfg(test)]
-                            println!("Unknown header key: {key}");
-                            continue;
-                        }
-                    },
-                    value.parse().unwrap(),
-                );
-            }
-        }
-        if !headers.contains_key(reqwest::header::COOKIE) {
-            return Err(YoutubeMusicError::InvalidHeaders);
-        }
-        if !headers.contains_key(reqwest::header::USER_AGENT) {
-            headers.insert(
-                reqwest::header::USER_AGENT,
-                "Mozilla/5.0 (X11; Linux x86_64; rv:108.0) Gecko/20100101 Firefox/108.0"
-                    .parse()
-                    .unwrap(),
-            );
-        }
-        Self::new(headers).await
+        Self::builder().from_header_file(path).await
     }
 
     pub async fn new(headers: HeaderMap) -> Result<Self> {
+        Self::builder().build(headers).await
+    }
+
+    pub(crate) async fn connect(
+        rest_client: reqwest::Client,
+        base_url: String,
+        headers: HeaderMap,
+    ) -> Result<Self> {
         trace!("Creating new YoutubeMusicInstance");
-        let rest_client = reqwest::ClientBuilder::default()
-            .default_headers(headers.clone())
-            .build()
-            .map_err(YoutubeMusicError::RequestError)?;
         trace!("Fetching YoutubeMusic homepage");
         let response: String = rest_client
-            .get(YTM_DOMAIN)
+            .get(&base_url)
             .headers(headers.clone())
             .send()
             .await
-            .map_err(YoutubeMusicError::RequestError)?
+            .map_err(YoutubeMusicError::RequestError)
+            .and_then(check_status)?
             .text()
             .await
             .map_err(YoutubeMusicError::RequestError)?;
@@ -174,27 +185,36 @@ impl YoutubeMusicInstance {
             .to_str()
             .map_err(|_| YoutubeMusicError::InvalidCookie)?
             .to_string();
+        let user_agent = headers.get(reqwest::header::USER_AGENT).cloned();
         let sapisid = cookies
             .between("SAPISID=", ";")
             .ok_or_else(|| YoutubeMusicError::NoSapsidInCookie)?;
         trace!("Cookies parsed! SAPISID: {}", sapisid);
         let innertube_api_key = response
             .between("INNERTUBE_API_KEY\":\"", "\"")
-            .ok_or_else(|| YoutubeMusicError::CantFindInnerTubeApiKey(response.to_string()))?;
+            .ok_or_else(|| YoutubeMusicError::CantFindInnerTubeApiKey(excerpt(&response)))?;
         trace!("Innertube API key: {}", innertube_api_key);
         let client_version = response
             .between("INNERTUBE_CLIENT_VERSION\":\"", "\"")
-            .ok_or_else(|| {
-                YoutubeMusicError::CantFindInnerTubeClientVersion(response.to_string())
-            })?;
+            .ok_or_else(|| YoutubeMusicError::CantFindInnerTubeClientVersion(excerpt(&response)))?;
         trace!("Innertube client version: {}", client_version);
         Ok(Self {
             sapisid: sapisid.to_string(),
             innertube_api_key: innertube_api_key.to_string(),
             client_version: client_version.to_string(),
             cookies,
+            user_agent,
+            base_url,
+            client: rest_client,
         })
     }
+    fn user_agent_header(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(user_agent) = &self.user_agent {
+            headers.insert(reqwest::header::USER_AGENT, user_agent.clone());
+        }
+        headers
+    }
     fn compute_sapi_hash(&self) -> String {
         let start = SystemTime::now();
         let since_the_epoch = start
@@ -241,26 +261,28 @@ impl YoutubeMusicInstance {
     ) -> Result<String> {
         trace!("Browse continuation {continuation}");
         let url = format!(
-            "https://music.youtube.com/youtubei/v1/browse?ctoken={continuation}&continuation={continuation}&type=next&itct={click_tracking_params}&key={}&prettyPrint=false",
-            self.innertube_api_key
+            "{}/youtubei/v1/browse?ctoken={continuation}&continuation={continuation}&type=next&itct={click_tracking_params}&key={}&prettyPrint=false",
+            self.base_url, self.innertube_api_key
         );
         let body = format!(
             r#"{{"context":{{"client":{{"clientName":"WEB_REMIX","clientVersion":"{}"}}}}}}"#,
             self.client_version
         );
-        reqwest::Client::new()
+        self.client
             .post(&url)
             .header("Content-Type", "application/json")
             .header(
                 "Authorization",
                 format!("SAPISIDHASH {}", self.compute_sapi_hash()),
             )
-            .header("X-Origin", "https://music.youtube.com")
+            .header("X-Origin", YTM_DOMAIN)
             .header("Cookie", &self.cookies)
+            .headers(self.user_agent_header())
             .body(body)
             .send()
             .await
-            .map_err(YoutubeMusicError::RequestError)?
+            .map_err(YoutubeMusicError::RequestError)
+            .and_then(check_status)?
             .text()
             .await
             .map_err(YoutubeMusicError::RequestError)
@@ -270,33 +292,56 @@ impl YoutubeMusicInstance {
         endpoint_route: &str,
         endpoint_key: &str,
         endpoint_param: &str,
+        endpoint_extra: &str,
     ) -> Result<String> {
         trace!("Browse {endpoint_route}");
+        self.post_raw(
+            endpoint_route,
+            &format!(r#""{endpoint_key}":"{endpoint_param}"{endpoint_extra}"#),
+        )
+        .await
+    }
+    /// Sends the InnerTube context and the given fields of the body to the route
+    async fn post_raw(&self, route: &str, fields: &str) -> Result<String> {
         let url = format!(
-            "https://music.youtube.com/youtubei/v1/{endpoint_route}?key={}&prettyPrint=false",
-            self.innertube_api_key
+            "{}/youtubei/v1/{route}?key={}&prettyPrint=false",
+            self.base_url, self.innertube_api_key
         );
         let body = format!(
-            r#"{{"context":{{"client":{{"clientName":"WEB_REMIX","clientVersion":"{}"}}}},"{endpoint_key}":"{endpoint_param}"}}"#,
+            r#"{{"context":{{"client":{{"clientName":"WEB_REMIX","clientVersion":"{}"}}}},{fields}}}"#,
             self.client_version
         );
-        reqwest::Client::new()
+        self.client
             .post(&url)
             .header("Content-Type", "application/json")
             .header(
                 "Authorization",
                 format!("SAPISIDHASH {}", self.compute_sapi_hash()),
             )
-            .header("X-Origin", "https://music.youtube.com")
+            .header("X-Origin", YTM_DOMAIN)
             .header("Cookie", &self.cookies)
+            .headers(self.user_agent_header())
             .body(body)
             .send()
             .await
-            .map_err(YoutubeMusicError::RequestError)?
+            .map_err(YoutubeMusicError::RequestError)
+            .and_then(check_status)?
             .text()
             .await
             .map_err(YoutubeMusicError::RequestError)
     }
+    /// Sends an action changing the account, like rating a song
+    async fn action(&self, route: &str, fields: &str) -> Result<Value> {
+        let json: Value = serde_json::from_str(&self.post_raw(route, fields).await?)
+            .map_err(YoutubeMusicError::SerdeJson)?;
+        debug!("Action response: {json}");
+        if json.get("error").is_some() {
+            error!("Error in action ({route})");
+            error!("{:?}", json);
+            return Err(YoutubeMusicError::YoutubeMusicError(json));
+        }
+        Ok(json)
+    }
     async fn browse(
         &self,
         endpoint: &Endpoint,
@@ -308,10 +353,11 @@ impl YoutubeMusicInstance {
                     &endpoint.get_route(),
                     &endpoint.get_key(),
                     &endpoint.get_param(),
+                    &endpoint.get_extra(),
                 )
                 .await?,
         )
-        .map_err(YoutubeMusicError::SerdeJson)?; 
+        .map_err(YoutubeMusicError::SerdeJson)?;
         debug!("Browse response: {playlist_json}");
         if playlist_json.get("error").is_some() {
             error!("Error in browse ({endpoint:?})");
@@ -330,9 +376,7 @@ impl YoutubeMusicInstance {
         endpoint: &Endpoint,
         mut n_continuations: usize,
     ) -> Result<Vec<YoutubeMusicPlaylistRef>> {
-        let (library_json, mut continuations) = self
-            .browse(endpoint, n_continuations > 0)
-            .await?;
+        let (library_json, mut continuations) = self.browse(endpoint, n_continuations > 0).await?;
         trace!("Fetched library");
         debug!("Library response: {library_json}");
         debug!("Continuations: {continuations:?}");
@@ -347,7 +391,7 @@ impl YoutubeMusicInstance {
             debug!("Library response: {library_json}");
             continuations.extend(new_continuations);
             let new_library = from_json(&library_json, get_playlist)?;
-            trace!("Fetched {} playlists",new_library.len());
+            trace!("Fetched {} playlists", new_library.len());
             debug!("Library response: {library_json}");
             library.extend(new_library);
             if n_continuations == 0 {
@@ -368,8 +412,30 @@ impl YoutubeMusicInstance {
     pub async fn get_playlist_raw(
         &self,
         playlist_id: &str,
-        mut n_continuations: usize,
+        n_continuations: usize,
     ) -> Result<Vec<YoutubeMusicVideoRef>> {
+        self.browse_playlist(playlist_id, n_continuations, parse_playlist)
+            .await
+    }
+    /// The videos of a playlist of the library with the ids of their entries, which tell
+    /// apart the entries of a video that is in the playlist several times
+    pub async fn get_playlist_entries(
+        &self,
+        playlist: &YoutubeMusicPlaylistRef,
+        n_continuations: usize,
+    ) -> Result<Vec<(YoutubeMusicVideoRef, String)>> {
+        self.browse_playlist(&playlist.browse_id, n_continuations, |json| {
+            from_json(json, get_playlist_item)
+        })
+        .await
+    }
+    /// Browses a playlist and its continuations, parsing each page with `parse`
+    async fn browse_playlist<T: std::fmt::Debug>(
+        &self,
+        playlist_id: &str,
+        mut n_continuations: usize,
+        parse: impl Fn(&Value) -> Result<Vec<T>>,
+    ) -> Result<Vec<T>> {
         let (playlist_json, mut continuations) = self
             .browse(
                 &Endpoint::Playlist(playlist_id.to_string()),
@@ -379,7 +445,7 @@ impl YoutubeMusicInstance {
         trace!("Fetched playlist");
         debug!("Playlist response: {playlist_json}");
         debug!("Continuations: {continuations:?}");
-        let mut videos = parse_playlist(&playlist_json)?;
+        let mut videos = parse(&playlist_json)?;
 
         debug!("Videos: {videos:?}");
 
@@ -391,8 +457,8 @@ impl YoutubeMusicInstance {
                 .await?;
             debug!("Playlist response: {playlist_json}");
             continuations.extend(new_continuations);
-            let new_videos = parse_playlist(&playlist_json)?;
-            trace!("Fetched {} videos",new_videos.len());
+            let new_videos = parse(&playlist_json)?;
+            trace!("Fetched {} videos", new_videos.len());
             debug!("Playlist response: {playlist_json}");
             videos.extend(new_videos);
             if n_continuations == 0 {
@@ -437,6 +503,251 @@ impl YoutubeMusicInstance {
         Ok(SearchResults { videos, playlists })
     }
 
+    /// The tracks of an album in order, with its artist, year and cover.
+    /// Unlike [`Self::get_playlist`], the tracks are numbered.
+    pub async fn get_album(&self, browse_id: &str) -> Result<YoutubeMusicAlbum> {
+        let (album_json, _) = self
+            .browse(&Endpoint::Album(browse_id.to_string()), false)
+            .await?;
+        debug!("Album response: {album_json}");
+        let album = parse_album(&album_json)?;
+        debug!("Album: {album:?}");
+        Ok(album)
+    }
+
+    pub async fn get_artist(&self, artist_id: &str) -> Result<YoutubeMusicArtist> {
+        let (artist_json, _) = self
+            .browse(&Endpoint::Artist(artist_id.to_string()), false)
+            .await?;
+        debug!("Artist response: {artist_json}");
+        let artist = parse_artist(&artist_json)?;
+        debug!("Artist: {artist:?}");
+        Ok(artist)
+    }
+
+    /// The lyrics of a video, found through the lyrics tab of its watch page.
+    /// `None` when the video has no lyrics.
+    pub async fn get_lyrics(&self, video_id: &str) -> Result<Option<YoutubeMusicLyrics>> {
+        let (next_json, _) = self
+            .browse(&Endpoint::Next(video_id.to_string()), false)
+            .await?;
+        debug!("Next response: {next_json}");
+        let Some(browse_id) = from_json(&next_json, get_lyrics_browse_id)?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let (lyrics_json, _) = self.browse(&Endpoint::Lyrics(browse_id), false).await?;
+        debug!("Lyrics response: {lyrics_json}");
+        Ok(parse_lyrics(&lyrics_json))
+    }
+
+    /// The tracks of the radio of a video, starting with the video itself
+    pub async fn get_watch_playlist(&self, video_id: &str) -> Result<Vec<YoutubeMusicVideoRef>> {
+        let (radio_json, _) = self
+            .browse(&Endpoint::Radio(video_id.to_string()), false)
+            .await?;
+        debug!("Radio response: {radio_json}");
+        let videos = from_json(&radio_json, get_video_from_queue)?;
+        debug!("Videos: {videos:?}");
+        Ok(videos)
+    }
+
+    /// Likes, dislikes or removes the rating of a song on the account
+    pub async fn rate_song(&self, video_id: &str, status: LikeStatus) -> Result<()> {
+        trace!("Rate {video_id} {status:?}");
+        self.action(
+            status.route(),
+            &format!(r#""target":{{"videoId":"{video_id}"}}"#),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// How the account rated a song, `None` when the watch page doesn't tell
+    pub async fn get_like_status(&self, video_id: &str) -> Result<Option<LikeStatus>> {
+        let (next_json, _) = self
+            .browse(&Endpoint::Next(video_id.to_string()), false)
+            .await?;
+        Ok(from_json(&next_json, |x| get_like_status(x, video_id))?
+            .into_iter()
+            .next())
+    }
+
+    /// The musics played recently on the account, the last played first
+    pub async fn get_history(&self) -> Result<Vec<YoutubeMusicVideoRef>> {
+        let (history_json, _) = self.browse(&Endpoint::History, false).await?;
+        debug!("History response: {history_json}");
+        let videos = from_json(&history_json, get_video)?;
+        debug!("Videos: {videos:?}");
+        Ok(videos)
+    }
+
+    /// Reports a play of a song like the web player does, which adds it to the history of
+    /// the account and to what the recommendations are based on
+    pub async fn add_history_item(&self, video_id: &str) -> Result<()> {
+        let (player_json, _) = self
+            .browse(&Endpoint::Player(video_id.to_string()), false)
+            .await?;
+        debug!("Player response: {player_json}");
+        let url = get_playback_url(&player_json).ok_or_else(|| {
+            YoutubeMusicError::Other(format!("No playback tracking for {video_id}"))
+        })?;
+        trace!("Report a play of {video_id}");
+        self.client
+            .get(playback_ping_url(&url, &playback_nonce(video_id)))
+            .header(
+                "Authorization",
+                format!("SAPISIDHASH {}", self.compute_sapi_hash()),
+            )
+            .header("X-Origin", YTM_DOMAIN)
+            .header("Cookie", &self.cookies)
+            .headers(self.user_agent_header())
+            .send()
+            .await
+            .map_err(YoutubeMusicError::RequestError)
+            .and_then(check_status)?;
+        Ok(())
+    }
+
+    /// The entries of the playlist of the videos, in the same order, browsing the pages
+    /// until all of them are found
+    async fn find_playlist_entries(
+        &self,
+        browse_id: &str,
+        video_ids: &[&str],
+    ) -> Result<Vec<(String, String)>> {
+        let (mut playlist_json, mut continuations) = self
+            .browse(&Endpoint::Playlist(browse_id.to_string()), true)
+            .await?;
+        let mut entries = Vec::new();
+        let mut fetched = Vec::new();
+        loop {
+            entries.extend(from_json(&playlist_json, get_playlist_entry)?);
+            let found = video_ids
+                .iter()
+                .map(|id| entries.iter().find(|(video_id, _)| video_id == id).cloned())
+                .collect::<Option<Vec<_>>>();
+            if let Some(found) = found {
+                return Ok(found);
+            }
+            // A page seen before means the end of the playlist was passed
+            let Some(continuation) = continuations.pop().filter(|x| !fetched.contains(x)) else {
+                return Err(YoutubeMusicError::Other(format!(
+                    "The videos aren't all in the playlist {browse_id}"
+                )));
+            };
+            fetched.push(continuation.clone());
+            trace!("Fetching continuation {continuation:?}");
+            (playlist_json, continuations) = self.browse_continuation(&continuation, true).await?;
+        }
+    }
+
+    /// Creates a private playlist with the videos, returns its browse id
+    pub async fn create_playlist(
+        &self,
+        title: &str,
+        description: &str,
+        video_ids: &[&str],
+    ) -> Result<String> {
+        trace!("Create the playlist {title}");
+        let json = self
+            .action(
+                "playlist/create",
+                &fields(json!({
+                    "title": title,
+                    "description": description,
+                    "privacyStatus": "PRIVATE",
+                    "videoIds": video_ids,
+                })),
+            )
+            .await?;
+        let id = json
+            .get("playlistId")
+            .and_then(Value::as_str)
+            .ok_or_else(|| YoutubeMusicError::Other("No playlist was created".to_string()))?;
+        Ok(format!("VL{id}"))
+    }
+
+    pub async fn delete_playlist(&self, browse_id: &str) -> Result<()> {
+        trace!("Delete the playlist {browse_id}");
+        self.action(
+            "playlist/delete",
+            &fields(json!({ "playlistId": playlist_id(browse_id) })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn edit_playlist(&self, browse_id: &str, actions: Vec<Value>) -> Result<()> {
+        self.action(
+            "browse/edit_playlist",
+            &fields(json!({
+                "playlistId": playlist_id(browse_id),
+                "actions": actions,
+            })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Adds the videos at the end of the playlist, skipping the ones already in it
+    pub async fn add_to_playlist(&self, browse_id: &str, video_ids: &[&str]) -> Result<()> {
+        trace!("Add {} videos to {browse_id}", video_ids.len());
+        self.edit_playlist(browse_id, video_ids.iter().map(|x| add_video(x)).collect())
+            .await
+    }
+
+    /// Removes the first entry of each video from the playlist
+    pub async fn remove_from_playlist(&self, browse_id: &str, video_ids: &[&str]) -> Result<()> {
+        trace!("Remove {} videos from {browse_id}", video_ids.len());
+        let entries = self.find_playlist_entries(browse_id, video_ids).await?;
+        self.edit_playlist(browse_id, entries.iter().map(remove_video).collect())
+            .await
+    }
+
+    /// Removes the entries from the playlist, given by their video ids and their entry ids
+    pub async fn remove_playlist_entries(
+        &self,
+        browse_id: &str,
+        entries: &[(String, String)],
+    ) -> Result<()> {
+        trace!("Remove {} entries from {browse_id}", entries.len());
+        self.edit_playlist(browse_id, entries.iter().map(remove_video).collect())
+            .await
+    }
+
+    /// Moves the entry before another one of the playlist, or to its end when `successor`
+    /// is `None`. Entries are given by their ids.
+    pub async fn move_playlist_entry(
+        &self,
+        browse_id: &str,
+        set_video_id: &str,
+        successor: Option<&str>,
+    ) -> Result<()> {
+        trace!("Move {set_video_id} before {successor:?} in {browse_id}");
+        self.edit_playlist(browse_id, vec![move_video(set_video_id, successor)])
+            .await
+    }
+
+    /// Moves the video before another one of the playlist, or to its end when `before` is `None`
+    pub async fn move_in_playlist(
+        &self,
+        browse_id: &str,
+        video_id: &str,
+        before: Option<&str>,
+    ) -> Result<()> {
+        trace!("Move {video_id} before {before:?} in {browse_id}");
+        let video_ids = [video_id].into_iter().chain(before).collect::<Vec<_>>();
+        let entries = self.find_playlist_entries(browse_id, &video_ids).await?;
+        let successor = entries
+            .get(1)
+            .map(|(_, set_video_id)| set_video_id.as_str());
+        self.edit_playlist(browse_id, vec![move_video(&entries[0].1, successor)])
+            .await
+    }
+
     pub async fn get_home(&self, mut n_continuations: usize) -> Result<SearchResults> {
         let (home_json, mut continuations) = self
             .browse(&Endpoint::MusicHome, n_continuations > 0)
@@ -469,6 +780,23 @@ impl YoutubeMusicInstance {
     }
 }
 
+/// Turns unsuccessful HTTP statuses into errors. InnerTube explains its errors in a JSON
+/// body, which is kept when there is one.
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success()
+        || response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|x| x.to_str().ok())
+            .is_some_and(|x| x.starts_with("application/json"))
+    {
+        return Ok(response);
+    }
+    error!("HTTP {status} from {}", response.url());
+    Err(YoutubeMusicError::from_status(status))
+}
+
 fn parse_playlist(playlist_json: &Value) -> Result<Vec<YoutubeMusicVideoRef>> {
     let mut videos = from_json(playlist_json, get_video)?;
     let info = extract_playlist_info(playlist_json);
@@ -502,6 +830,20 @@ pub enum Endpoint {
     MusicLibraryLanding,
     Playlist(String),
     Search(String),
+    /// The channel id of an artist
+    Artist(String),
+    /// The browse id of an album, starting with `MPREb_`
+    Album(String),
+    /// The watch page of a video, with the queue that follows it and its tabs
+    Next(String),
+    /// The browse id of the lyrics of a video, starting with `MPLYt`
+    Lyrics(String),
+    /// The radio started from a video: the watch page of the video in its radio playlist
+    Radio(String),
+    /// The musics played recently on the account, grouped by day
+    History,
+    /// The streams of a video and the URLs its plays are reported to
+    Player(String),
 }
 
 impl Endpoint {
@@ -512,6 +854,13 @@ impl Endpoint {
             Endpoint::Playlist(_) => "browseId".to_owned(),
             Endpoint::MusicHome => "browseId".to_owned(),
             Endpoint::Search(_) => "query".to_owned(),
+            Endpoint::Artist(_) => "browseId".to_owned(),
+            Endpoint::Album(_) => "browseId".to_owned(),
+            Endpoint::Next(_) => "videoId".to_owned(),
+            Endpoint::Lyrics(_) => "browseId".to_owned(),
+            Endpoint::Radio(_) => "videoId".to_owned(),
+            Endpoint::History => "browseId".to_owned(),
+            Endpoint::Player(_) => "videoId".to_owned(),
         }
     }
     fn get_param(&self) -> String {
@@ -521,6 +870,13 @@ impl Endpoint {
             Endpoint::Playlist(id) => id.to_owned(),
             Endpoint::Search(query) => query.to_owned(),
             Endpoint::MusicHome => "FEmusic_home".to_owned(),
+            Endpoint::Artist(id) => id.to_owned(),
+            Endpoint::Album(id) => id.to_owned(),
+            Endpoint::Next(id) => id.to_owned(),
+            Endpoint::Lyrics(id) => id.to_owned(),
+            Endpoint::Radio(id) => id.to_owned(),
+            Endpoint::History => "FEmusic_history".to_owned(),
+            Endpoint::Player(id) => id.to_owned(),
         }
     }
     fn get_route(&self) -> String {
@@ -530,23 +886,20 @@ impl Endpoint {
             Endpoint::Playlist(_) => "browse".to_owned(),
             Endpoint::Search(_) => "search".to_owned(),
             Endpoint::MusicHome => "browse".to_owned(),
+            Endpoint::Artist(_) => "browse".to_owned(),
+            Endpoint::Album(_) => "browse".to_owned(),
+            Endpoint::Next(_) => "next".to_owned(),
+            Endpoint::Lyrics(_) => "browse".to_owned(),
+            Endpoint::Radio(_) => "next".to_owned(),
+            Endpoint::History => "browse".to_owned(),
+            Endpoint::Player(_) => "player".to_owned(),
+        }
+    }
+    /// The other fields of the request, each starting with a comma
+    fn get_extra(&self) -> String {
+        match self {
+            Endpoint::Radio(id) => format!(r#","playlistId":"RDAMVM{id}","params":"wAEB""#),
+            _ => String::new(),
         }
     }
-}
-
-#[derive(Debug)]
-pub enum YoutubeMusicError {
-    RequestError(reqwest::Error),
-    Other(String),
-    NoCookieAttribute,
-    NoSapsidInCookie,
-    InvalidCookie,
-    NeedToLogin,
-    CantFindInnerTubeApiKey(String),
-    CantFindInnerTubeClientVersion(String),
-    CantFindVisitorData(String),
-    SerdeJson(serde_json::Error),
-    IoError(std::io::Error),
-    YoutubeMusicError(Value),
-    InvalidHeaders,
 }
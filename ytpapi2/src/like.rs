@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How the account rated a song
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LikeStatus {
+    Like,
+    Dislike,
+    /// Neither liked nor disliked
+    Indifferent,
+}
+
+impl LikeStatus {
+    /// The route of the request giving this rating
+    pub(crate) fn route(self) -> &'static str {
+        match self {
+            Self::Like => "like/like",
+            Self::Dislike => "like/dislike",
+            Self::Indifferent => "like/removelike",
+        }
+    }
+
+    fn parse(status: &str) -> Option<Self> {
+        match status {
+            "LIKE" => Some(Self::Like),
+            "DISLIKE" => Some(Self::Dislike),
+            "INDIFFERENT" => Some(Self::Indifferent),
+            _ => None,
+        }
+    }
+}
+
+/// Tries to extract the rating of the video from a like button
+pub(crate) fn get_like_status(value: &Value, video_id: &str) -> Option<LikeStatus> {
+    let button = value.get("likeButtonRenderer")?;
+    let target = button
+        .get("target")
+        .and_then(|x| x.get("videoId"))
+        .and_then(Value::as_str)?;
+    if target != video_id {
+        return None;
+    }
+    LikeStatus::parse(button.get("likeStatus")?.as_str()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::{fixture, from_json};
+
+    #[test]
+    fn like_status() {
+        let json = fixture("next");
+        let status = |video_id: &str| from_json(&json, |x| get_like_status(x, video_id)).unwrap();
+        assert_eq!(status("searchSong1"), vec![LikeStatus::Like]);
+        assert_eq!(status("radioSong2"), vec![LikeStatus::Indifferent]);
+        assert_eq!(status("radioSong3"), vec![LikeStatus::Dislike]);
+        assert!(status("unknown").is_empty());
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::json_extractor::get_text;
+
+/// The lyrics of a track, split in lines
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YoutubeMusicLyrics {
+    pub lines: Vec<YoutubeMusicLyricLine>,
+    /// Where the lyrics come from, like `Source: Musixmatch`. Empty when unknown
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct YoutubeMusicLyricLine {
+    /// Time the line starts at in milliseconds, `None` when the lyrics aren't synced
+    pub start: Option<u32>,
+    /// Empty between two verses
+    pub text: String,
+}
+
+impl YoutubeMusicLyrics {
+    /// Whether the lines come with the time they are sung at
+    pub fn is_synced(&self) -> bool {
+        !self.lines.is_empty() && self.lines.iter().all(|x| x.start.is_some())
+    }
+
+    /// Index of the line sung at `position` milliseconds into the track,
+    /// `None` before the first line or when the lyrics aren't synced
+    pub fn line_at(&self, position: u32) -> Option<usize> {
+        if !self.is_synced() {
+            return None;
+        }
+        self.lines
+            .iter()
+            .rposition(|x| x.start.is_some_and(|start| start <= position))
+    }
+}
+
+/// The browse id of the lyrics tab of a watch page, starting with `MPLYt`
+pub(crate) fn get_lyrics_browse_id(value: &Value) -> Option<String> {
+    let endpoint = value.get("browseEndpoint")?;
+    let browse_id = endpoint.get("browseId").and_then(Value::as_str)?;
+    let page_type = endpoint
+        .get("browseEndpointContextSupportedConfigs")
+        .and_then(|x| x.get("browseEndpointContextMusicConfig"))
+        .and_then(|x| x.get("pageType"))
+        .and_then(Value::as_str);
+    (page_type == Some("MUSIC_PAGE_TYPE_TRACK_LYRICS") || browse_id.starts_with("MPLYt"))
+        .then(|| browse_id.to_string())
+}
+
+/// Finds the first value stored under `key`, anywhere in the json
+fn find<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Array(e) => e.iter().find_map(|x| find(x, key)),
+        Value::Object(e) => e.get(key).or_else(|| e.values().find_map(|x| find(x, key))),
+        _ => None,
+    }
+}
+
+/// Lines with their times, which only some clients are given
+fn parse_synced(json: &Value) -> Option<YoutubeMusicLyrics> {
+    let data = find(json, "timedLyricsModel")?.get("lyricsData")?;
+    let lines = data
+        .get("timedLyricsData")?
+        .as_array()?
+        .iter()
+        .filter_map(|x| {
+            let start = x
+                .get("cueRange")?
+                .get("startTimeMilliseconds")?
+                .as_str()?
+                .parse()
+                .ok()?;
+            // Instrumental parts are shown as a note
+            let text = match x.get("lyricLine")?.as_str()? {
+                "♪" => "",
+                text => text,
+            };
+            Some(YoutubeMusicLyricLine {
+                start: Some(start),
+                text: text.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    Some(YoutubeMusicLyrics {
+        lines,
+        source: data
+            .get("sourceMessage")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+/// Lyrics shown as a block of text
+fn parse_plain(json: &Value) -> Option<YoutubeMusicLyrics> {
+    let shelf = find(json, "musicDescriptionShelfRenderer")?;
+    // The runs are concatenated without `get_text`, which would trim the line breaks
+    let text = shelf
+        .get("description")?
+        .get("runs")?
+        .as_array()?
+        .iter()
+        .filter_map(|x| x.get("text").and_then(Value::as_str))
+        .collect::<String>();
+    Some(YoutubeMusicLyrics {
+        lines: text
+            .lines()
+            .map(|x| YoutubeMusicLyricLine {
+                start: None,
+                text: x.trim().to_string(),
+            })
+            .collect(),
+        source: shelf
+            .get("footer")
+            .and_then(|x| get_text(x, false, false))
+            .unwrap_or_default(),
+    })
+}
+
+/// The lyrics of a lyrics page, `None` when the track has none
+pub(crate) fn parse_lyrics(json: &Value) -> Option<YoutubeMusicLyrics> {
+    parse_synced(json)
+        .or_else(|| parse_plain(json))
+        .filter(|x| x.lines.iter().any(|x| !x.text.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::{fixture, from_json};
+
+    #[test]
+    fn lyrics_tab() {
+        assert_eq!(
+            from_json(&fixture("next"), get_lyrics_browse_id).unwrap(),
+            vec!["MPLYt_fixturelyrics".to_owned()]
+        );
+    }
+
+    #[test]
+    fn plain() {
+        let lyrics = parse_lyrics(&fixture("lyrics")).unwrap();
+        assert!(!lyrics.is_synced());
+        assert_eq!(lyrics.source, "Source: Musixmatch");
+        assert_eq!(
+            lyrics
+                .lines
+                .iter()
+                .map(|x| x.text.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Dancing through the night",
+                "Under the city lights",
+                "",
+                "We never stop",
+                "We never stop",
+            ]
+        );
+        assert_eq!(lyrics.line_at(20_000), None);
+    }
+
+    #[test]
+    fn synced() {
+        let lyrics = parse_lyrics(&fixture("lyrics_synced")).unwrap();
+        assert!(lyrics.is_synced());
+        assert_eq!(lyrics.source, "Source: LyricFind");
+        assert_eq!(lyrics.lines[0].text, "");
+        assert_eq!(lyrics.lines[1].start, Some(12_500));
+        assert_eq!(lyrics.line_at(0), Some(0));
+        assert_eq!(lyrics.line_at(12_499), Some(0));
+        assert_eq!(lyrics.line_at(12_500), Some(1));
+        assert_eq!(lyrics.line_at(60_000), Some(3));
+    }
+
+    #[test]
+    fn no_lyrics() {
+        assert_eq!(parse_lyrics(&fixture("album")), None);
+    }
+}
@@ -0,0 +1,272 @@
+//! A local server answering like InnerTube with the recorded fixtures, so the crate and
+//! the app can be tested without YouTube Music. Any cookie with a `SAPISID` is accepted.
+
+use std::net::SocketAddr;
+
+use log::{error, trace};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+/// Home page the API key and the client version are read from
+const HOME_PAGE: &str = r#"<!DOCTYPE html><html><head><script>ytcfg.set({"INNERTUBE_API_KEY":"mockApiKey","INNERTUBE_CLIENT_VERSION":"1.20240101.01.00"});</script></head><body></body></html>"#;
+
+const HOME: &str = include_str!("../fixtures/home.json");
+const LIBRARY: &str = include_str!("../fixtures/library.json");
+const PLAYLIST: &str = include_str!("../fixtures/playlist.json");
+const ALBUM: &str = include_str!("../fixtures/album.json");
+const SEARCH: &str = include_str!("../fixtures/search.json");
+const ARTIST: &str = include_str!("../fixtures/artist.json");
+const NEXT: &str = include_str!("../fixtures/next.json");
+const LYRICS: &str = include_str!("../fixtures/lyrics.json");
+const HISTORY: &str = include_str!("../fixtures/history.json");
+const PLAYER: &str = include_str!("../fixtures/player.json");
+/// Server the plays are reported to, replaced by the mock server in the responses
+const STATS_URL: &str = "https://s.youtube.com";
+/// Answer to the actions changing the account
+const ACTION: &str = r#"{"responseContext":{"visitorData":"CgtGSVhUVVJFREFUQQ%3D%3D"}}"#;
+/// Answer to the creation of a playlist
+const CREATED: &str = r#"{"responseContext":{"visitorData":"CgtGSVhUVVJFREFUQQ%3D%3D"},"playlistId":"PLcreatedFixture"}"#;
+const CONTINUATION: &str = include_str!("../fixtures/continuation.json");
+
+/// Serves until dropped
+pub struct MockServer {
+    address: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts the server on a free local port
+    pub async fn start() -> std::io::Result<Self> {
+        Self::bind("127.0.0.1:0").await
+    }
+
+    pub async fn bind(address: impl ToSocketAddrs) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address).await?;
+        let address = listener.local_addr()?;
+        let task = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(async move {
+                            if let Err(e) = serve(stream).await {
+                                error!("Mock server: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => error!("Mock server: {e}"),
+                }
+            }
+        });
+        Ok(Self { address, task })
+    }
+
+    /// Base URL to give to [`crate::YoutubeMusicInstanceBuilder::base_url`]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answers the requests of a connection until the client closes it
+async fn serve(stream: TcpStream) -> std::io::Result<()> {
+    let address = stream.local_addr()?;
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if stream.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await? == 0 {
+                return Ok(());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                if key.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().unwrap_or_default();
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        stream.read_exact(&mut body).await?;
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let target = parts.next().unwrap_or_default();
+        trace!("Mock server: {method} {target}");
+        let (status, content_type, content) = respond(method, target, &body);
+        let content = content.replace(STATS_URL, &format!("http://{address}"));
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{content}",
+            content.len()
+        );
+        stream.get_mut().write_all(response.as_bytes()).await?;
+    }
+}
+
+/// Status, content type and body of the response to a request
+fn respond(method: &str, target: &str, body: &[u8]) -> (&'static str, &'static str, &'static str) {
+    const JSON: &str = "application/json; charset=UTF-8";
+    const NOT_FOUND: (&str, &str, &str) = ("404 Not Found", "text/html", "<h1>Not Found</h1>");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match (method, path) {
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", HOME_PAGE),
+        ("POST", "/youtubei/v1/search") => ("200 OK", JSON, SEARCH),
+        ("POST", "/youtubei/v1/next") => ("200 OK", JSON, NEXT),
+        ("POST", "/youtubei/v1/player") => ("200 OK", JSON, PLAYER),
+        ("GET", "/api/stats/playback") => ("204 No Content", "text/plain", ""),
+        ("POST", path) if path.starts_with("/youtubei/v1/like/") => ("200 OK", JSON, ACTION),
+        ("POST", "/youtubei/v1/playlist/create") => ("200 OK", JSON, CREATED),
+        ("POST", "/youtubei/v1/playlist/delete" | "/youtubei/v1/browse/edit_playlist") => {
+            ("200 OK", JSON, ACTION)
+        }
+        ("POST", "/youtubei/v1/browse") if query.contains("ctoken=") => {
+            ("200 OK", JSON, CONTINUATION)
+        }
+        ("POST", "/youtubei/v1/browse") => {
+            let browse_id = serde_json::from_slice::<Value>(body)
+                .ok()
+                .and_then(|x| x.get("browseId")?.as_str().map(str::to_owned))
+                .unwrap_or_default();
+            match browse_id.as_str() {
+                "FEmusic_home" => ("200 OK", JSON, HOME),
+                "FEmusic_history" => ("200 OK", JSON, HISTORY),
+                "FEmusic_liked_playlists" | "FEmusic_library_landing" => ("200 OK", JSON, LIBRARY),
+                id if id.starts_with("MPREb_") => ("200 OK", JSON, ALBUM),
+                id if id.starts_with("VL") => ("200 OK", JSON, PLAYLIST),
+                id if id.starts_with("UC") => ("200 OK", JSON, ARTIST),
+                id if id.starts_with("MPLYt") => ("200 OK", JSON, LYRICS),
+                _ => NOT_FOUND,
+            }
+        }
+        _ => NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{HeaderMap, COOKIE};
+
+    use super::*;
+    use crate::{Endpoint, LikeStatus, YoutubeMusicError, YoutubeMusicInstance};
+
+    async fn connect(server: &MockServer) -> YoutubeMusicInstance {
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, "SAPISID=mock; HSID=mock".parse().unwrap());
+        YoutubeMusicInstance::builder()
+            .base_url(server.url())
+            .timeout(std::time::Duration::from_secs(5))
+            .build(headers)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn browses_the_fixtures() {
+        let server = MockServer::start().await.unwrap();
+        let api = connect(&server).await;
+
+        let home = api.get_home(0).await.unwrap();
+        assert_eq!(home.videos.len(), 1);
+        assert_eq!(home.playlists.len(), 2);
+
+        let library = api
+            .get_library(&Endpoint::MusicLikedPlaylists, 0)
+            .await
+            .unwrap();
+        assert_eq!(library[1].name, "Road trip");
+
+        // The first page and one continuation
+        let videos = api.get_playlist(&library[1], 2).await.unwrap();
+        assert_eq!(
+            videos.iter().map(|x| x.title.as_str()).collect::<Vec<_>>(),
+            vec!["Highway Song", "Long Night", "Last Exit"]
+        );
+
+        let entries = api.get_playlist_entries(&library[1], 2).await.unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].1, "setplaylist003");
+
+        let album = api.get_playlist_raw("MPREb_fixture", 0).await.unwrap();
+        assert!(album.iter().all(|x| x.author == "Album Artist"));
+
+        let search = api.search("dance", 0).await.unwrap();
+        assert_eq!(search.videos[0].video_id, "searchSong1");
+        assert_eq!(search.playlists[0].name, "Dance Classics");
+
+        let artist = api.get_artist(&search.videos[0].artist_id).await.unwrap();
+        assert_eq!(artist.top_songs.len(), 2);
+        let album = api.get_album(&artist.albums[0].browse_id).await.unwrap();
+        assert_eq!(album.title, "Fixture Album");
+        assert_eq!(album.year, Some(2019));
+        assert_eq!(album.tracks.len(), 2);
+
+        let lyrics = api.get_lyrics(&search.videos[0].video_id).await.unwrap();
+        assert_eq!(lyrics.unwrap().lines[0].text, "Dancing through the night");
+
+        let radio = api.get_watch_playlist("searchSong1").await.unwrap();
+        assert_eq!(radio.len(), 3);
+
+        api.rate_song("radioSong2", LikeStatus::Like).await.unwrap();
+        assert_eq!(
+            api.get_like_status("radioSong3").await.unwrap(),
+            Some(LikeStatus::Dislike)
+        );
+
+        let created = api
+            .create_playlist("New playlist", "", &["radioSong1"])
+            .await
+            .unwrap();
+        assert_eq!(created, "VLPLcreatedFixture");
+        api.add_to_playlist(&created, &["radioSong2", "radioSong3"])
+            .await
+            .unwrap();
+        // The last entry is only found in the continuation
+        api.remove_from_playlist(&library[1].browse_id, &["playlist003"])
+            .await
+            .unwrap();
+        api.move_in_playlist(&library[1].browse_id, "playlist002", Some("playlist001"))
+            .await
+            .unwrap();
+        api.remove_playlist_entries(
+            &library[1].browse_id,
+            &[(entries[0].0.video_id.clone(), entries[0].1.clone())],
+        )
+        .await
+        .unwrap();
+        api.move_playlist_entry(&library[1].browse_id, &entries[2].1, None)
+            .await
+            .unwrap();
+        let error = api
+            .move_in_playlist(&library[1].browse_id, "notInThePlaylist", None)
+            .await;
+        assert!(error.is_err());
+        api.delete_playlist(&created).await.unwrap();
+
+        let history = api.get_history().await.unwrap();
+        assert_eq!(history[0].title, "Morning Tune");
+        api.add_history_item("searchSong1").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_pages() {
+        let server = MockServer::start().await.unwrap();
+        let api = connect(&server).await;
+        let error = api.get_playlist_raw("unknown", 0).await.unwrap_err();
+        assert!(matches!(error, YoutubeMusicError::NotFound));
+        assert!(!error.is_retryable());
+    }
+}
@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+
+use crate::json_extractor::{get_video, YoutubeMusicVideoRef};
+
+/// Tries to extract the id of a video of a playlist and the id of its entry, which tells
+/// the entries apart when the video is in the playlist several times
+pub(crate) fn get_playlist_entry(value: &Value) -> Option<(String, String)> {
+    let data = value.get("playlistItemData")?;
+    Some((
+        data.get("videoId")?.as_str()?.to_string(),
+        data.get("playlistSetVideoId")?.as_str()?.to_string(),
+    ))
+}
+
+/// Tries to extract a video of a playlist with the id of its entry
+pub(crate) fn get_playlist_item(value: &Value) -> Option<(YoutubeMusicVideoRef, String)> {
+    let (_, set_video_id) = get_playlist_entry(value)?;
+    Some((get_video(value)?, set_video_id))
+}
+
+/// The playlist id the edition endpoints expect, which is the browse id without `VL`
+pub(crate) fn playlist_id(browse_id: &str) -> &str {
+    browse_id.strip_prefix("VL").unwrap_or(browse_id)
+}
+
+/// The fields of a JSON object, to send them after the context
+pub(crate) fn fields(object: Value) -> String {
+    let object = object.to_string();
+    object[1..object.len() - 1].to_owned()
+}
+
+pub(crate) fn add_video(video_id: &str) -> Value {
+    json!({
+        "action": "ACTION_ADD_VIDEO",
+        "addedVideoId": video_id,
+        "dedupeOption": "DEDUPE_OPTION_SKIP",
+    })
+}
+
+pub(crate) fn remove_video((video_id, set_video_id): &(String, String)) -> Value {
+    json!({
+        "action": "ACTION_REMOVE_VIDEO",
+        "removedVideoId": video_id,
+        "setVideoId": set_video_id,
+    })
+}
+
+/// Moves an entry before another one, or to the end of the playlist
+pub(crate) fn move_video(set_video_id: &str, successor: Option<&str>) -> Value {
+    match successor {
+        Some(successor) => json!({
+            "action": "ACTION_MOVE_VIDEO_BEFORE",
+            "setVideoId": set_video_id,
+            "movedSetVideoIdSuccessor": successor,
+        }),
+        None => json!({
+            "action": "ACTION_MOVE_VIDEO_BEFORE",
+            "setVideoId": set_video_id,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_extractor::{fixture, from_json};
+
+    #[test]
+    fn entries() {
+        let json = fixture("playlist");
+        assert_eq!(
+            from_json(&json, get_playlist_entry).unwrap(),
+            vec![
+                ("playlist001".to_owned(), "setplaylist001".to_owned()),
+                ("playlist002".to_owned(), "setplaylist002".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn items() {
+        let json = fixture("playlist");
+        let items = from_json(&json, get_playlist_item).unwrap();
+        assert_eq!(
+            items
+                .iter()
+                .map(|(video, set_video_id)| (video.title.as_str(), set_video_id.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Highway Song", "setplaylist001"),
+                ("Long Night", "setplaylist002")
+            ]
+        );
+    }
+
+    #[test]
+    fn bodies() {
+        assert_eq!(playlist_id("VLPLfixture"), "PLfixture");
+        assert_eq!(playlist_id("PLfixture"), "PLfixture");
+        assert_eq!(
+            fields(json!({ "playlistId": "PL\"quoted" })),
+            r#""playlistId":"PL\"quoted""#
+        );
+        assert_eq!(
+            move_video("set1", None),
+            json!({ "action": "ACTION_MOVE_VIDEO_BEFORE", "setVideoId": "set1" })
+        );
+    }
+}